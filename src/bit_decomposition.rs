@@ -1,111 +1,131 @@
 pub mod bit_decomposition {
     use crate::computing_party::computing_party::ComputingParty;
-    use num::abs;
-    use num::integer::*;
-    use crate::constants::constants::BINARY_PRIME;
-    use crate::multiplication::multiplication::{multiplication_byte, batch_multiplication_byte};
-    use crate::utils::utils::increment_current_share_index;
-    use std::sync::{Arc, Mutex};
-    use threadpool::ThreadPool;
-    use std::collections::HashMap;
-    use std::cmp::min;
-    use std::num::Wrapping;
+    use crate::multiplication::multiplication::batch_beaver_multiply;
+    use crate::ring::ring::{AdditiveShare, BigZp, Z2, Zp};
+    use crate::utils::utils::{to_bits, biguint_to_bits};
+    use crate::error::error::MpcError;
 
-    pub fn bit_decomposition(input: u64, ctx: &mut ComputingParty) -> Vec<u8> {
+    pub fn bit_decomposition(input: &AdditiveShare<Zp>, ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<Z2>>, MpcError> {
+        Ok(batch_bit_decomposition(std::slice::from_ref(input), ctx)?.remove(0))
+    }
+
+    /* LSB-first binary shares of additive shares modulo ctx.dt_training.prime */
+    pub fn batch_bit_decomposition(inputs: &[AdditiveShare<Zp>], ctx: &mut ComputingParty) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
         ctx.thread_hierarchy.push("bit_decomposition".to_string());
-        let mut input_shares = Vec::new();
+        let prime = ctx.dt_training.prime;
+        let bit_length = (64 - prime.leading_zeros()) as usize;
+        let own_bits: Vec<Vec<u8>> = inputs.iter().map(|x| to_bits(x.value().0 % prime, bit_length)).collect();
+        let result = modular_bit_decomposition(&own_bits, &to_bits(prime, bit_length + 1), ctx)?;
+        ctx.thread_hierarchy.pop();
+        Ok(result)
+    }
+
+    /* LSB-first binary shares of additive shares modulo ctx.dt_training.big_int_prime, bit_length bits each */
+    pub fn batch_bit_decomposition_bigint(inputs: &[AdditiveShare<BigZp>], ctx: &mut ComputingParty) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
+        ctx.thread_hierarchy.push("bit_decomposition_bigint".to_string());
+        let prime = ctx.dt_training.big_int_prime.clone();
         let bit_length = ctx.dt_training.bit_length as usize;
-        let mut d_shares = vec![0u8; bit_length];
-        let mut c_shares = vec![0u8; bit_length];
-        let mut y_shares = vec![0u8; bit_length];
-        let mut x_shares = vec![0u8; bit_length];
-        let binary_str = format!("{:b}", input);
-        let input_binary_str_vec: Vec<&str> = binary_str.split("").collect();
-        let mut temp: Vec<u8> = Vec::new();
-        for item in binary_str.chars() {
-            temp.push(item as u8);
-        }
-        let mut temp0 = vec![0u8; bit_length];
-        let diff = abs(bit_length as isize - temp.len() as isize);
-        for i in 0..diff {
-            temp.push(0);
+        if bit_length < prime.bits() {
+            return Err(MpcError::Config { key: "bit_length".to_string(), problem: format!("{} does not cover big_int_prime", bit_length) });
         }
-        // hard-coded for two-party
+        let own_bits: Vec<Vec<u8>> = inputs.iter().map(|x| biguint_to_bits(&(x.value() % &prime), bit_length)).collect();
+        let result = modular_bit_decomposition(&own_bits, &biguint_to_bits(&prime, bit_length + 1), ctx)?;
+        ctx.thread_hierarchy.pop();
+        Ok(result)
+    }
 
-        for i in 0..2 {
-            let mut temp = temp.clone();
-            let mut temp0 = temp0.clone();
-            if i == ctx.party_id {
-                input_shares.push(temp);
-            } else {
-                input_shares.push(temp0);
-            }
+    // Each party feeds the bits of its own share. Party 0's bits form the first addend and party 1's the
+    // second, so the XOR of the addends is already held locally. The ripple-carry sum may exceed the
+    // modulus once, which is corrected by subtracting it and keeping whichever of the two is in range.
+    fn modular_bit_decomposition(own_bits: &[Vec<u8>], modulus_bits: &[u8], ctx: &mut ComputingParty) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
+        let count = own_bits.len();
+        if count == 0 {
+            return Ok(Vec::new());
         }
-        //initY in Java Lynx
-        for i in 0..bit_length {
-            let y = input_shares[0][i] + input_shares[1][i];
-            y_shares[i] = mod_floor(y, BINARY_PRIME as u8);
-        }
-        x_shares[0] = y_shares[0];
+        let bit_length = own_bits[0].len();
+        let asymmetric_bit = ctx.asymmetric_bit;
+        let party_id = ctx.party_id;
+        let zero = AdditiveShare::zero(&Z2);
+        let addend = |holder: u8| -> Vec<Vec<AdditiveShare<Z2>>> {
+            own_bits.iter().map(|bits| bits.iter().map(|bit| AdditiveShare::input(&Z2, bit, party_id == holder)).collect()).collect()
+        };
+        let (first, second) = (addend(0), addend(1));
+        let propagate: Vec<Vec<AdditiveShare<Z2>>> = first.iter().zip(second.iter())
+            .map(|(x, y)| x.iter().zip(y.iter()).map(|(x, y)| *x ^ *y).collect())
+            .collect();
 
-        //bit_multiplication in Java Lynx
-        let first_c_share = multiplication_byte(input_shares[0][0], input_shares[1][0], ctx);
-        increment_current_share_index(Arc::clone(&ctx.dt_shares.current_binary_index));
-        c_shares[0] = mod_floor(first_c_share, BINARY_PRIME as u8);
+        //generate (AND) bits of the two addends
+        let x_list: Vec<AdditiveShare<Z2>> = first.into_iter().flatten().collect();
+        let y_list: Vec<AdditiveShare<Z2>> = second.into_iter().flatten().collect();
+        ctx.thread_hierarchy.push("compute_generate".to_string());
+        let generate = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
 
-        //computeDShares in Java Lynx
-        let thread_pool = ThreadPool::new(ctx.thread_count);
-        let mut i = 0;
-        let mut output_map = Arc::new(Mutex::new(HashMap::new()));
-        let mut batch_count = 0;
-        ctx.thread_hierarchy.push("compute_d_shares".to_string());
-        while i < bit_length {
-            let mut output_map = Arc::clone(&output_map);
-            let to_index = min(i + ctx.batch_size, bit_length);
-            let mut ctx_copied = ctx.clone();
-            ctx_copied.thread_hierarchy.push(format!("{}",batch_count));
-            let mut input_shares = input_shares.clone();
-            thread_pool.execute(move || {
-                let mut batch_mul_result = batch_multiplication_byte(&input_shares[0][i..to_index].to_vec(), &input_shares[1][i..to_index].to_vec(), &mut ctx_copied);
-                let mut output_map = output_map.lock().unwrap();
-                (*output_map).insert(batch_count, batch_mul_result);
-            });
-            i = to_index;
-            batch_count += 1;
+        //carry chain: c_i = g_i xor (p_i and c_{i-1})
+        let mut carries = vec![vec![zero; bit_length]; count];
+        for v in 0..count {
+            carries[v][0] = generate[v * bit_length];
+        }
+        ctx.thread_hierarchy.push("compute_carry".to_string());
+        for i in 1..bit_length {
+            let current: Vec<AdditiveShare<Z2>> = propagate.iter().map(|bits| bits[i]).collect();
+            let previous: Vec<AdditiveShare<Z2>> = carries.iter().map(|c| c[i - 1]).collect();
+            ctx.thread_hierarchy.push(format!("{}", i));
+            let products = batch_beaver_multiply(&Z2, &current, &previous, ctx)?;
+            ctx.thread_hierarchy.pop();
+            for v in 0..count {
+                carries[v][i] = generate[v * bit_length + i] ^ products[v];
+            }
         }
-        thread_pool.join();
         ctx.thread_hierarchy.pop();
 
-        let output_map = &(*(output_map.lock().unwrap()));
-        let mut global_index = 0;
-        for i in 0..batch_count {
-            let batch_result = output_map.get(&i).unwrap();
-            for item in batch_result.iter() {
-                d_shares[global_index] = mod_floor(item + ctx.asymmetric_bit, BINARY_PRIME as u8);
-                global_index += 1;
+        let sums: Vec<Vec<AdditiveShare<Z2>>> = (0..count).map(|v| {
+            let mut sum = Vec::new();
+            for i in 0..bit_length {
+                sum.push(if i == 0 { propagate[v][0] } else { propagate[v][i] ^ carries[v][i - 1] });
             }
-        }
-        let mut e_shares = vec![0u8; bit_length];
+            sum.push(carries[v][bit_length - 1]);
+            sum
+        }).collect();
 
-        ctx.thread_hierarchy.push("compute_variables".to_string());
-        for i in 1..bit_length {
-            //computeVariables
-            ctx.thread_hierarchy.push("compute_e".to_string());
-            let e_result = (Wrapping(multiplication_byte(y_shares[i], c_shares[i - 1], ctx)) + Wrapping(ctx.asymmetric_bit)).0;
-            ctx.thread_hierarchy.pop();
-            e_shares[i] = mod_floor(e_result, BINARY_PRIME as u8);
-            let x_result = (Wrapping(y_shares[i]) + Wrapping(c_shares[i - 1])).0;
-            x_shares[i] = mod_floor(x_result, BINARY_PRIME as u8);
-            ctx.thread_hierarchy.push("compute_c".to_string());
-            let c_result = (Wrapping(multiplication_byte(e_shares[i], d_shares[i], ctx)) + Wrapping(ctx.asymmetric_bit)).0;
+        //subtract the public modulus, tracking the borrow
+        let mut differences = vec![vec![zero; bit_length + 1]; count];
+        let mut borrows = vec![zero; count];
+        ctx.thread_hierarchy.push("compute_borrow".to_string());
+        for i in 0..bit_length + 1 {
+            let modulus_bit = modulus_bits[i];
+            for v in 0..count {
+                differences[v][i] = sums[v][i] ^ borrows[v] ^ AdditiveShare::public(&Z2, &modulus_bit, asymmetric_bit);
+            }
+            let (x_list, y_list): (Vec<AdditiveShare<Z2>>, Vec<AdditiveShare<Z2>>) = if modulus_bit == 1 {
+                (sums.iter().map(|s| s[i]).collect(), borrows.iter().map(|b| b.complement(asymmetric_bit)).collect())
+            } else {
+                (sums.iter().map(|s| s[i].complement(asymmetric_bit)).collect(), borrows.clone())
+            };
+            ctx.thread_hierarchy.push(format!("{}", i));
+            let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
             ctx.thread_hierarchy.pop();
-            c_shares[i] = mod_floor(c_result, BINARY_PRIME as u8);
+            for v in 0..count {
+                borrows[v] = if modulus_bit == 1 { products[v].complement(asymmetric_bit) } else { products[v] };
+            }
         }
-        // pop computeVariables
         ctx.thread_hierarchy.pop();
 
-        // pop bit_decomposition
+        //a final borrow means the sum was already below the modulus
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for v in 0..count {
+            for i in 0..bit_length {
+                x_list.push(borrows[v]);
+                y_list.push(sums[v][i] ^ differences[v][i]);
+            }
+        }
+        ctx.thread_hierarchy.push("select_result".to_string());
+        let selected = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
         ctx.thread_hierarchy.pop();
-        x_shares
+
+        Ok((0..count).map(|v| {
+            (0..bit_length).map(|i| differences[v][i] ^ selected[v * bit_length + i]).collect()
+        }).collect())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,126 @@
+pub mod budget {
+    use crate::computing_party::computing_party::ComputingParty;
+    use crate::decision_tree::decision_tree::DecisionTreeShares;
+    use crate::error::error::{MpcError, Shortfall};
+    use crate::random_forest::random_forest::MaxFeatures;
+    use crate::settings::settings::ShareCountConfig;
+    use crate::share_source::share_source::ShareCounts;
+    use std::cmp::min;
+
+    // How much correlated randomness training one tree consumes. Every protocol draws a fixed number
+    // of triples per call, independent of the data's values, so the totals follow from the shape
+    // alone: n instances, A attributes of V values each, C classes, trees of depth D, and the bit
+    // lengths of prime (Lp) and of the BigUint decompositions (bit_length, Lb).
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct TripleBudget {
+        pub instance_count: usize,
+        pub attribute_count: usize,
+        pub attr_value_count: usize,
+        pub class_value_count: usize,
+        pub max_depth: usize,
+        pub feature_count: usize,
+        pub prime: u64,
+        pub bit_length: usize,
+    }
+
+    /* the options describing the dataset, which the TI needs in place of the data itself */
+    pub const SHAPE_KEYS: [&str; 4] = ["instance_count", "attribute_count", "attr_value_count", "class_value_count"];
+
+    impl TripleBudget {
+        /* None unless the shape options describe the dataset */
+        pub fn for_shape(shape: &ShareCountConfig, max_depth: usize, max_features: &MaxFeatures, prime: u64, bit_length: usize) -> Option<TripleBudget> {
+            Some(TripleBudget {
+                instance_count: shape.instance_count?,
+                attribute_count: shape.attribute_count?,
+                attr_value_count: shape.attr_value_count?,
+                class_value_count: shape.class_value_count?,
+                max_depth,
+                feature_count: max_features.feature_count(shape.attribute_count?),
+                prime,
+                bit_length,
+            })
+        }
+
+        /* the party's loaded data and options */
+        pub fn for_party(ctx: &ComputingParty) -> TripleBudget {
+            TripleBudget {
+                instance_count: ctx.dt_data.instance_count,
+                attribute_count: ctx.dt_data.attribute_count,
+                attr_value_count: ctx.dt_data.attr_value_count,
+                class_value_count: ctx.dt_data.class_value_count,
+                max_depth: ctx.dt_training.max_depth,
+                feature_count: ctx.max_features.feature_count(ctx.dt_data.attribute_count),
+                prime: ctx.dt_training.prime,
+                bit_length: ctx.dt_training.bit_length as usize,
+            }
+        }
+
+        // random_forest::train converts the one-hot columns into both fields, then decision_tree::train
+        // grows the complete V-ary tree of depth D: every node finds its majority class and checks the
+        // stopping condition, every node above depth D also scores the attributes and splits.
+        pub fn per_tree(&self) -> ShareCounts {
+            let (n, a, v, c) = (self.instance_count, self.attribute_count, self.attr_value_count, self.class_value_count);
+            let depth = min(min(self.max_depth, self.feature_count), a);
+            let prime_bits = (64 - self.prime.leading_zeros()) as usize;
+            let internal_count: usize = (0..depth).map(|d| v.pow(d as u32)).sum();
+            let node_count = internal_count + v.pow(depth as u32);
+
+            let columns = (a * v + c) * n;
+            let class_values = if depth > 0 { a * v * c * n } else { 0 };
+
+            let node_additive = n + c * n;
+            let node_binary = bit_decomposition(c + 1, prime_bits) + arg_max(c, prime_bits) + comparison(2, prime_bits) + 2;
+            let split_bigint = n + a + a * v * c * n + a * v * c + 2 * a * v.saturating_sub(1) + a * v + a * (v + 1) + a + a * a.saturating_sub(1);
+            let split_binary = bit_decomposition(a * a.saturating_sub(1) / 2, self.bit_length) + arg_max_by_comparisons(a) + v * a * n + v * n + a;
+
+            ShareCounts {
+                additive: columns + node_count * node_additive,
+                additive_z64: 0,
+                additive_bigint: columns + class_values + internal_count * split_bigint,
+                /* every node but the root also picks between its own and its parent's class */
+                binary: node_count * node_binary + (node_count - 1) * c + internal_count * split_binary,
+                equality: 0,
+            }
+        }
+
+        /* an Exhausted error naming every pool of shares that cannot last a tree */
+        pub fn check(&self, shares: &DecisionTreeShares) -> Result<(), MpcError> {
+            let needed = self.per_tree();
+            let pools = [
+                ("additive triples", "add_shares_per_tree", shares.additive_triples.len(), needed.additive),
+                ("triples modulo 2^64", "add_shares_z64_per_tree", shares.additive_z64_triples.len(), needed.additive_z64),
+                ("BigUint triples", "add_shares_bigint_per_tree", shares.additive_bigint_triples.len(), needed.additive_bigint),
+                ("binary triples", "binary_shares_per_tree", shares.binary_triples.len(), needed.binary),
+                ("equality shares", "equality_shares_per_tree", shares.equality_shares.len(), needed.equality),
+            ];
+            let short: Vec<Shortfall> = pools.iter()
+                .filter(|(_, _, available, needed)| available < needed)
+                .map(|(pool, key, available, needed)| Shortfall { pool, key, available: *available, needed: Some(*needed) })
+                .collect();
+            if short.is_empty() {
+                Ok(())
+            } else {
+                Err(MpcError::Exhausted(short))
+            }
+        }
+    }
+
+    /* modular_bit_decomposition: the generate bits, the carry chain, the borrow chain and the selection */
+    fn bit_decomposition(count: usize, bit_length: usize) -> usize {
+        4 * count * bit_length
+    }
+
+    /* batch_comparison: the D shares, the suffix products of the E shares and the c shares */
+    fn comparison(count: usize, bit_length: usize) -> usize {
+        count * (bit_length + bit_length.saturating_sub(2) + bit_length - 1)
+    }
+
+    /* each of the k rows of k - 1 comparisons is multiplied out */
+    fn arg_max_by_comparisons(k: usize) -> usize {
+        k * k.saturating_sub(2)
+    }
+
+    fn arg_max(k: usize, bit_length: usize) -> usize {
+        comparison(k * k.saturating_sub(1) / 2, bit_length) + arg_max_by_comparisons(k)
+    }
+}
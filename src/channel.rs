@@ -0,0 +1,136 @@
+pub mod channel {
+    use crate::error::error::MpcError;
+    use crate::message::message::{MessageError, MessageManager, RFMessage, search_pop_message};
+    use crate::wire::wire::{decode_message, encode_message, read_message, write_message};
+    use std::io::BufReader;
+    use std::net::TcpStream;
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    // A bidirectional link to the other computing party. Messages are tagged with the sender's
+    // thread_hierarchy, so any number of protocol threads can share one channel as long as each
+    // receives only the tags it sent.
+    pub trait Channel: Send + Sync {
+        fn send(&self, message: &RFMessage) -> Result<(), MpcError>;
+
+        /* blocks until the other party's message tagged message_id has arrived, or the channel's timeout */
+        fn receive(&self, message_id: &str) -> Result<RFMessage, MpcError>;
+
+        /* party 1 sends first and party 0 receives first, so a tag is never in flight twice */
+        fn exchange(&self, message: &RFMessage, asymmetric_bit: u8) -> Result<RFMessage, MpcError> {
+            if asymmetric_bit == 1 {
+                self.send(message)?;
+                self.receive(&message.message_id)
+            } else {
+                let received = self.receive(&message.message_id)?;
+                self.send(message)?;
+                Ok(received)
+            }
+        }
+    }
+
+    /* hands an incoming message to its receiver; a protocol that reuses a tag cannot continue */
+    fn deliver(manager: &MessageManager, message: &RFMessage) -> bool {
+        match manager.add_message(message) {
+            Ok(()) => true,
+            Err(error) => {
+                manager.fail(MpcError::PeerAbort(error));
+                false
+            }
+        }
+    }
+
+    /* length-prefixed binary frames (see wire) over a pair of TCP streams */
+    pub struct TcpChannel {
+        o_stream: Mutex<TcpStream>,
+        message_manager: Arc<MessageManager>,
+    }
+
+    impl TcpChannel {
+        pub fn new(in_stream: TcpStream, o_stream: TcpStream, timeout: Option<Duration>) -> TcpChannel {
+            let message_manager = Arc::new(MessageManager::new(timeout));
+            let manager = Arc::clone(&message_manager);
+            thread::spawn(move || {
+                let mut reader = BufReader::new(in_stream);
+                loop {
+                    match read_message(&mut reader) {
+                        Ok(Some(message)) => {
+                            if !deliver(&manager, &message) {
+                                break;
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(error) => {
+                            manager.fail(MpcError::io("failed to read a message from the other party", error));
+                            break;
+                        }
+                    }
+                }
+                manager.close();
+            });
+            TcpChannel {
+                o_stream: Mutex::new(o_stream),
+                message_manager,
+            }
+        }
+    }
+
+    impl Channel for TcpChannel {
+        fn send(&self, message: &RFMessage) -> Result<(), MpcError> {
+            let mut o_stream = self.o_stream.lock().unwrap();
+            write_message(&mut *o_stream, message).map_err(|error| MpcError::io(format!("failed to write message {}", message.message_id), error))
+        }
+
+        fn receive(&self, message_id: &str) -> Result<RFMessage, MpcError> {
+            search_pop_message(&self.message_manager, message_id)
+        }
+    }
+
+    /* both parties in one process, linked by mpsc queues carrying the same frames as TcpChannel */
+    pub struct InMemoryChannel {
+        sender: Mutex<Sender<Vec<u8>>>,
+        message_manager: Arc<MessageManager>,
+    }
+
+    impl InMemoryChannel {
+        pub fn pair(timeout: Option<Duration>) -> (InMemoryChannel, InMemoryChannel) {
+            let (sender0, receiver1) = channel::<Vec<u8>>();
+            let (sender1, receiver0) = channel::<Vec<u8>>();
+            let manager0 = Arc::new(MessageManager::new(timeout));
+            let manager1 = Arc::new(MessageManager::new(timeout));
+            for (receiver, manager) in [(receiver0, Arc::clone(&manager0)), (receiver1, Arc::clone(&manager1))] {
+                thread::spawn(move || {
+                    for frame in receiver {
+                        let message = match decode_message(&frame[4..]) {
+                            Ok(message) => message,
+                            Err(error) => {
+                                manager.fail(MpcError::from(error));
+                                break;
+                            }
+                        };
+                        if !deliver(&manager, &message) {
+                            break;
+                        }
+                    }
+                    manager.close();
+                });
+            }
+            (InMemoryChannel { sender: Mutex::new(sender0), message_manager: manager0 },
+             InMemoryChannel { sender: Mutex::new(sender1), message_manager: manager1 })
+        }
+    }
+
+    impl Channel for InMemoryChannel {
+        fn send(&self, message: &RFMessage) -> Result<(), MpcError> {
+            let frame = encode_message(message).map_err(|error| MpcError::io(format!("failed to encode message {}", message.message_id), error))?;
+            self.sender.lock().unwrap().send(frame)
+                .map_err(|_| MpcError::PeerAbort(MessageError::Disconnected { message_id: message.message_id.clone() }))
+        }
+
+        fn receive(&self, message_id: &str) -> Result<RFMessage, MpcError> {
+            search_pop_message(&self.message_manager, message_id)
+        }
+    }
+}
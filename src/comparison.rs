@@ -1,141 +1,85 @@
 pub mod comparison {
     use crate::computing_party::computing_party::ComputingParty;
-    use threadpool::ThreadPool;
-    use crate::constants::constants::BINARY_PRIME;
-    use std::cmp::{max, min};
-    use num::integer::*;
-    use std::sync::{Arc, Mutex};
-    use crate::multiplication::multiplication::{multi_thread_batch_mul_byte, batch_multiplication_byte};
-    use std::collections::HashMap;
+    use std::cmp::max;
+    use crate::multiplication::multiplication::batch_beaver_multiply;
+    use crate::ring::ring::{AdditiveShare, Z2};
+    use crate::error::error::MpcError;
 
-    pub fn comparison(x_list: &Vec<u8>, y_list: &Vec<u8>, ctx: &mut ComputingParty) -> u8 {
+    /* binary share of [x >= y] for LSB-first bit shares */
+    pub fn comparison(x_list: &[AdditiveShare<Z2>], y_list: &[AdditiveShare<Z2>], ctx: &mut ComputingParty) -> Result<AdditiveShare<Z2>, MpcError> {
+        Ok(batch_comparison(&[x_list.to_vec()], &[y_list.to_vec()], ctx)?[0])
+    }
+
+    pub fn batch_comparison(x_lists: &[Vec<AdditiveShare<Z2>>], y_lists: &[Vec<AdditiveShare<Z2>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<Z2>>, MpcError> {
         ctx.thread_hierarchy.push("comparison".to_string());
-        let bit_length = max(x_list.len(), y_list.len());
-        let prime = BINARY_PRIME;
-        let ti_shares = &ctx.dt_shares.binary_triples;
-        let ti_shares_start_index = *(ctx.dt_shares.current_binary_index.lock().unwrap());
-        let mut e_shares = vec![0u8; bit_length];
-        let mut d_shares = vec![0u8; bit_length];
-        let mut c_shares = vec![0u8; bit_length];
-        let mut w = -1;
-        //computeEShares in Java Lynx
-        for i in 0..bit_length {
-            let e_share = x_list[i] + y_list[i] + ctx.asymmetric_bit;
-            e_shares[i] = mod_floor(e_share, BINARY_PRIME as u8);
+        let count = x_lists.len();
+        let asymmetric_bit = ctx.asymmetric_bit;
+        let mut bit_length = 1;
+        for i in 0..count {
+            bit_length = max(bit_length, max(x_lists[i].len(), y_lists[i].len()));
         }
-        //compute D shares
-        let thread_pool = ThreadPool::new(ctx.thread_count);
-        let mut x_list_copied = x_list.clone();
-        let mut y_list_copied = y_list.clone();
-        let mut ctx_copied = ctx.clone();
-        ctx_copied.thread_hierarchy.push("compute_D_shares".to_string());
-        let mut d_shares_wrapper = Arc::new(Mutex::new(d_shares));
-        let mut d_shares_wrapper_copy = Arc::clone(&d_shares_wrapper);
-        thread_pool.execute(move || {
-            let (batch_count, output_map) = multi_thread_batch_mul_byte(&x_list_copied, &y_list_copied, &mut ctx_copied, bit_length);
-            let mut global_index = 0;
-            let mut d_shares_wrapper_copy = d_shares_wrapper_copy.lock().unwrap();
-            for i in 0..batch_count {
-                let product_result = output_map.get(&i).unwrap();
-                for item in product_result {
-                    let local_diff = y_list_copied[global_index] - *item;
+        let pad = |list: &Vec<AdditiveShare<Z2>>| {
+            let mut list = list.clone();
+            list.resize(bit_length, AdditiveShare::zero(&Z2));
+            list
+        };
+        let x_lists: Vec<Vec<AdditiveShare<Z2>>> = x_lists.iter().map(&pad).collect();
+        let y_lists: Vec<Vec<AdditiveShare<Z2>>> = y_lists.iter().map(&pad).collect();
 
-                    (*d_shares_wrapper_copy)[global_index] = mod_floor(local_diff, BINARY_PRIME as u8);
-                    global_index += 1;
-                }
-            }
-        });
-        let mut d_shares = (*d_shares_wrapper.lock().unwrap()).clone();
-        //compute multiplication E parallel
-        let mut ctx_copied = ctx.clone();
-        ctx.thread_hierarchy.push("compute_E_parallel".to_string());
-        let mut multiplication_e = vec![0u8; bit_length];
-        let mut multiplication_e_wrapper = Arc::new(Mutex::new(multiplication_e));
-        let mut multiplication_e_copied = Arc::clone(&multiplication_e_wrapper);
-        thread_pool.execute(move || {
-            let mut main_index = bit_length - 1;
-            main_index -= 1;
-            let mut multiplication_e_copied = multiplication_e_copied.lock().unwrap();
-            (*multiplication_e_copied)[main_index] = e_shares[bit_length - 1];
-            let mut temp_mul_e = vec![0u8; bit_length];
+        //computeEShares in Java Lynx: e_i = [x_i == y_i]
+        let e_shares: Vec<Vec<AdditiveShare<Z2>>> = (0..count).map(|v| {
+            (0..bit_length).map(|i| (x_lists[v][i] ^ y_lists[v][i]).complement(asymmetric_bit)).collect()
+        }).collect();
 
-            while temp_mul_e.len() > 1 {
-                let inner_pool = ThreadPool::new(ctx_copied.thread_count);
-                let mut i = 0;
-                let mut batch_count = 0;
-                let mut output_map = Arc::new(Mutex::new(HashMap::new()));
-                while i < temp_mul_e.len() - 1 {
-                    let mut output_map = Arc::clone(&output_map);
-                    let to_index = min(i + ctx_copied.batch_size, temp_mul_e.len());
-                    let mut ctx_copied_inner = ctx_copied.clone();
-                    ctx_copied_inner.thread_hierarchy.push(format!("{}",batch_count));
-                    let mut temp_mul_e = temp_mul_e.clone();
-                    inner_pool.execute(move || {
-                        let mut batch_mul_result = batch_multiplication_byte(&temp_mul_e[i..to_index - 1].to_vec(), &temp_mul_e[i + 1..to_index].to_vec(), &mut ctx_copied_inner);
-                        let mut output_map = output_map.lock().unwrap();
-                        (*output_map).insert(batch_count, batch_mul_result);
-                    });
-                    i += to_index - 1;
-                    batch_count += 1;
-                }
-                inner_pool.join();
-                let output_map = &*(output_map.lock().unwrap());
-                let mut products = Vec::new();
-                for i in 0..batch_count {
-                    let product_result = output_map.get(&i).unwrap();
-                    for item in product_result {
-                        products.push(*item);
-                    }
-                }
-                temp_mul_e.clear();
-                temp_mul_e = products;
-                main_index -= 1;
-                (*multiplication_e_copied)[main_index] = *temp_mul_e.last().unwrap();
-            }
-            (*multiplication_e_copied)[0] = 0;
-        });
-        thread_pool.join();
+        //compute D shares: d_i = y_i and not x_i
+        let x_flat: Vec<AdditiveShare<Z2>> = x_lists.iter().flatten().cloned().collect();
+        let y_flat: Vec<AdditiveShare<Z2>> = y_lists.iter().flatten().cloned().collect();
+        ctx.thread_hierarchy.push("compute_D_shares".to_string());
+        let products = batch_beaver_multiply(&Z2, &x_flat, &y_flat, ctx)?;
         ctx.thread_hierarchy.pop();
-        let mut multiplication_e = (*multiplication_e_wrapper.lock().unwrap()).clone();
-        //compute c shares
-        let mut i = 0;
-        let mut batch_count = 0;
-        let mut output_map = Arc::new(Mutex::new(HashMap::new()));
-        ctx.thread_hierarchy.push("compute_c_shares".to_string());
-        while i < bit_length - 1 {
-            let mut output_map = Arc::clone(&output_map);
-            let to_index = min(i + ctx.batch_size, bit_length - 1);
-            let mut ctx_copied = ctx.clone();
-            ctx_copied.thread_hierarchy.push(format!("{}",batch_count));
-            let d_shares_copied = (&d_shares)[i..to_index].to_vec().clone();
-            let multiplication_e_copied = (&multiplication_e)[i + 1..to_index + 1].to_vec().clone();
-            thread_pool.execute(move || {
-                let mut batch_mul_result = batch_multiplication_byte(&multiplication_e_copied, &d_shares_copied, &mut ctx_copied);
-                let mut output_map = output_map.lock().unwrap();
-                (*output_map).insert(batch_count, batch_mul_result);
-            });
-            i = to_index;
-            batch_count += 1;
+        let d_shares: Vec<Vec<AdditiveShare<Z2>>> = (0..count).map(|v| {
+            (0..bit_length).map(|i| y_lists[v][i] ^ products[v * bit_length + i]).collect()
+        }).collect();
+
+        //multiplication_e[i] holds the product of e_j for j >= i
+        let mut multiplication_e = e_shares.clone();
+        ctx.thread_hierarchy.push("compute_E_parallel".to_string());
+        for i in (1..bit_length.saturating_sub(1)).rev() {
+            let current: Vec<AdditiveShare<Z2>> = (0..count).map(|v| e_shares[v][i]).collect();
+            let higher: Vec<AdditiveShare<Z2>> = (0..count).map(|v| multiplication_e[v][i + 1]).collect();
+            ctx.thread_hierarchy.push(format!("{}", i));
+            let products = batch_beaver_multiply(&Z2, &current, &higher, ctx)?;
+            ctx.thread_hierarchy.pop();
+            for v in 0..count {
+                multiplication_e[v][i] = products[v];
+            }
         }
-        thread_pool.join();
         ctx.thread_hierarchy.pop();
-        let output_map = &*(output_map.lock().unwrap());
-        for i in 0..batch_count {
-            let products = output_map.get(&i).unwrap();
-            for j in 0..products.len() {
-                let global_index = i * 10 + j;
-                c_shares[global_index] = products[j];
+
+        //compute c shares: y wins at bit i and all higher bits are equal
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for v in 0..count {
+            for i in 0..bit_length - 1 {
+                x_list.push(multiplication_e[v][i + 1]);
+                y_list.push(d_shares[v][i]);
             }
         }
-        c_shares[bit_length - 1] = d_shares[bit_length - 1];
+        ctx.thread_hierarchy.push("compute_c_shares".to_string());
+        let c_products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+
         //compute w shares
-        w = ctx.asymmetric_bit as i8;
-        for i in 0..bit_length {
-            w += c_shares[i] as i8;
-            w = mod_floor(w, BINARY_PRIME as i8);
+        let mut result = Vec::new();
+        for v in 0..count {
+            let mut w = d_shares[v][bit_length - 1].complement(asymmetric_bit);
+            for i in 0..bit_length - 1 {
+                w ^= c_products[v * (bit_length - 1) + i];
+            }
+            result.push(w);
         }
 
         ctx.thread_hierarchy.pop();
-        w as u8
+        Ok(result)
     }
-}
\ No newline at end of file
+}
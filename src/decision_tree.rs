@@ -1,35 +1,42 @@
 pub mod decision_tree {
     use crate::computing_party::computing_party::ComputingParty;
     use num::bigint::{BigInt, BigUint};
-    use num::integer::*;
-    use std::io::{Bytes, Write, BufReader, BufRead};
-    use serde::{Serialize, Deserialize, Serializer};
     use std::num::Wrapping;
     use crate::utils::utils::big_uint_clone;
     use threadpool::ThreadPool;
     use std::sync::{Arc, Mutex};
     use std::collections::HashMap;
     //    use crate::dot_product::dot_product::dot_product;
-    use crate::field_change::field_change::change_binary_to_decimal_field;
-    use std::thread::sleep;
-    use std::time::{Duration, SystemTime};
-    use crate::dot_product::dot_product::{dot_product, dot_product_integer};
-    use crate::bit_decomposition::bit_decomposition::bit_decomposition;
-    use crate::protocol::protocol::arg_max;
-    use crate::constants::constants::BINARY_PRIME;
-    use crate::message::message::{RFMessage, search_pop_message};
+    use crate::field_change::field_change::{change_binary_to_decimal_field, change_binary_to_bigint_field};
+    use std::time::SystemTime;
+    use std::cmp::min;
+    use num::One;
+    use crate::dot_product::dot_product::dot_product;
+    use crate::ring::ring::{AdditiveShare, BigZp, Triple, Z2, Zp};
+    use crate::bit_decomposition::bit_decomposition::{batch_bit_decomposition, batch_bit_decomposition_bigint};
+    use crate::protocol::protocol::{arg_max, arg_max_by_comparisons};
+    use crate::comparison::comparison::batch_comparison;
+    use crate::multiplication::multiplication::batch_beaver_multiply;
+    use crate::utils::utils::{public_bit_shares, reserve_shares};
+    use crate::model::model::SecretSharedTree;
+    use crate::error::error::MpcError;
+    use crate::parameters::parameters;
+
+    type BitShares = Vec<AdditiveShare<Z2>>;
+    //attribute_class_values[k][j][i][t] = [instance t has value j for attribute k and class i]
+    type AttributeClassValues = Vec<Vec<Vec<Vec<AdditiveShare<BigZp>>>>>;
 
     pub struct DecisionTreeData {
         pub attr_value_count: usize,
         pub class_value_count: usize,
         pub attribute_count: usize,
         pub instance_count: usize,
-        pub attr_values: Vec<Vec<Vec<Wrapping<u64>>>>,
-        pub class_values: Vec<Vec<Wrapping<u64>>>,
-        pub attr_values_bytes: Vec<Vec<Vec<u8>>>,
-        pub class_values_bytes: Vec<Vec<u8>>,
-        pub attr_values_big_integer: Vec<Vec<Vec<BigUint>>>,
-        pub class_values_big_integer: Vec<Vec<BigUint>>,
+        pub attr_values: Vec<Vec<Vec<AdditiveShare<Zp>>>>,
+        pub class_values: Vec<Vec<AdditiveShare<Zp>>>,
+        pub attr_values_bytes: Vec<Vec<Vec<AdditiveShare<Z2>>>>,
+        pub class_values_bytes: Vec<Vec<AdditiveShare<Z2>>>,
+        pub attr_values_big_integer: Vec<Vec<Vec<AdditiveShare<BigZp>>>>,
+        pub class_values_big_integer: Vec<Vec<AdditiveShare<BigZp>>>,
     }
 
     pub struct DecisionTreeTraining {
@@ -37,35 +44,30 @@ pub mod decision_tree {
         pub alpha: BigInt,
         pub epsilon: f64,
         pub cutoff_transaction_set_size: usize,
-        pub subset_transaction_bit_vector: Vec<u8>,
-        pub attribute_bit_vector: Vec<u8>,
+        pub subset_transaction_bit_vector: Vec<AdditiveShare<Z2>>,
+        pub attribute_bit_vector: Vec<AdditiveShare<Z2>>,
+        /* public bootstrap multiplicity of every instance */
+        pub instance_weights: Vec<u64>,
         pub prime: u64,
         pub big_int_prime: BigUint,
-        pub dataset_size_prime: u64,
-        pub dataset_size_bit_length: u64,
         pub bit_length: u64,
         pub big_int_ti_index: u64,
     }
 
     pub struct DecisionTreeShares {
-        pub additive_triples: Vec<(Wrapping<u64>, Wrapping<u64>, Wrapping<u64>)>,
-        pub additive_bigint_triples: Vec<(BigUint, BigUint, BigUint)>,
-        pub binary_triples: Vec<(u8, u8, u8)>,
-        pub equality_shares: Vec<(BigUint)>,
+        pub additive_triples: Arc<Vec<Triple<Wrapping<u64>>>>,
+        /* triples modulo 2^64 rather than prime */
+        pub additive_z64_triples: Arc<Vec<Triple<Wrapping<u64>>>>,
+        pub additive_bigint_triples: Arc<Vec<Triple<BigUint>>>,
+        pub binary_triples: Arc<Vec<(u8, u8, u8)>>,
+        pub equality_shares: Arc<Vec<BigUint>>,
         pub current_additive_index: Arc<Mutex<usize>>,
+        pub current_additive_z64_index: Arc<Mutex<usize>>,
         pub current_additive_bigint_index: Arc<Mutex<usize>>,
         pub current_equality_index: Arc<Mutex<usize>>,
         pub current_binary_index: Arc<Mutex<usize>>,
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
-    pub struct DecisionTreeTIShareMessage {
-        pub additive_triples: String,
-        pub additive_bigint_triples: String,
-        pub binary_triples: String,
-        pub equality_shares: String,
-    }
-
 
     impl Clone for DecisionTreeData {
         fn clone(&self) -> Self {
@@ -89,14 +91,13 @@ pub mod decision_tree {
             DecisionTreeTraining {
                 max_depth: self.max_depth,
                 alpha: self.alpha.clone(),
-                epsilon: self.epsilon.clone(),
+                epsilon: self.epsilon,
                 cutoff_transaction_set_size: self.cutoff_transaction_set_size,
                 subset_transaction_bit_vector: self.subset_transaction_bit_vector.clone(),
                 attribute_bit_vector: self.attribute_bit_vector.clone(),
+                instance_weights: self.instance_weights.clone(),
                 prime: self.prime,
                 big_int_prime: big_uint_clone(&self.big_int_prime),
-                dataset_size_prime: self.dataset_size_prime,
-                dataset_size_bit_length: self.dataset_size_bit_length,
                 bit_length: self.bit_length,
                 big_int_ti_index: self.big_int_ti_index,
             }
@@ -106,34 +107,14 @@ pub mod decision_tree {
 
     impl Clone for DecisionTreeShares {
         fn clone(&self) -> Self {
-            let mut additive_triples = Vec::new();
-            let mut additive_bigint_triples = Vec::new();
-            let mut binary_triples = Vec::new();
-            let mut equality_shares = Vec::new();
-
-            for item in self.additive_triples.iter() {
-                additive_triples.push((item.0.clone(), item.1.clone(), item.2.clone()));
-            }
-
-            for item in self.binary_triples.iter() {
-                binary_triples.push((item.0.clone(), item.1.clone(), item.2.clone()));
-            }
-
-            for item in self.additive_bigint_triples.iter() {
-                additive_bigint_triples.push((BigUint::from_bytes_le(&item.0.to_bytes_le().clone()),
-                                              BigUint::from_bytes_le(&item.1.to_bytes_le().clone()),
-                                              BigUint::from_bytes_le(&item.2.to_bytes_le().clone())));
-            }
-            for item in self.equality_shares.iter() {
-                equality_shares.push(BigUint::from_bytes_le(&item.to_bytes_le().clone()));
-            }
-
             DecisionTreeShares {
-                additive_triples,
-                additive_bigint_triples,
-                binary_triples,
-                equality_shares,
+                additive_triples: Arc::clone(&self.additive_triples),
+                additive_z64_triples: Arc::clone(&self.additive_z64_triples),
+                additive_bigint_triples: Arc::clone(&self.additive_bigint_triples),
+                binary_triples: Arc::clone(&self.binary_triples),
+                equality_shares: Arc::clone(&self.equality_shares),
                 current_additive_index: Arc::clone(&self.current_additive_index),
+                current_additive_z64_index: Arc::clone(&self.current_additive_z64_index),
                 current_additive_bigint_index: Arc::clone(&self.current_additive_bigint_index),
                 current_equality_index: Arc::clone(&self.current_equality_index),
                 current_binary_index: Arc::clone(&self.current_binary_index),
@@ -142,120 +123,372 @@ pub mod decision_tree {
     }
 
 
-    pub fn train(ctx: &mut ComputingParty) {
+    pub fn train(ctx: &mut ComputingParty) -> Result<SecretSharedTree, MpcError> {
         println!("start building model");
+        let now = SystemTime::now();
         ctx.thread_hierarchy.push("DT".to_string());
-        let major_class_index = find_common_class_index(ctx);
-        // Make majority class index one-hot encoding public
-        // Share major class index
-        ctx.thread_hierarchy.push("share_major_class_index".to_string());
-
-        let mut o_stream = ctx.o_stream.try_clone()
-            .expect("failed cloning tcp o_stream");
-        let mut message = RFMessage {
-            message_id: ctx.thread_hierarchy.join(":"),
-            message_content: serde_json::to_string(&major_class_index).unwrap(),
-        };
-        let mut major_class_index_receive: Vec<u8> = Vec::new();
-        if ctx.asymmetric_bit == 1 {
-            o_stream.write(format!("{}\n", serde_json::to_string(&message).unwrap()).as_bytes());
-            let mut received_message = search_pop_message(ctx, message.message_id.clone()).unwrap();
-            major_class_index_receive = serde_json::from_str(&received_message.message_content).unwrap();
-        } else {
-            let mut received_message = search_pop_message(ctx, message.message_id.clone()).unwrap();
-            major_class_index_receive = serde_json::from_str(&received_message.message_content).unwrap();
-            o_stream.write(format!("{}\n", serde_json::to_string(&message).unwrap()).as_bytes());
-        }
-//        o_stream.write(format!("{}\n", serde_json::to_string(&message).unwrap()).as_bytes());
-//        let mut received_message = search_pop_message(ctx, message.message_id.clone()).unwrap();
-//        major_class_index_receive = serde_json::from_str(&received_message.message_content).unwrap();
+        let depth_limit = min(ctx.dt_training.max_depth, ctx.dt_data.attribute_count);
+        let mut tree = SecretSharedTree::new(ctx.party_id, ctx.dt_data.attribute_count, ctx.dt_data.attr_value_count, ctx.dt_data.class_value_count, depth_limit);
+        let attribute_class_values = if depth_limit > 0 { compute_attribute_class_values(ctx)? } else { Vec::new() };
+        train_node(ctx, &mut tree, &attribute_class_values, 0, 0, AdditiveShare::zero(&Z2), None)?;
+        ctx.thread_hierarchy.pop();
+        println!("model built in {}ms", now.elapsed().unwrap().as_millis());
+        Ok(tree)
+    }
 
+    // Grows the complete attr_value_count-ary tree below node_index so that its shape reveals nothing.
+    // A node becomes a leaf at the depth limit or once fewer than cutoff_transaction_set_size
+    // transactions reach it; everything below a leaf (and below an empty node) inherits its class.
+    fn train_node(ctx: &mut ComputingParty, tree: &mut SecretSharedTree, attribute_class_values: &AttributeClassValues, node_index: usize, depth: usize,
+                  parent_leaf: AdditiveShare<Z2>, parent_class: Option<&Vec<AdditiveShare<Z2>>>) -> Result<(), MpcError> {
+        let depth_limit = tree.max_depth;
+        ctx.thread_hierarchy.push(format!("node_{}", node_index));
+        let asymmetric_bit = ctx.asymmetric_bit;
         let class_value_count = ctx.dt_data.class_value_count;
-        let mut major_class_index_shared = vec![0u8; class_value_count];
-        for i in 0..class_value_count {
-            major_class_index_shared[i] = mod_floor((Wrapping((&major_class_index)[i]) + Wrapping((&major_class_index_receive)[i])).0, BINARY_PRIME as u8);
-        }
+        let (major_class_index, transaction_count_bits) = find_common_class_index(ctx)?;
+
+        ctx.thread_hierarchy.push("check_stopping_condition".to_string());
+        let bit_length = transaction_count_bits.len();
+        let cutoff = public_bit_shares(ctx.dt_training.cutoff_transaction_set_size as u64, bit_length, asymmetric_bit);
+        let one = public_bit_shares(1, bit_length, asymmetric_bit);
+        let at_least = batch_comparison(&[transaction_count_bits.clone(), transaction_count_bits.clone()], &[cutoff, one], ctx)?;
+        let below_cutoff = at_least[0].complement(asymmetric_bit);
+        let empty = at_least[1].complement(asymmetric_bit);
+        let or_terms = batch_beaver_multiply(&Z2, &[parent_leaf, parent_leaf], &[below_cutoff, empty], ctx)?;
+        let is_leaf = if depth == depth_limit { AdditiveShare::public(&Z2, &1, asymmetric_bit) } else { parent_leaf ^ below_cutoff ^ or_terms[0] };
+        let inherit = parent_leaf ^ empty ^ or_terms[1];
+        let class_index = match parent_class {
+            Some(parent_class) => {
+                let diff: Vec<AdditiveShare<Z2>> = (0..class_value_count).map(|i| parent_class[i] ^ major_class_index[i]).collect();
+                let selected = batch_beaver_multiply(&Z2, &vec![inherit; class_value_count], &diff, ctx)?;
+                (0..class_value_count).map(|i| major_class_index[i] ^ selected[i]).collect()
+            }
+            None => major_class_index,
+        };
         ctx.thread_hierarchy.pop();
 
-        let mut major_index = 0;
-        for i in 0..class_value_count {
-            if major_class_index_shared[i] == 1 {
-                major_index = i;
-                break;
+        tree.nodes[node_index].is_leaf = is_leaf;
+        tree.nodes[node_index].class_index = class_index.clone();
+
+        if depth < depth_limit {
+            let split_attribute_index = find_best_attribute_index(ctx, attribute_class_values)?;
+            let (child_subsets, child_attribute_bit_vector) = split_transactions(ctx, &split_attribute_index)?;
+            tree.nodes[node_index].split_attribute = split_attribute_index;
+
+            let subset_transaction_bit_vector = ctx.dt_training.subset_transaction_bit_vector.clone();
+            let attribute_bit_vector = ctx.dt_training.attribute_bit_vector.clone();
+            ctx.dt_training.attribute_bit_vector = child_attribute_bit_vector;
+            for (j, child_subset) in child_subsets.into_iter().enumerate() {
+                ctx.dt_training.subset_transaction_bit_vector = child_subset;
+                let child_index = tree.child_index(node_index, j);
+                train_node(ctx, tree, attribute_class_values, child_index, depth + 1, is_leaf, Some(&class_index))?;
             }
+            ctx.dt_training.subset_transaction_bit_vector = subset_transaction_bit_vector;
+            ctx.dt_training.attribute_bit_vector = attribute_bit_vector;
         }
 
-        for i in major_index + 1..class_value_count {
-            major_class_index_shared[i] = 0;
+        if ctx.debug_output {
+            println!("node {} at depth {} trained", node_index, depth);
         }
-
-        println!("{:?}", major_class_index_shared);
         ctx.thread_hierarchy.pop();
+        Ok(())
     }
 
-    fn find_common_class_index(ctx: &mut ComputingParty) -> Vec<u8> {
-        let mut now = SystemTime::now();
+    fn find_common_class_index(ctx: &mut ComputingParty) -> Result<(BitShares, BitShares), MpcError> {
+        let now = SystemTime::now();
         ctx.thread_hierarchy.push("find_common_class_index".to_string());
-        let mut subset_transaction_bit_vector = ctx.dt_training.subset_transaction_bit_vector.clone();
-        let mut subset_decimal = change_binary_to_decimal_field(&subset_transaction_bit_vector, ctx);
+        let ring = Zp::of(ctx);
+        let subset_transaction_bit_vector = ctx.dt_training.subset_transaction_bit_vector.clone();
+        let subset_decimal: Vec<AdditiveShare<Zp>> = change_binary_to_decimal_field(&subset_transaction_bit_vector, ctx)?.iter().enumerate()
+            .map(|(t, item)| item.scale(&ring, &Wrapping(ctx.dt_training.instance_weights[t])))
+            .collect();
         let mut s = Vec::new();
         let thread_pool = ThreadPool::new(ctx.thread_count);
-        let mut dp_result_map = Arc::new(Mutex::new(HashMap::new()));
+        let dp_result_map = Arc::new(Mutex::new(HashMap::new()));
         let mut ctx_copied = ctx.clone();
 
         ctx_copied.thread_hierarchy.push("compute_dp".to_string());
         for i in 0..ctx.dt_data.class_value_count {
-            let mut dp_result_map = Arc::clone(&dp_result_map);
-            let mut subset_decimal_cloned = subset_decimal.clone();
-            let mut class_value_transaction = ctx.dt_data.class_values.clone();
+            let dp_result_map = Arc::clone(&dp_result_map);
+            let subset_decimal_cloned = subset_decimal.clone();
+            let class_value_transaction = ctx.dt_data.class_values[i].clone();
             let mut ctx = ctx_copied.clone();
             ctx.thread_hierarchy.push(format!("{}", i));
+            ctx.dt_shares.current_additive_index = reserve_shares(&ctx_copied.dt_shares.current_additive_index, subset_decimal.len());
             thread_pool.execute(move || {
-                let precision = ctx.decimal_precision;
-                let dp_result = dot_product_integer(&subset_decimal_cloned, &class_value_transaction[i], &mut ctx);
+                let dp_result = dot_product(&ring, &subset_decimal_cloned, &class_value_transaction, &mut ctx);
                 let mut dp_result_map = dp_result_map.lock().unwrap();
-                (*dp_result_map).insert(i, (dp_result.0));
+                (*dp_result_map).insert(i, dp_result);
             });
         }
         thread_pool.join();
-        println!("compute_dp completes in {}ms", now.elapsed().unwrap().as_millis());
+        if ctx.debug_output {
+            println!("compute_dp completes in {}ms", now.elapsed().unwrap().as_millis());
+        }
         ctx_copied.thread_hierarchy.pop();
 
-        let mut dp_result_map = &*(dp_result_map.lock().unwrap());
+        let mut dp_result_map = dp_result_map.lock().unwrap();
         for i in 0..ctx.dt_data.class_value_count {
-            s.push((dp_result_map.get(&i).unwrap()).clone());
+            s.push(dp_result_map.remove(&i).unwrap()?);
         }
+        s.push(AdditiveShare::sum(&ring, &s));
 
-        let mut ctx_copied = ctx.clone();
-        let mut bd_result_map = Arc::new(Mutex::new(HashMap::new()));
+        ctx.thread_hierarchy.push("compute_bd".to_string());
+        let mut bit_shares = batch_bit_decomposition(&s, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let transaction_count_bits = bit_shares.pop().unwrap();
 
-        ctx_copied.thread_hierarchy.push("compute_bd".to_string());
-        for i in 0..ctx.dt_data.class_value_count {
-            let mut bd_result_map = Arc::clone(&bd_result_map);
-            let mut ctx = ctx_copied.clone();
-            ctx.thread_hierarchy.push(format!("{}", i));
-            let s_copied = s[i];
-            //run in parallel would cause data corruption
-            thread_pool.execute(move || {
-                let bd_result = bit_decomposition(s_copied, &mut ctx);
-                let mut bd_result_map = bd_result_map.lock().unwrap();
-                (*bd_result_map).insert(i, bd_result);
-            });
+        let arg_max = arg_max(&bit_shares, ctx)?;
+
+        ctx.thread_hierarchy.pop();
+        if ctx.debug_output {
+            println!("find common class index completes in {}ms", now.elapsed().unwrap().as_millis());
         }
-        thread_pool.join();
-        println!("compute_bd completes in {}ms", now.elapsed().unwrap().as_millis());
-        ctx_copied.thread_hierarchy.pop();
+        Ok((arg_max, transaction_count_bits))
+    }
 
-        let mut bd_result_map = &*(bd_result_map.lock().unwrap());
-        let mut bit_shares = Vec::new();
-        for i in 0..ctx.dt_data.class_value_count {
-            bit_shares.push((bd_result_map.get(&i).unwrap()).clone());
+    /* per attribute value and class, the products a_kj * c_i that every node's Gini counts are dot products against */
+    fn compute_attribute_class_values(ctx: &mut ComputingParty) -> Result<AttributeClassValues, MpcError> {
+        ctx.thread_hierarchy.push("compute_attribute_class_values".to_string());
+        let attribute_count = ctx.dt_data.attribute_count;
+        let attr_value_count = ctx.dt_data.attr_value_count;
+        let class_value_count = ctx.dt_data.class_value_count;
+        let instance_count = ctx.dt_data.instance_count;
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for k in 0..attribute_count {
+            for j in 0..attr_value_count {
+                for i in 0..class_value_count {
+                    x_list.extend(ctx.dt_data.attr_values_big_integer[k][j].iter().cloned());
+                    y_list.extend(ctx.dt_data.class_values_big_integer[i].iter().cloned());
+                }
+            }
+        }
+        let products = batch_beaver_multiply(&BigZp::of(ctx), &x_list, &y_list, ctx)?;
+        let mut chunks = products.chunks(instance_count);
+        let mut result = Vec::new();
+        for _k in 0..attribute_count {
+            let mut attribute = Vec::new();
+            for _j in 0..attr_value_count {
+                let mut value = Vec::new();
+                for _i in 0..class_value_count {
+                    value.push(chunks.next().unwrap().to_vec());
+                }
+                attribute.push(value);
+            }
+            result.push(attribute);
+        }
+        ctx.thread_hierarchy.pop();
+        Ok(result)
+    }
+
+    /* bits needed for the cross-multiplied Gini scores N_a * D_b, bounded via AM-GM over the alpha-scaled value counts */
+    pub fn gini_score_bit_length(ctx: &ComputingParty) -> usize {
+        parameters::gini_score_bit_length(ctx.dt_data.instance_count, ctx.dt_data.attr_value_count, &ctx.dt_training.alpha.to_biguint().unwrap())
+    }
+
+    // Scores attribute k with the alpha-scaled Gini index of de Hoogh et al.,
+    //   G_k = sum_j (sum_i x_kji^2) / (alpha * |T_kj| + 1) = N_k / D_k,
+    // kept as a fraction and compared pairwise through the sign of N_a * D_b - N_b * D_a.
+    fn find_best_attribute_index(ctx: &mut ComputingParty, attribute_class_values: &AttributeClassValues) -> Result<Vec<AdditiveShare<Z2>>, MpcError> {
+        let now = SystemTime::now();
+        ctx.thread_hierarchy.push("find_best_attribute_index".to_string());
+        let attribute_count = ctx.dt_data.attribute_count;
+        let attr_value_count = ctx.dt_data.attr_value_count;
+        let class_value_count = ctx.dt_data.class_value_count;
+        let ring = BigZp::of(ctx);
+        let prime = ring.prime.clone();
+        let one = AdditiveShare::public(&ring, &BigUint::one(), ctx.asymmetric_bit);
+        let alpha = ctx.dt_training.alpha.to_biguint().unwrap();
+        let score_bit_length = gini_score_bit_length(ctx);
+        if score_bit_length + 2 > prime.bits() {
+            return Err(MpcError::Config { key: "big_int_prime".to_string(), problem: format!("must exceed 2^{} to compare Gini scores", score_bit_length + 1) });
+        }
+
+        let subset_transaction_bit_vector = ctx.dt_training.subset_transaction_bit_vector.clone();
+        let subset_bigint: Vec<AdditiveShare<BigZp>> = change_binary_to_bigint_field(&subset_transaction_bit_vector, ctx)?.iter().enumerate()
+            .map(|(t, item)| item.scale(&ring, &BigUint::from(ctx.dt_training.instance_weights[t])))
+            .collect();
+        let attribute_bit_vector = ctx.dt_training.attribute_bit_vector.clone();
+        let attribute_bigint = change_binary_to_bigint_field(&attribute_bit_vector, ctx)?;
+
+        //x_kji = |T with attribute k = value j and class i|
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for attribute in attribute_class_values.iter() {
+            for value in attribute.iter() {
+                for class in value.iter() {
+                    x_list.extend(subset_bigint.iter().cloned());
+                    y_list.extend(class.iter().cloned());
+                }
+            }
+        }
+        ctx.thread_hierarchy.push("compute_counts".to_string());
+        let products = batch_beaver_multiply(&ring, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let counts: Vec<AdditiveShare<BigZp>> = products.chunks(subset_bigint.len().max(1))
+            .map(|chunk| AdditiveShare::sum(&ring, chunk))
+            .collect();
+
+        ctx.thread_hierarchy.push("compute_squares".to_string());
+        let squares = batch_beaver_multiply(&ring, &counts, &counts, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let mut numerators = vec![vec![AdditiveShare::zero(&ring); attr_value_count]; attribute_count];
+        let mut denominators = vec![vec![AdditiveShare::zero(&ring); attr_value_count]; attribute_count];
+        for k in 0..attribute_count {
+            for j in 0..attr_value_count {
+                let mut value_count = AdditiveShare::zero(&ring);
+                for i in 0..class_value_count {
+                    let index = (k * attr_value_count + j) * class_value_count + i;
+                    numerators[k][j] = numerators[k][j].add(&ring, &squares[index]);
+                    value_count = value_count.add(&ring, &counts[index]);
+                }
+                denominators[k][j] = value_count.scale(&ring, &alpha).add(&ring, &one);
+            }
         }
 
-        let mut arg_max = arg_max(&bit_shares, ctx);
+        //prefix[k][j] and suffix[k][j] are the products of the denominators before and after value j
+        let mut prefix = vec![vec![one.clone(); attr_value_count]; attribute_count];
+        let mut suffix = vec![vec![one.clone(); attr_value_count]; attribute_count];
+        ctx.thread_hierarchy.push("compute_denominator_products".to_string());
+        for j in 1..attr_value_count {
+            let mut x_list = Vec::new();
+            let mut y_list = Vec::new();
+            for k in 0..attribute_count {
+                x_list.push(prefix[k][j - 1].clone());
+                y_list.push(denominators[k][j - 1].clone());
+                x_list.push(suffix[k][attr_value_count - j].clone());
+                y_list.push(denominators[k][attr_value_count - j].clone());
+            }
+            ctx.thread_hierarchy.push(format!("{}", j));
+            let products = batch_beaver_multiply(&ring, &x_list, &y_list, ctx)?;
+            ctx.thread_hierarchy.pop();
+            for k in 0..attribute_count {
+                prefix[k][j] = products[2 * k].clone();
+                suffix[k][attr_value_count - j - 1] = products[2 * k + 1].clone();
+            }
+        }
+        ctx.thread_hierarchy.pop();
+
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for k in 0..attribute_count {
+            for j in 0..attr_value_count {
+                x_list.push(prefix[k][j].clone());
+                y_list.push(suffix[k][j].clone());
+            }
+        }
+        ctx.thread_hierarchy.push("compute_other_denominators".to_string());
+        let others = batch_beaver_multiply(&ring, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for k in 0..attribute_count {
+            for j in 0..attr_value_count {
+                x_list.push(numerators[k][j].clone());
+                y_list.push(others[k * attr_value_count + j].clone());
+            }
+            x_list.push(prefix[k][attr_value_count - 1].clone());
+            y_list.push(denominators[k][attr_value_count - 1].clone());
+        }
+        ctx.thread_hierarchy.push("compute_fractions".to_string());
+        let products = batch_beaver_multiply(&ring, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let mut score_numerators = Vec::new();
+        let mut score_denominators = Vec::new();
+        for k in 0..attribute_count {
+            let offset = k * (attr_value_count + 1);
+            score_numerators.push(AdditiveShare::sum(&ring, &products[offset..offset + attr_value_count]));
+            score_denominators.push(products[offset + attr_value_count].clone());
+        }
+
+        //attributes already used on the path score zero
+        ctx.thread_hierarchy.push("mask_attributes".to_string());
+        let score_numerators = batch_beaver_multiply(&ring, &score_numerators, &attribute_bigint, ctx)?;
+        ctx.thread_hierarchy.pop();
+
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for a in 0..attribute_count {
+            for b in a + 1..attribute_count {
+                x_list.push(score_numerators[a].clone());
+                y_list.push(score_denominators[b].clone());
+                x_list.push(score_numerators[b].clone());
+                y_list.push(score_denominators[a].clone());
+            }
+        }
+        ctx.thread_hierarchy.push("compute_cross_products".to_string());
+        let cross_products = batch_beaver_multiply(&ring, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let offset = AdditiveShare::public(&ring, &num::pow(BigUint::from(2u8), score_bit_length), ctx.asymmetric_bit);
+        let differences: Vec<AdditiveShare<BigZp>> = (0..cross_products.len() / 2)
+            .map(|p| cross_products[2 * p].add(&ring, &offset).subtract(&ring, &cross_products[2 * p + 1]))
+            .collect();
+        let difference_bits = batch_bit_decomposition_bigint(&differences, ctx)?;
+
+        let mut comparisons = vec![vec![AdditiveShare::zero(&Z2); attribute_count]; attribute_count];
+        let mut key = 0;
+        for (a, row) in comparisons.iter_mut().enumerate() {
+            for comparison in row.iter_mut().skip(a + 1) {
+                *comparison = difference_bits[key][score_bit_length];
+                key += 1;
+            }
+        }
+        let result = arg_max_by_comparisons(&comparisons, ctx)?;
+        ctx.thread_hierarchy.pop();
+        if ctx.debug_output {
+            println!("find best attribute index completes in {}ms", now.elapsed().unwrap().as_millis());
+        }
+        Ok(result)
+    }
+
+    /* one subset per value of the chosen attribute, and the attribute vector with the chosen attribute removed */
+    fn split_transactions(ctx: &mut ComputingParty, split_attribute_index: &[AdditiveShare<Z2>]) -> Result<(Vec<BitShares>, BitShares), MpcError> {
+        ctx.thread_hierarchy.push("split_transactions".to_string());
+        let attribute_count = ctx.dt_data.attribute_count;
+        let attr_value_count = ctx.dt_data.attr_value_count;
+        let instance_count = ctx.dt_data.instance_count;
+
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for j in 0..attr_value_count {
+            for (selected, attribute) in split_attribute_index.iter().zip(ctx.dt_data.attr_values_bytes.iter()) {
+                x_list.extend(vec![*selected; instance_count]);
+                y_list.extend(attribute[j].iter().cloned());
+            }
+        }
+        ctx.thread_hierarchy.push("select_attribute_values".to_string());
+        let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+
+        let subset_transaction_bit_vector = &ctx.dt_training.subset_transaction_bit_vector;
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for j in 0..attr_value_count {
+            for t in 0..instance_count {
+                let mut selected = AdditiveShare::zero(&Z2);
+                for k in 0..attribute_count {
+                    selected ^= products[(j * attribute_count + k) * instance_count + t];
+                }
+                x_list.push(subset_transaction_bit_vector[t]);
+                y_list.push(selected);
+            }
+        }
+        x_list.extend(ctx.dt_training.attribute_bit_vector.iter().cloned());
+        y_list.extend(split_attribute_index.iter().cloned());
+        ctx.thread_hierarchy.push("compute_subsets".to_string());
+        let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
 
+        let child_subsets = products[..attr_value_count * instance_count].chunks(instance_count.max(1))
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        let used = &products[attr_value_count * instance_count..];
+        let attribute_bit_vector = (0..attribute_count)
+            .map(|k| ctx.dt_training.attribute_bit_vector[k] ^ used[k])
+            .collect();
         ctx.thread_hierarchy.pop();
-        println!("find common class index completes in {}ms", now.elapsed().unwrap().as_millis());
-        arg_max
+        Ok((child_subsets, attribute_bit_vector))
     }
-}
\ No newline at end of file
+}
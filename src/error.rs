@@ -0,0 +1,149 @@
+pub mod error {
+    use crate::message::message::MessageError;
+    use crate::wire::wire::WireError;
+    use std::error::Error;
+    use std::fmt;
+    use std::io;
+
+    // Everything that can stop a party, the TI or a simulation short of its result. Protocol code
+    // returns these instead of panicking so that an embedding application can report the failure,
+    // close the session and start another.
+    #[derive(Debug)]
+    pub enum MpcError {
+        /* a socket or file could not be read or written */
+        Io { context: String, error: io::Error },
+        /* bytes from the other party, the TI or disk that do not decode to what was expected */
+        Deserialize(String),
+        /* the share source provided less correlated randomness than the computation consumes */
+        Exhausted(Vec<Shortfall>),
+        /* the other party timed out, hung up or reused a message id */
+        PeerAbort(MessageError),
+        /* the other party's messages disagree with this party's view of the protocol */
+        Protocol(String),
+        /* a missing or invalid option */
+        Config { key: String, problem: String },
+        /* every missing, malformed or inconsistent option found while loading the settings */
+        Settings(Vec<ConfigProblem>),
+        /* the session handshake found parameters on which this role and the peer disagree */
+        Mismatch { peer: String, conflicts: Vec<Conflict> },
+        /* a command line that does not fit the subcommand */
+        Usage(String),
+    }
+
+    /* one session parameter two roles hold different values of */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Conflict {
+        pub key: String,
+        pub own: String,
+        pub peer: String,
+    }
+
+    /* one option the settings loader rejected */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ConfigProblem {
+        pub key: String,
+        pub problem: String,
+    }
+
+    /* one pool of correlated randomness that falls short */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Shortfall {
+        pub pool: &'static str,
+        /* the option sizing the pool */
+        pub key: &'static str,
+        pub available: usize,
+        /* what a tree needs, where the budget knows it */
+        pub needed: Option<usize>,
+    }
+
+    impl MpcError {
+        /* a malformed stream is a decoding problem rather than a failed read */
+        pub fn io(context: impl Into<String>, error: io::Error) -> MpcError {
+            match error.kind() {
+                io::ErrorKind::InvalidData => MpcError::Deserialize(format!("{}: {}", context.into(), error)),
+                _ => MpcError::Io { context: context.into(), error },
+            }
+        }
+
+        pub fn config(key: &str, problem: impl fmt::Debug) -> MpcError {
+            MpcError::Config { key: key.to_string(), problem: format!("{:?}", problem) }
+        }
+
+        /* the values received under message_id, which must pair up with the expected count */
+        pub fn check_length<T>(values: Vec<T>, expected: usize, message_id: &str) -> Result<Vec<T>, MpcError> {
+            if values.len() == expected {
+                Ok(values)
+            } else {
+                Err(MpcError::Protocol(format!("message {} carries {} values, expected {}", message_id, values.len(), expected)))
+            }
+        }
+    }
+
+    impl fmt::Display for Shortfall {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.needed {
+                Some(needed) => write!(f, "{} {} of the {} a tree needs ({})", self.available, self.pool, needed, self.key),
+                None => write!(f, "{} {} per tree ({})", self.available, self.pool, self.key),
+            }
+        }
+    }
+
+    impl fmt::Display for ConfigProblem {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}: {}", self.key, self.problem)
+        }
+    }
+
+    impl fmt::Display for Conflict {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} is {} here and {} there", self.key, self.own, self.peer)
+        }
+    }
+
+    impl fmt::Display for MpcError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                MpcError::Io { context, error } => write!(f, "{}: {}", context, error),
+                MpcError::Deserialize(problem) => write!(f, "failed to decode {}", problem),
+                MpcError::Exhausted(short) => {
+                    let short: Vec<String> = short.iter().map(|shortfall| shortfall.to_string()).collect();
+                    write!(f, "ran out of correlated randomness: {}; raise the options named or leave them unset to have them computed", short.join(", "))
+                }
+                MpcError::PeerAbort(error) => write!(f, "{}", error),
+                MpcError::Protocol(problem) => write!(f, "protocol violation: {}", problem),
+                MpcError::Config { key, problem } => write!(f, "Encountered a problem while parsing {}: {}", key, problem),
+                MpcError::Settings(problems) => {
+                    let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+                    write!(f, "Encountered {} problem(s) in the settings: {}", problems.len(), problems.join("; "))
+                }
+                MpcError::Mismatch { peer, conflicts } => {
+                    let conflicts: Vec<String> = conflicts.iter().map(|conflict| conflict.to_string()).collect();
+                    write!(f, "the session parameters disagree with {}'s: {}", peer, conflicts.join("; "))
+                }
+                MpcError::Usage(usage) => write!(f, "usage: {}", usage),
+            }
+        }
+    }
+
+    impl Error for MpcError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                MpcError::Io { error, .. } => Some(error),
+                MpcError::PeerAbort(error) => Some(error),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<MessageError> for MpcError {
+        fn from(error: MessageError) -> MpcError {
+            MpcError::PeerAbort(error)
+        }
+    }
+
+    impl From<WireError> for MpcError {
+        fn from(error: WireError) -> MpcError {
+            MpcError::Deserialize(format!("a message frame: {}", error))
+        }
+    }
+}
@@ -1,43 +1,39 @@
 pub mod field_change{
     use crate::computing_party::computing_party::ComputingParty;
     use std::num::Wrapping;
-    use num::integer::*;
-    use num::bigint::{BigUint, ToBigUint, ToBigInt};
-    use num::{Zero, One, FromPrimitive, abs, BigInt};
-    use crate::or_xor::or_xor::{or_xor, or_xor_bigint};
+    use num::bigint::BigUint;
+    use crate::or_xor::or_xor::or_xor;
+    use crate::ring::ring::{AdditiveShare, BigZp, Ring, Z2, Zp};
+    use crate::error::error::MpcError;
 
-    pub fn change_binary_to_decimal_field(binary_numbers: &Vec<u8>, ctx: &mut ComputingParty) -> Vec<Wrapping<u64>> {
+    // A bit b = b0 XOR b1 is b0 + b1 - 2 b0 b1 over the integers: each party inputs its own XOR
+    // share into the target ring and or_xor with c = 2 combines the two inputs.
+    pub fn change_binary_to_decimal_field(binary_numbers: &[AdditiveShare<Z2>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<Zp>>, MpcError> {
         ctx.thread_hierarchy.push("change_binary_to_decimal_field".to_string());
-        let mut dummy_list = vec![Wrapping(0u64); binary_numbers.len()];
-        let mut output = Vec::new();
-        let mut binary_int_list = Vec::new();
-        for item in binary_numbers {
-            binary_int_list.push(Wrapping(*item as u64));
-        }
-        if ctx.asymmetric_bit == 1 {
-            output = or_xor(&binary_int_list, &dummy_list, ctx, 2);
-        } else {
-            output = or_xor(&dummy_list, &binary_int_list, ctx, 2);
-        }
+        let ring = Zp::of(ctx);
+        let (x_list, y_list) = inputs(&ring, binary_numbers, |bit| Wrapping(bit as u64), ctx.asymmetric_bit);
+        let output = or_xor(&ring, &x_list, &y_list, ctx, &Wrapping(2))?;
         ctx.thread_hierarchy.pop();
-        output
+        Ok(output)
     }
 
-    pub fn change_binary_to_bigint_field(binary_numbers: &Vec<u8>, ctx: &mut ComputingParty) -> Vec<BigUint> {
+    pub fn change_binary_to_bigint_field(binary_numbers: &[AdditiveShare<Z2>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<BigZp>>, MpcError> {
         ctx.thread_hierarchy.push("change_binary_to_bigint_field".to_string());
-        let mut binary_num_bigint = Vec::new();
-        for item in binary_numbers.iter() {
-            binary_num_bigint.push(item.to_biguint().unwrap());
-        }
-        let mut dummy_list = vec![BigUint::zero(); binary_numbers.len()];
-
-        let mut output = Vec::new();
-        if ctx.asymmetric_bit == 1 {
-            output = or_xor_bigint(&binary_num_bigint, &dummy_list, ctx, &BigUint::from_usize(2).unwrap());
-        } else {
-            output = or_xor_bigint(&dummy_list, &binary_num_bigint, ctx, &BigUint::from_usize(2).unwrap());
-        }
+        let ring = BigZp::of(ctx);
+        let (x_list, y_list) = inputs(&ring, binary_numbers, BigUint::from, ctx.asymmetric_bit);
+        let output = or_xor(&ring, &x_list, &y_list, ctx, &BigUint::from(2u8))?;
         ctx.thread_hierarchy.pop();
-        output
+        Ok(output)
+    }
+
+    /* the asymmetric party's XOR shares as inputs in ring, and the other party's */
+    fn inputs<R: Ring>(ring: &R, binary_numbers: &[AdditiveShare<Z2>], lift: fn(u8) -> R::Element, asymmetric_bit: u8)
+                       -> (Vec<AdditiveShare<R>>, Vec<AdditiveShare<R>>) {
+        binary_numbers.iter()
+            .map(|bit| {
+                let value = lift(*bit.value());
+                (AdditiveShare::input(ring, &value, asymmetric_bit == 1), AdditiveShare::input(ring, &value, asymmetric_bit == 0))
+            })
+            .unzip()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,182 @@
+pub mod handshake {
+    use crate::computing_party::computing_party::ComputingParty;
+    use crate::error::error::{Conflict, MpcError};
+    use crate::message::message::{Payload, RFMessage};
+    use crate::settings::settings::TiConfig;
+    use crate::wire::wire::{read_message, write_message};
+    use hmac::{Hmac, Mac};
+    use rand::Rng;
+    use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+    use std::net::TcpStream;
+
+    // At session start every pair of roles that talk (the two computing parties, and each party
+    // and a TI serving it over TCP) compares the parameters it runs with. Both sides first exchange
+    // fresh nonces, then their parameters together with an HMAC-SHA256, keyed by session_key, over
+    // the sender's role, both nonces and the SHA-256 digest of the parameters. A receiver that
+    // cannot verify the MAC, or that holds a different value of a parameter both sides know, stops
+    // before any shares are used. Without a session_key the MAC is keyed by the empty string and
+    // catches misconfiguration, not a peer that lies; the handshake says so on stderr.
+
+    pub const TI_ROLE: &str = "the TI";
+
+    /* the role name of computing party party_id */
+    pub fn party_role(party_id: u8) -> String {
+        format!("party {}", party_id)
+    }
+
+    /* protocol parameters and dataset shape by option name */
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    pub struct SessionParameters {
+        values: BTreeMap<String, String>,
+    }
+
+    impl SessionParameters {
+        pub fn for_party(ctx: &ComputingParty) -> SessionParameters {
+            let mut parameters = SessionParameters::default();
+            parameters.set("tree_count", ctx.tree_count);
+            parameters.set("max_depth", ctx.dt_training.max_depth);
+            parameters.set("max_features", format!("{:?}", ctx.max_features));
+            parameters.set("bootstrap", ctx.bootstrap);
+            parameters.set("alpha", &ctx.dt_training.alpha);
+            parameters.set("epsilon", ctx.dt_training.epsilon);
+            parameters.set("prime", ctx.dt_training.prime);
+            parameters.set("big_int_prime", &ctx.dt_training.big_int_prime);
+            parameters.set("bit_length", ctx.dt_training.bit_length);
+            parameters.set("instance_count", ctx.dt_data.instance_count);
+            parameters.set("attribute_count", ctx.dt_data.attribute_count);
+            parameters.set("attr_value_count", ctx.dt_data.attr_value_count);
+            parameters.set("class_value_count", ctx.dt_data.class_value_count);
+            parameters
+        }
+
+        /* the TI knows the dataset shape only if its settings describe it */
+        pub fn for_ti(config: &TiConfig) -> SessionParameters {
+            let mut parameters = SessionParameters::default();
+            parameters.set("tree_count", config.tree_count);
+            parameters.set("prime", config.prime);
+            parameters.set("big_int_prime", &config.big_int_prime);
+            let optional = [
+                ("max_depth", config.max_depth), ("bit_length", config.bit_length), ("instance_count", config.shares.instance_count),
+                ("attribute_count", config.shares.attribute_count), ("attr_value_count", config.shares.attr_value_count),
+                ("class_value_count", config.shares.class_value_count),
+            ];
+            for (key, value) in optional.iter() {
+                if let Some(value) = value {
+                    parameters.set(key, value);
+                }
+            }
+            parameters
+        }
+
+        pub fn set(&mut self, key: &str, value: impl ToString) {
+            self.values.insert(key.to_string(), value.to_string());
+        }
+
+        pub fn get(&self, key: &str) -> Option<&String> {
+            self.values.get(key)
+        }
+
+        pub fn digest(&self) -> Vec<u8> {
+            let mut hasher = Sha256::new();
+            for (key, value) in self.values.iter() {
+                hasher.input(framed(key.as_bytes()));
+                hasher.input(framed(value.as_bytes()));
+            }
+            hasher.result().to_vec()
+        }
+
+        /* the parameters both sides hold with different values */
+        pub fn conflicts(&self, peer: &SessionParameters) -> Vec<Conflict> {
+            self.values.iter()
+                .filter_map(|(key, own)| match peer.values.get(key) {
+                    Some(theirs) if theirs != own => Some(Conflict { key: key.clone(), own: own.clone(), peer: theirs.clone() }),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Hello {
+        role: String,
+        parameters: SessionParameters,
+        mac: Vec<u8>,
+    }
+
+    /* with the other computing party over the channel, then with the party's share source */
+    pub fn start_session(ctx: &ComputingParty) -> Result<(), MpcError> {
+        let parameters = SessionParameters::for_party(ctx);
+        let session_key = ctx.session_key.as_deref();
+        let prefix = ctx.thread_hierarchy.join(":");
+        handshake(&party_role(ctx.party_id), &party_role(1 - ctx.party_id), &parameters, session_key, |step, bytes| {
+            let message = RFMessage { message_id: format!("{}:handshake:{}", prefix, step), payload: Payload::Bytes(bytes) };
+            ctx.channel.exchange(&message, ctx.asymmetric_bit)?.payload.into_bytes()
+        })?;
+        ctx.share_source.handshake(ctx.party_id, &parameters, session_key)
+    }
+
+    /* over a connection between a party and the TI, before any shares; both ends write before they read */
+    pub fn handshake_over_stream(stream: &mut TcpStream, own_role: &str, peer_role: &str, parameters: &SessionParameters,
+                                 session_key: Option<&str>) -> Result<(), MpcError> {
+        handshake(own_role, peer_role, parameters, session_key, |step, bytes| {
+            let message_id = format!("handshake:{}", step);
+            write_message(stream, &RFMessage { message_id: message_id.clone(), payload: Payload::Bytes(bytes) })
+                .map_err(|error| MpcError::io(format!("failed to send the handshake to {}", peer_role), error))?;
+            match read_message(stream).map_err(|error| MpcError::io(format!("failed to read the handshake of {}", peer_role), error))? {
+                Some(message) if message.message_id == message_id => message.payload.into_bytes(),
+                Some(message) => Err(MpcError::Protocol(format!("{} sent {} during the handshake, expected {}", peer_role, message.message_id, message_id))),
+                None => Err(MpcError::Protocol(format!("{} hung up during the handshake", peer_role))),
+            }
+        })
+    }
+
+    fn handshake<E>(own_role: &str, peer_role: &str, parameters: &SessionParameters, session_key: Option<&str>, mut exchange: E) -> Result<(), MpcError>
+        where E: FnMut(&str, Vec<u8>) -> Result<Vec<u8>, MpcError> {
+        if session_key.is_none() {
+            eprintln!("handshake: no session_key is set, so the handshake with {} is not authenticated", peer_role);
+        }
+        let key = session_key.unwrap_or("").as_bytes();
+        let mut own_nonce = [0u8; 32];
+        rand::thread_rng().fill(&mut own_nonce);
+        let peer_nonce = MpcError::check_length(exchange("nonce", own_nonce.to_vec())?, 32, "handshake:nonce")?;
+
+        let hello = Hello {
+            role: own_role.to_string(),
+            parameters: parameters.clone(),
+            mac: hmac(key, &[own_role.as_bytes(), &peer_nonce, &own_nonce, &parameters.digest()])?.result().code().to_vec(),
+        };
+        let hello = serde_json::to_vec(&hello).map_err(|error| MpcError::Protocol(format!("failed to encode the handshake: {}", error)))?;
+        let received = exchange("hello", hello)?;
+        let peer: Hello = serde_json::from_slice(&received)
+            .map_err(|error| MpcError::Deserialize(format!("the handshake of {}: {}", peer_role, error)))?;
+        if peer.role != peer_role {
+            return Err(MpcError::Protocol(format!("expected {} at the other end, found {}", peer_role, peer.role)));
+        }
+        let expected = hmac(key, &[peer_role.as_bytes(), &own_nonce, &peer_nonce, &peer.parameters.digest()])?;
+        if expected.verify(&peer.mac).is_err() {
+            return Err(MpcError::Protocol(format!("the handshake of {} does not authenticate; do the session_key options agree?", peer_role)));
+        }
+        let conflicts = parameters.conflicts(&peer.parameters);
+        if !conflicts.is_empty() {
+            return Err(MpcError::Mismatch { peer: peer_role.to_string(), conflicts });
+        }
+        Ok(())
+    }
+
+    /* HMAC-SHA256 over the length-framed parts, to take the code of or verify a code with */
+    fn hmac(key: &[u8], parts: &[&[u8]]) -> Result<Hmac<Sha256>, MpcError> {
+        let mut mac = Hmac::<Sha256>::new_varkey(key).map_err(|_| MpcError::config("session_key", "not a valid HMAC key"))?;
+        for part in parts.iter() {
+            mac.input(&framed(part));
+        }
+        Ok(mac)
+    }
+
+    fn framed(bytes: &[u8]) -> Vec<u8> {
+        let mut frame = (bytes.len() as u64).to_le_bytes().to_vec();
+        frame.extend_from_slice(bytes);
+        frame
+    }
+}
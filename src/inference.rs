@@ -0,0 +1,154 @@
+pub mod inference {
+    use crate::computing_party::computing_party::ComputingParty;
+    use crate::model::model::{SecretSharedForest, SecretSharedTree};
+    use crate::multiplication::multiplication::batch_beaver_multiply;
+    use crate::ring::ring::{AdditiveShare, Z2, Zp};
+    use crate::field_change::field_change::change_binary_to_decimal_field;
+    use crate::bit_decomposition::bit_decomposition::batch_bit_decomposition;
+    use crate::protocol::protocol::arg_max;
+    use crate::error::error::MpcError;
+    use std::fs::File;
+    use std::io::{Write, BufWriter};
+    use std::time::SystemTime;
+
+    /* shares of the one-hot predicted class for every query held in ctx.dt_data */
+    pub fn predict(forest: &SecretSharedForest, ctx: &mut ComputingParty) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
+        println!("start predicting");
+        let now = SystemTime::now();
+        ctx.thread_hierarchy.push("predict".to_string());
+        let query_count = ctx.dt_data.instance_count;
+        let class_value_count = ctx.dt_data.class_value_count;
+        if forest.trees.len() as u64 >= ctx.dt_training.prime {
+            return Err(MpcError::Config { key: "prime".to_string(), problem: format!("must exceed the number of trees, {}, to count votes", forest.trees.len()) });
+        }
+
+        let mut vote_bits = Vec::new();
+        for (i, tree) in forest.trees.iter().enumerate() {
+            ctx.thread_hierarchy.push(format!("{}", i));
+            let votes = evaluate_tree(tree, ctx)?;
+            ctx.thread_hierarchy.pop();
+            vote_bits.extend(votes.into_iter().flatten());
+        }
+
+        //votes[q][c] = number of trees predicting class c for query q
+        let vote_values = change_binary_to_decimal_field(&vote_bits, ctx)?;
+        let ring = Zp::of(ctx);
+        let mut vote_counts = vec![AdditiveShare::zero(&ring); query_count * class_value_count];
+        for (i, vote) in vote_values.iter().enumerate() {
+            let key = i % (query_count * class_value_count);
+            vote_counts[key] = vote_counts[key].add(&ring, vote);
+        }
+        let vote_count_bits = batch_bit_decomposition(&vote_counts, ctx)?;
+
+        let mut result = Vec::new();
+        for q in 0..query_count {
+            ctx.thread_hierarchy.push(format!("query_{}", q));
+            result.push(arg_max(&vote_count_bits[q * class_value_count..(q + 1) * class_value_count], ctx)?);
+            ctx.thread_hierarchy.pop();
+        }
+        ctx.thread_hierarchy.pop();
+        println!("prediction completes in {}ms", now.elapsed().unwrap().as_millis());
+        Ok(result)
+    }
+
+    // A query reaches a node iff it follows every edge on the way down, so reach[child] = reach[parent] AND
+    // [query has value j for the parent's split attribute], one batch_beaver_multiply over Z_2 per level. Exactly
+    // one node at max_depth is reached, so XOR-ing reach * class over that level yields the tree's vote.
+    pub fn evaluate_tree(tree: &SecretSharedTree, ctx: &mut ComputingParty) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
+        ctx.thread_hierarchy.push("evaluate_tree".to_string());
+        let query_count = ctx.dt_data.instance_count;
+        let attribute_count = tree.attribute_count;
+        let attr_value_count = tree.attr_value_count;
+        let class_value_count = tree.class_value_count;
+        if attribute_count != ctx.dt_data.attribute_count {
+            return Err(MpcError::Config { key: "attribute_count".to_string(), problem: format!("the queries have {} attributes, the model {}", ctx.dt_data.attribute_count, attribute_count) });
+        }
+        if attr_value_count != ctx.dt_data.attr_value_count {
+            return Err(MpcError::Config { key: "attr_value_count".to_string(), problem: format!("the queries have {} values per attribute, the model {}", ctx.dt_data.attr_value_count, attr_value_count) });
+        }
+        let leaf_indices = tree.leaf_indices();
+        let internal_count = leaf_indices.start;
+
+        //edges[node][j][q] = [query q has value j for the split attribute of node]
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for node in tree.nodes[..internal_count].iter() {
+            for j in 0..attr_value_count {
+                for k in 0..attribute_count {
+                    x_list.extend(vec![node.split_attribute[k]; query_count]);
+                    y_list.extend(ctx.dt_data.attr_values_bytes[k][j].iter().cloned());
+                }
+            }
+        }
+        ctx.thread_hierarchy.push("compute_edges".to_string());
+        let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let mut edges = vec![vec![vec![AdditiveShare::zero(&Z2); query_count]; attr_value_count]; internal_count];
+        for (node, node_edges) in edges.iter_mut().enumerate() {
+            for (j, value_edges) in node_edges.iter_mut().enumerate() {
+                for k in 0..attribute_count {
+                    let offset = ((node * attr_value_count + j) * attribute_count + k) * query_count;
+                    for (edge, product) in value_edges.iter_mut().zip(products[offset..offset + query_count].iter()) {
+                        *edge ^= *product;
+                    }
+                }
+            }
+        }
+
+        let mut reach = vec![Vec::new(); tree.nodes.len()];
+        reach[0] = vec![AdditiveShare::public(&Z2, &1, ctx.asymmetric_bit); query_count];
+        for depth in 0..tree.max_depth {
+            let level: Vec<usize> = (0..internal_count).filter(|i| tree.nodes[*i].depth == depth).collect();
+            let mut x_list = Vec::new();
+            let mut y_list = Vec::new();
+            for node in level.iter() {
+                for value_edges in edges[*node].iter() {
+                    x_list.extend(reach[*node].iter().cloned());
+                    y_list.extend(value_edges.iter().cloned());
+                }
+            }
+            ctx.thread_hierarchy.push(format!("compute_path_{}", depth));
+            let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+            ctx.thread_hierarchy.pop();
+            let mut chunks = products.chunks(query_count.max(1));
+            for node in level.iter() {
+                for j in 0..attr_value_count {
+                    reach[tree.child_index(*node, j)] = chunks.next().unwrap().to_vec();
+                }
+            }
+        }
+
+        let mut x_list = Vec::new();
+        let mut y_list = Vec::new();
+        for leaf in leaf_indices.clone() {
+            for c in 0..class_value_count {
+                x_list.extend(reach[leaf].iter().cloned());
+                y_list.extend(vec![tree.nodes[leaf].class_index[c]; query_count]);
+            }
+        }
+        ctx.thread_hierarchy.push("compute_votes".to_string());
+        let products = batch_beaver_multiply(&Z2, &x_list, &y_list, ctx)?;
+        ctx.thread_hierarchy.pop();
+        let mut votes = vec![vec![AdditiveShare::zero(&Z2); class_value_count]; query_count];
+        for l in 0..leaf_indices.len() {
+            for c in 0..class_value_count {
+                for q in 0..query_count {
+                    votes[q][c] ^= products[(l * class_value_count + c) * query_count + q];
+                }
+            }
+        }
+        ctx.thread_hierarchy.pop();
+        Ok(votes)
+    }
+
+    pub fn save_predictions(predictions: &[Vec<AdditiveShare<Z2>>], path: &String) -> Result<(), MpcError> {
+        let context = || format!("failed to write prediction file {}", path);
+        let file = File::create(path).map_err(|error| MpcError::io(context(), error))?;
+        let mut writer = BufWriter::new(file);
+        for prediction in predictions.iter() {
+            let line: Vec<String> = prediction.iter().map(|x| x.value().to_string()).collect();
+            writeln!(writer, "{}", line.join(",")).map_err(|error| MpcError::io(context(), error))?;
+        }
+        writer.flush().map_err(|error| MpcError::io(context(), error))
+    }
+}
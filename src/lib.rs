@@ -1,3 +1,6 @@
+// every module is a file holding one `pub mod` of the same name
+#![allow(clippy::module_inception)]
+
 pub mod computing_party;
 pub mod ti;
 pub mod constants;
@@ -11,4 +14,25 @@ pub mod multiplication;
 pub mod dot_product;
 pub mod comparison;
 pub mod bit_decomposition;
-pub mod message;
\ No newline at end of file
+pub mod message;
+pub mod channel;
+pub mod wire;
+pub mod model;
+pub mod inference;
+pub mod share;
+pub mod reveal;
+pub mod share_source;
+pub mod simulation;
+pub mod plaintext;
+pub mod share_stream;
+pub mod seeded_shares;
+pub mod preprocessing;
+pub mod ot;
+pub mod paillier;
+pub mod budget;
+pub mod pipeline;
+pub mod error;
+pub mod settings;
+pub mod handshake;
+pub mod parameters;
+pub mod ring;
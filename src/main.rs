@@ -2,50 +2,127 @@ extern crate random_forest_rust;
 extern crate serde;
 
 use std::time::SystemTime;
-use std::{env, thread};
-use random_forest_rust::ti::ti::{TI, initialize_ti_context, run_ti_module};
+use std::env;
+use random_forest_rust::ti::ti::{initialize_ti_context, run_ti_module, ti_context_from_config};
+use random_forest_rust::preprocessing::preprocessing::write_preprocessed_shares;
 use random_forest_rust::computing_party::computing_party::initialize_party_context;
 use random_forest_rust::random_forest::random_forest;
+use random_forest_rust::model::model::SecretSharedForest;
+use random_forest_rust::inference::inference::save_predictions;
+use random_forest_rust::share::share::share_dataset;
+use random_forest_rust::reveal::reveal::{AdditiveModulus, reveal_binary_file, reveal_additive_file, reveal_bigint_file, reveal_model_file, format_forest};
+use random_forest_rust::simulation::simulation::simulate;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::settings::settings::{load_settings, RunConfig, TiConfig};
+use random_forest_rust::parameters::parameters::safe_prime;
+use std::str::FromStr;
 use num::BigUint;
-use std::net::{TcpListener, TcpStream, SocketAddr};
-use std::io::{Write, Read};
-use std::str;
-use serde::{Serialize, Deserialize, Serializer};
-use num::integer::*;
 
 
 fn main() {
-    run();
+    if let Err(error) = run() {
+        eprintln!("main:      {}", error);
+        std::process::exit(1);
+    }
 }
 
-fn run(){
-        let prefix = "main:      ";
+fn run() -> Result<(), MpcError> {
+    let prefix = "main:      ";
+
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        return Err(MpcError::Usage(format!("{} <settings file> | share <csv> <schema> <party0 output> <party1 output> | reveal <kind> <party0 file> <party1 file> [modulus] | simulate <settings file> | prime <bits>", &args[0])));
+    }
+    //reveal writes only the plaintext so that its output can be redirected
+    if args[1] == "reveal" {
+        return run_reveal(&args);
+    }
+    //so does prime, whose output is a big_int_prime for the settings
+    if args[1] == "prime" {
+        let bits = match args.get(2).map(|bits| bits.parse::<usize>()) {
+            Some(Ok(bits)) => bits,
+            _ => return Err(MpcError::Usage(format!("{} prime <bits>", &args[0]))),
+        };
+        println!("{}", safe_prime(&mut rand::thread_rng(), bits)?);
+        return Ok(());
+    }
 
     println!("{} runtime count starting...", &prefix);
     let now = SystemTime::now();
-    let args: Vec<String> = env::args().collect();
+    if args[1] == "share" {
+        if args.len() != 6 {
+            return Err(MpcError::Usage(format!("{} share <csv> <schema> <party0 output> <party1 output>", &args[0])));
+        }
+        share_dataset(&args[2], &args[3], &args[4], &args[5])?;
+        println!("{} shares written to {} and {}", &prefix, &args[4], &args[5]);
+        println!("{} total runtime = {:9} (ms)", &prefix, now.elapsed().unwrap().as_millis());
+        return Ok(());
+    }
+    if args[1] == "simulate" {
+        if args.len() != 3 {
+            return Err(MpcError::Usage(format!("{} simulate <settings file>", &args[0])));
+        }
+        let trees = simulate(&args[2])?;
+        println!("{} reconstructed forest:\n{}", &prefix, format_forest(&trees));
+        println!("{} total runtime = {:9} (ms)", &prefix, now.elapsed().unwrap().as_millis());
+        return Ok(());
+    }
     let settings_file = args[1].clone();
 
-    let mut settings = config::Config::default();
-    settings
-        .merge(config::File::with_name(&settings_file.as_str())).unwrap()
-        .merge(config::Environment::with_prefix("APP")).unwrap();
-
-    match settings.get_bool("ti") {
-        Ok(is_ti) => {
-            if is_ti {
-                let mut ti_context = initialize_ti_context(settings_file.clone());
-                run_ti_module(&mut ti_context);
-            } else {
-                let mut party_context = initialize_party_context(settings_file.clone());
-                random_forest::train(&mut party_context);
+    let settings = load_settings(&settings_file)?;
+
+    let run = RunConfig::from_settings(&settings)?;
+    if run.ti {
+        let config = TiConfig::from_settings(&settings)?;
+        match &config.preprocessed_shares_dir {
+            Some(dir) => {
+                let manifest = write_preprocessed_shares(&ti_context_from_config(&config)?, dir)?;
+                println!("{} shares for {} trees written to {}", &prefix, manifest.tree_count, dir);
+            }
+            None => {
+                let mut ti_context = initialize_ti_context(&settings, &config)?;
+                run_ti_module(&mut ti_context)?;
             }
         }
-        Err(error) => {
-            panic!(
-                format!("{} encountered a problem while parsing settings: {:?}", &prefix, error))
+    } else {
+        let mut party_context = initialize_party_context(&settings)?;
+        match (run.predict, run.model_path) {
+            (true, Some(model_path)) => {
+                let forest = SecretSharedForest::load(&model_path)?;
+                let predictions = random_forest::predict(&mut party_context, &forest)?;
+                save_predictions(&predictions, &party_context.output_path)?;
+                println!("{} prediction shares written to {}", &prefix, &party_context.output_path);
+            }
+            _ => {
+                let forest = random_forest::train(&mut party_context)?;
+                forest.save(&party_context.output_path)?;
+                println!("{} model share written to {}", &prefix, &party_context.output_path);
+            }
         }
-    };
+    }
     println!("{} total runtime = {:9} (ms)", &prefix, now.elapsed().unwrap().as_millis());
+    Ok(())
 }
 
+fn run_reveal(args: &[String]) -> Result<(), MpcError> {
+    let usage = || MpcError::Usage(format!("{} reveal <binary|additive|bigint|model> <party0 file> <party1 file> [modulus]", &args[0]));
+    if args.len() < 5 {
+        return Err(usage());
+    }
+    let modulus = || match args.get(5) {
+        Some(modulus) => Ok(modulus.clone()),
+        None => Err(MpcError::Usage(format!("{} reveal {} <party0 file> <party1 file> <modulus>", &args[0], &args[2]))),
+    };
+    let revealed = match args[2].as_str() {
+        "binary" => reveal_binary_file(&args[3], &args[4])?,
+        "additive" => reveal_additive_file(&args[3], &args[4], AdditiveModulus::parse(args.get(5).map(String::as_str))?)?,
+        "bigint" => {
+            let big_int_prime = BigUint::from_str(&modulus()?).map_err(|error| MpcError::config("modulus", error))?;
+            reveal_bigint_file(&args[3], &args[4], &big_int_prime)?
+        }
+        "model" => reveal_model_file(&args[3], &args[4])?,
+        _ => return Err(usage()),
+    };
+    println!("{}", revealed);
+    Ok(())
+}
@@ -1,106 +1,206 @@
 pub mod message {
     use std::collections::HashMap;
-    use serde::{Serialize, Deserialize, Serializer};
-    use std::sync::{Arc, Mutex};
-    use std::net::{TcpStream, TcpListener};
-    use std::io::{BufReader, BufRead, Read};
-    use std::time::SystemTime;
-    use threadpool::ThreadPool;
-    use std::thread;
-    use crate::computing_party::computing_party::ComputingParty;
-
-    pub const MAX_SEARCH_TIMES: u128 = 1000;
-
-    #[derive(Serialize, Deserialize, Debug)]
+    use std::sync::{Condvar, Mutex};
+    use std::io;
+    use std::time::{Duration, Instant};
+    use std::error::Error;
+    use std::fmt;
+    use num::bigint::BigUint;
+    use crate::error::error::MpcError;
+
+    /* what a message carries; the variant is the type tag of the wire format */
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Payload {
+        /* binary shares, one bit each, bit-packed on the wire */
+        Bits(Vec<u8>),
+        /* shares modulo 2^64 or a u64 prime */
+        U64s(Vec<u64>),
+        /* shares modulo big_int_prime */
+        BigUints(Vec<BigUint>),
+        /* anything else, e.g. seeds and commitments */
+        Bytes(Vec<u8>),
+    }
+
+    // The other party sends the same variant for the same message id, so a mismatch means the
+    // parties disagree about the protocol.
+    impl Payload {
+        fn kind(&self) -> &'static str {
+            match self {
+                Payload::Bits(_) => "bit",
+                Payload::U64s(_) => "u64",
+                Payload::BigUints(_) => "big integer",
+                Payload::Bytes(_) => "byte",
+            }
+        }
+
+        fn mismatch(&self, expected: &str) -> MpcError {
+            MpcError::Deserialize(format!("a {} payload, received a {} payload", expected, self.kind()))
+        }
+
+        pub fn into_bits(self) -> Result<Vec<u8>, MpcError> {
+            match self {
+                Payload::Bits(bits) => Ok(bits),
+                other => Err(other.mismatch("bit")),
+            }
+        }
+
+        pub fn into_u64s(self) -> Result<Vec<u64>, MpcError> {
+            match self {
+                Payload::U64s(values) => Ok(values),
+                other => Err(other.mismatch("u64")),
+            }
+        }
+
+        pub fn into_biguints(self) -> Result<Vec<BigUint>, MpcError> {
+            match self {
+                Payload::BigUints(values) => Ok(values),
+                other => Err(other.mismatch("big integer")),
+            }
+        }
+
+        pub fn into_bytes(self) -> Result<Vec<u8>, MpcError> {
+            match self {
+                Payload::Bytes(bytes) => Ok(bytes),
+                other => Err(other.mismatch("byte")),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
     pub struct RFMessage {
         pub message_id: String,
-        pub message_content: String,
+        pub payload: Payload,
     }
 
-    pub struct MessageManager {
-        pub map: HashMap<String, RFMessage>,
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum MessageError {
+        /* the message did not arrive within the manager's timeout */
+        Timeout { message_id: String, waited: Duration },
+        /* the other party hung up before sending the message */
+        Disconnected { message_id: String },
+        /* a second message arrived under an id that was still waiting to be received */
+        Duplicate { message_id: String },
     }
 
-    impl Clone for RFMessage {
-        fn clone(&self) -> Self {
-            RFMessage {
-                message_id: self.message_id.clone(),
-                message_content: self.message_content.clone(),
+    impl fmt::Display for MessageError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                MessageError::Timeout { message_id, waited } =>
+                    write!(f, "message {} did not arrive within {} ms", message_id, waited.as_millis()),
+                MessageError::Disconnected { message_id } =>
+                    write!(f, "the other party disconnected before sending message {}", message_id),
+                MessageError::Duplicate { message_id } =>
+                    write!(f, "message {} was received twice", message_id),
             }
         }
     }
 
+    impl Error for MessageError {}
 
-    impl MessageManager {
-        pub fn add_message(&mut self, message: &RFMessage) -> Result<&'static str, &'static str> {
-            self.map.insert(message.message_id.clone(), message.clone());
-            Ok("Successfully add the message")
+    struct Mailbox {
+        map: HashMap<String, RFMessage>,
+        closed: bool,
+        /* why the connection closed, unless the other party simply hung up */
+        failure: Option<Failure>,
+    }
+
+    /* the error every receiver still waiting is told; MpcError itself cannot be cloned */
+    #[derive(Clone, Debug)]
+    enum Failure {
+        Abort(MessageError),
+        Io { context: String, kind: io::ErrorKind, problem: String },
+        Deserialize(String),
+        Protocol(String),
+    }
+
+    impl Failure {
+        fn of(error: MpcError) -> Failure {
+            match error {
+                MpcError::PeerAbort(error) => Failure::Abort(error),
+                MpcError::Io { context, error } => Failure::Io { context, kind: error.kind(), problem: error.to_string() },
+                MpcError::Deserialize(problem) => Failure::Deserialize(problem),
+                error => Failure::Protocol(error.to_string()),
+            }
+        }
+
+        fn error(&self) -> MpcError {
+            match self.clone() {
+                Failure::Abort(error) => MpcError::PeerAbort(error),
+                Failure::Io { context, kind, problem } => MpcError::Io { context, error: io::Error::new(kind, problem) },
+                Failure::Deserialize(problem) => MpcError::Deserialize(problem),
+                Failure::Protocol(problem) => MpcError::Protocol(problem),
+            }
         }
     }
 
-//    pub fn search_pop_message(ctx: &mut ComputingParty, message_id: String) -> Result<RFMessage, &'static str> {
-//        println!("querying {}", &message_id);
-//        let in_stream = ctx.in_stream.try_clone().unwrap();
-//        let manager = Arc::clone(&ctx.message_manager);
-//        let mut manager_content = manager.lock().unwrap();
-//        if (*manager_content).map.contains_key(&message_id) {
-//            let mut message = (*manager_content).map.remove(&message_id);
-//            println!("found    {}", &message_id);
-//            return Ok(message.unwrap());
-//        }
-//
-//        let mut manager_arc = Arc::clone(&manager);
-//        let in_stream = in_stream.try_clone().unwrap();
-//        let mut reader = BufReader::new(&in_stream);
-//        let mut line = String::new();
-//        reader.read_line(&mut line);
-//        let message: RFMessage = serde_json::from_str(&line).unwrap();
-//        if message.message_id.eq(&message_id) {
-//            println!("found    {}", &message_id);
-//            return Ok(message);
-//        }
-//        let mut manager = manager_arc.lock().unwrap();
-//        (*manager).add_message(&message);
-//
-//        search_pop_message(ctx, message_id);
-//
-//        Err("Cannot find the message")
-//    }
-
-
-    pub fn search_pop_message(ctx: &mut ComputingParty, message_id: String) -> Result<RFMessage, &'static str> {
-        println!("querying {}", &message_id);
-        let mut manager_content = ctx.message_manager.lock().unwrap();
-        loop{
-            if (*manager_content).map.contains_key(&message_id){
-                break;
+    // Messages that arrived from the other party but have not been asked for yet. Receivers wait on
+    // a condition variable that is notified whenever a message is added or the connection closes.
+    pub struct MessageManager {
+        mailbox: Mutex<Mailbox>,
+        arrived: Condvar,
+        /* how long a receiver waits for its message; None waits indefinitely */
+        pub timeout: Option<Duration>,
+    }
+
+    impl MessageManager {
+        pub fn new(timeout: Option<Duration>) -> MessageManager {
+            MessageManager {
+                mailbox: Mutex::new(Mailbox { map: HashMap::new(), closed: false, failure: None }),
+                arrived: Condvar::new(),
+                timeout,
             }
         }
-        let mut message = (*manager_content).map.remove(&message_id);
-        println!("found    {}", &message_id);
-        return Ok(message.unwrap());
-//        if (*manager_content).map.contains_key(&message_id) {
-//            let mut message = (*manager_content).map.remove(&message_id);
-//            println!("found    {}", &message_id);
-//            return Ok(message.unwrap());
-//        }else{
-//
-//        }
-        Err("asd")
+
+        pub fn add_message(&self, message: &RFMessage) -> Result<(), MessageError> {
+            let mut mailbox = self.mailbox.lock().unwrap();
+            if mailbox.map.contains_key(&message.message_id) {
+                return Err(MessageError::Duplicate { message_id: message.message_id.clone() });
+            }
+            mailbox.map.insert(message.message_id.clone(), message.clone());
+            self.arrived.notify_all();
+            Ok(())
+        }
+
+        /* no more messages will arrive; receivers still waiting fail with MessageError::Disconnected */
+        pub fn close(&self) {
+            self.mailbox.lock().unwrap().closed = true;
+            self.arrived.notify_all();
+        }
+
+        /* no more messages will arrive because of error, which receivers still waiting fail with */
+        pub fn fail(&self, error: MpcError) {
+            let mut mailbox = self.mailbox.lock().unwrap();
+            if mailbox.failure.is_none() {
+                mailbox.failure = Some(Failure::of(error));
+            }
+            mailbox.closed = true;
+            self.arrived.notify_all();
+        }
     }
 
-//    pub fn setup_message_manager(in_stream: &TcpStream,manager:&Arc<Mutex<MessageManager>>) {
-//        let mut manager_copied = Arc::clone(manager);
-//        thread::spawn(move || {
-//            let in_stream = in_stream.try_clone().unwrap();
-//            let mut reader = BufReader::new(&stream);
-//            for line in reader.lines(){
-//                let message: RFMessage = serde_json::from_str(&line.unwrap()).unwrap();
-//                println!("{} received", &message.message_id);
-//                let mut manager = manager_copied.lock().unwrap();
-//                (*manager).add_message(&message);
-//            }
-//
-//        });
-//    }
+    pub fn search_pop_message(message_manager: &MessageManager, message_id: &str) -> Result<RFMessage, MpcError> {
+        let start = Instant::now();
+        let mut mailbox = message_manager.mailbox.lock().unwrap();
+        loop {
+            if let Some(message) = mailbox.map.remove(message_id) {
+                return Ok(message);
+            }
+            if mailbox.closed {
+                return Err(match &mailbox.failure {
+                    Some(failure) => failure.error(),
+                    None => MpcError::PeerAbort(MessageError::Disconnected { message_id: message_id.to_string() }),
+                });
+            }
+            mailbox = match message_manager.timeout {
+                Some(timeout) => {
+                    let waited = start.elapsed();
+                    if waited >= timeout {
+                        return Err(MpcError::PeerAbort(MessageError::Timeout { message_id: message_id.to_string(), waited }));
+                    }
+                    message_manager.arrived.wait_timeout(mailbox, timeout - waited).unwrap().0
+                }
+                None => message_manager.arrived.wait(mailbox).unwrap(),
+            };
+        }
+    }
 }
\ No newline at end of file
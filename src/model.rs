@@ -0,0 +1,99 @@
+pub mod model {
+    use crate::error::error::MpcError;
+    use crate::ring::ring::{AdditiveShare, Z2};
+    use serde::{Serialize, Deserialize};
+    use std::fs::File;
+    use std::io::{BufReader, BufWriter, Write};
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct SecretSharedNode {
+        pub index: usize,
+        pub depth: usize,
+        /* one-hot over attributes, all zero below max_depth */
+        pub split_attribute: Vec<AdditiveShare<Z2>>,
+        pub is_leaf: AdditiveShare<Z2>,
+        /* one-hot over class values */
+        pub class_index: Vec<AdditiveShare<Z2>>,
+    }
+
+    // Nodes of the complete attr_value_count-ary tree in breadth-first order: the children of node i
+    // are i * attr_value_count + 1 ..= i * attr_value_count + attr_value_count. Every field holds
+    // this party's XOR share only, so the tree shape is public but the splits and classes are not.
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct SecretSharedTree {
+        pub party_id: u8,
+        pub attribute_count: usize,
+        pub attr_value_count: usize,
+        pub class_value_count: usize,
+        pub max_depth: usize,
+        pub nodes: Vec<SecretSharedNode>,
+        /* the joint seed of random_forest::tree_sample, which both parties hold in the clear */
+        #[serde(default)]
+        pub seed: Option<[u8; 32]>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct SecretSharedForest {
+        pub party_id: u8,
+        pub trees: Vec<SecretSharedTree>,
+    }
+
+    impl SecretSharedTree {
+        pub fn new(party_id: u8, attribute_count: usize, attr_value_count: usize, class_value_count: usize, max_depth: usize) -> SecretSharedTree {
+            let mut nodes = Vec::new();
+            let mut level_size = 1;
+            for depth in 0..max_depth + 1 {
+                for _ in 0..level_size {
+                    nodes.push(SecretSharedNode {
+                        index: nodes.len(),
+                        depth,
+                        split_attribute: vec![AdditiveShare::zero(&Z2); attribute_count],
+                        is_leaf: AdditiveShare::zero(&Z2),
+                        class_index: vec![AdditiveShare::zero(&Z2); class_value_count],
+                    });
+                }
+                level_size *= attr_value_count;
+            }
+            SecretSharedTree {
+                party_id,
+                attribute_count,
+                attr_value_count,
+                class_value_count,
+                max_depth,
+                nodes,
+                seed: None,
+            }
+        }
+
+        pub fn child_index(&self, node_index: usize, attr_value: usize) -> usize {
+            node_index * self.attr_value_count + attr_value + 1
+        }
+
+        /* indices of the nodes at max_depth, left to right */
+        pub fn leaf_indices(&self) -> std::ops::Range<usize> {
+            let first = self.nodes.iter().position(|node| node.depth == self.max_depth).unwrap_or(self.nodes.len());
+            first..self.nodes.len()
+        }
+    }
+
+    impl SecretSharedForest {
+        pub fn new(party_id: u8) -> SecretSharedForest {
+            SecretSharedForest {
+                party_id,
+                trees: Vec::new(),
+            }
+        }
+
+        pub fn save(&self, path: &String) -> Result<(), MpcError> {
+            let file = File::create(path).map_err(|error| MpcError::io(format!("failed to create model file {}", path), error))?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, self).map_err(|error| MpcError::io(format!("failed to write model file {}", path), error.into()))?;
+            writer.flush().map_err(|error| MpcError::io(format!("failed to write model file {}", path), error))
+        }
+
+        pub fn load(path: &String) -> Result<SecretSharedForest, MpcError> {
+            let file = File::open(path).map_err(|error| MpcError::io(format!("failed to open model file {}", path), error))?;
+            serde_json::from_reader(BufReader::new(file)).map_err(|error| MpcError::Deserialize(format!("model file {}: {}", path, error)))
+        }
+    }
+}
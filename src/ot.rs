@@ -0,0 +1,444 @@
+pub mod ot {
+    use crate::channel::channel::Channel;
+    use crate::decision_tree::decision_tree::DecisionTreeShares;
+    use crate::error::error::MpcError;
+    use crate::message::message::{Payload, RFMessage};
+    use crate::parameters::parameters::STATISTICAL_SECURITY;
+    use crate::ring::ring::Triple;
+    use crate::share_source::share_source::ShareCounts;
+    use num::bigint::{BigUint, RandBigInt};
+    use num::{Num, One, Zero};
+    use rand::prng::chacha::ChaChaRng;
+    use rand::{Rng, SeedableRng};
+    use sha2::{Digest, Sha256};
+    use std::num::Wrapping;
+    use std::sync::{Arc, Mutex};
+
+    // Beaver triples generated by the two computing parties alone, without the TI. A session runs
+    // SECURITY_PARAMETER base OTs in each direction once (Chou-Orlandi over a prime order group) and
+    // then extends them IKNP-style into as many random OTs as needed. Every extension runs both
+    // directions at once, so each party is the sender of one instance and the receiver of the other,
+    // and every message is a symmetric exchange. Security holds against semi-honest parties only.
+    pub const SECURITY_PARAMETER: usize = 128;
+    /* random OTs per extension; bounds the memory and message size of one round */
+    pub const OT_BATCH_SIZE: usize = 1 << 16;
+
+    /* RFC 3526 group 14: a 2048-bit safe prime; 2 generates its subgroup of prime order (p - 1) / 2 */
+    const GROUP_PRIME: &str = "\
+        FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DD\
+        EF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED\
+        EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F\
+        83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B\
+        E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA0510\
+        15728E5A8AACAA68FFFFFFFFFFFFFFFF";
+    const GROUP_GENERATOR: u32 = 2;
+    /* short exponents; discrete logarithms in the group are no easier than 2^128 work anyway */
+    const EXPONENT_BITS: usize = 256;
+
+    type OtKey = [u8; 32];
+    type OtKeyPair = (OtKey, OtKey);
+
+    pub struct OtSession {
+        party_id: u8,
+        channel: Arc<dyn Channel>,
+        message_count: u64,
+        /* extended OTs so far in each direction; indexes the key derivation */
+        ot_count: u64,
+        /* the extension sender's secret choices s and the base OT keys it chose with them */
+        sender_choices: [u64; 2],
+        sender_prgs: Vec<ChaChaRng>,
+        /* the extension receiver's pairs of base OT keys */
+        receiver_prgs: Vec<(ChaChaRng, ChaChaRng)>,
+    }
+
+    fn base_key(index: usize, element: &BigUint) -> OtKey {
+        let mut hasher = Sha256::new();
+        hasher.input(b"base");
+        hasher.input((index as u64).to_le_bytes());
+        hasher.input(element.to_bytes_le());
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&hasher.result());
+        key
+    }
+
+    /* the key of extended OT index in the instance whose sender is sender_id */
+    fn extension_key(sender_id: u8, index: u64, row: [u64; 2]) -> OtKey {
+        let mut hasher = Sha256::new();
+        hasher.input([sender_id]);
+        hasher.input(index.to_le_bytes());
+        hasher.input(row[0].to_le_bytes());
+        hasher.input(row[1].to_le_bytes());
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&hasher.result());
+        key
+    }
+
+    fn random_words(rng: &mut ChaChaRng, word_count: usize) -> Vec<u64> {
+        (0..word_count).map(|_| rng.gen()).collect()
+    }
+
+    fn pack_choices(choices: &[u8]) -> Vec<u64> {
+        let mut words = vec![0u64; choices.len().div_ceil(64)];
+        for (i, choice) in choices.iter().enumerate() {
+            words[i / 64] |= ((choice & 1) as u64) << (i % 64);
+        }
+        words
+    }
+
+    /* column j of the SECURITY_PARAMETER x count bit matrix becomes bit j of every row */
+    fn transpose(columns: &[Vec<u64>], count: usize) -> Vec<[u64; 2]> {
+        let mut rows = vec![[0u64; 2]; count];
+        for (j, column) in columns.iter().enumerate() {
+            for (w, word) in column.iter().enumerate() {
+                let mut bits = *word;
+                while bits != 0 {
+                    let i = w * 64 + bits.trailing_zeros() as usize;
+                    if i < count {
+                        rows[i][j / 64] |= 1 << (j % 64);
+                    }
+                    bits &= bits - 1;
+                }
+            }
+        }
+        rows
+    }
+
+    impl OtSession {
+        /* the base OTs; both parties call this at the same point of the conversation */
+        pub fn setup(channel: Arc<dyn Channel>, party_id: u8) -> Result<OtSession, MpcError> {
+            let prime = BigUint::from_str_radix(GROUP_PRIME, 16).unwrap();
+            let generator = BigUint::from(GROUP_GENERATOR);
+            let mut session = OtSession {
+                party_id,
+                channel,
+                message_count: 0,
+                ot_count: 0,
+                sender_choices: [0; 2],
+                sender_prgs: Vec::new(),
+                receiver_prgs: Vec::new(),
+            };
+            let mut rng = rand::thread_rng();
+
+            // base OT sender for the instance this party receives in
+            let a = rng.gen_biguint(EXPONENT_BITS);
+            let own_a = generator.modpow(&a, &prime);
+            let other_a = session.exchange("base_a", Payload::BigUints(vec![own_a.clone()]))?.into_biguints()?;
+            let other_a = MpcError::check_length(other_a, 1, "ot:0:base_a")?.remove(0);
+            if other_a <= BigUint::one() || other_a >= prime {
+                return Err(MpcError::Protocol("the other party sent an invalid base OT message".to_string()));
+            }
+
+            // base OT receiver, choosing with s, for the instance this party sends in
+            session.sender_choices = [rng.gen(), rng.gen()];
+            let b: Vec<BigUint> = (0..SECURITY_PARAMETER).map(|_| rng.gen_biguint(EXPONENT_BITS)).collect();
+            let own_b = (0..SECURITY_PARAMETER).map(|j| {
+                let g_b = generator.modpow(&b[j], &prime);
+                if session.sender_choice(j) == 1 { g_b * &other_a % &prime } else { g_b }
+            }).collect();
+            let other_b = session.exchange("base_b", Payload::BigUints(own_b))?.into_biguints()?;
+            let other_b = MpcError::check_length(other_b, SECURITY_PARAMETER, "ot:1:base_b")?;
+
+            session.sender_prgs = (0..SECURITY_PARAMETER)
+                .map(|j| ChaChaRng::from_seed(base_key(j, &other_a.modpow(&b[j], &prime))))
+                .collect();
+            let a_inverse = own_a.modpow(&a, &prime).modpow(&(&prime - BigUint::from(2u8)), &prime);
+            session.receiver_prgs = other_b.iter().enumerate().map(|(j, other_b)| {
+                let shared0 = other_b.modpow(&a, &prime);
+                let shared1 = &shared0 * &a_inverse % &prime;
+                (ChaChaRng::from_seed(base_key(j, &shared0)), ChaChaRng::from_seed(base_key(j, &shared1)))
+            }).collect();
+            Ok(session)
+        }
+
+        fn sender_choice(&self, j: usize) -> u8 {
+            ((self.sender_choices[j / 64] >> (j % 64)) & 1) as u8
+        }
+
+        fn exchange(&mut self, label: &str, payload: Payload) -> Result<Payload, MpcError> {
+            let message = RFMessage {
+                message_id: format!("ot:{}:{}", self.message_count, label),
+                payload,
+            };
+            self.message_count += 1;
+            Ok(self.channel.exchange(&message, self.party_id)?.payload)
+        }
+
+        // Random OTs in both directions: this party receives the keys its choices pick and, as the
+        // other party's counterpart, holds both keys of every pair the other party picks from.
+        fn extend(&mut self, choices: &[u8]) -> Result<(Vec<OtKeyPair>, Vec<OtKey>), MpcError> {
+            let count = choices.len();
+            let word_count = count.div_ceil(64);
+            let packed_choices = pack_choices(choices);
+
+            let mut t_columns = Vec::with_capacity(SECURITY_PARAMETER);
+            let mut u_columns = Vec::with_capacity(SECURITY_PARAMETER * word_count);
+            for (prg0, prg1) in self.receiver_prgs.iter_mut() {
+                let t = random_words(prg0, word_count);
+                let masks = random_words(prg1, word_count);
+                u_columns.extend((0..word_count).map(|w| t[w] ^ masks[w] ^ packed_choices[w]));
+                t_columns.push(t);
+            }
+            let other_u = self.exchange("extend", Payload::U64s(u_columns))?.into_u64s()?;
+            let other_u = MpcError::check_length(other_u, SECURITY_PARAMETER * word_count, &format!("ot:{}:extend", self.message_count - 1))?;
+
+            let mut q_columns = Vec::with_capacity(SECURITY_PARAMETER);
+            for j in 0..SECURITY_PARAMETER {
+                let choice = self.sender_choice(j);
+                let mut q = random_words(&mut self.sender_prgs[j], word_count);
+                if choice == 1 {
+                    for (w, word) in q.iter_mut().enumerate() {
+                        *word ^= other_u[j * word_count + w];
+                    }
+                }
+                q_columns.push(q);
+            }
+
+            let first_index = self.ot_count;
+            self.ot_count += count as u64;
+            let s = self.sender_choices;
+            let pairs = transpose(&q_columns, count).into_iter().enumerate()
+                .map(|(i, q)| (extension_key(self.party_id, first_index + i as u64, q),
+                               extension_key(self.party_id, first_index + i as u64, [q[0] ^ s[0], q[1] ^ s[1]])))
+                .collect();
+            let chosen = transpose(&t_columns, count).into_iter().enumerate()
+                .map(|(i, t)| extension_key(1 - self.party_id, first_index + i as u64, t))
+                .collect();
+            Ok((pairs, chosen))
+        }
+
+        // Each party picks b at random and a as the XOR of its pair of keys' low bits, as the OT
+        // sender, so its counterpart's chosen key already shares the cross term a * b' between them.
+        pub fn binary_triples(&mut self, count: usize) -> Result<Vec<(u8, u8, u8)>, MpcError> {
+            let mut triples = Vec::with_capacity(count);
+            let mut rng = rand::thread_rng();
+            while triples.len() < count {
+                let batch = (count - triples.len()).min(OT_BATCH_SIZE);
+                let b: Vec<u8> = (0..batch).map(|_| rng.gen::<bool>() as u8).collect();
+                let (pairs, chosen) = self.extend(&b)?;
+                for i in 0..batch {
+                    let (x0, x1) = (pairs[i].0[0] & 1, pairs[i].1[0] & 1);
+                    let a = x0 ^ x1;
+                    triples.push((a, b[i], (a & b[i]) ^ x0 ^ (chosen[i][0] & 1)));
+                }
+            }
+            Ok(triples)
+        }
+
+        pub fn additive_triples(&mut self, count: usize, modulus: Modulus) -> Result<Vec<Triple<Wrapping<u64>>>, MpcError> {
+            Ok(self.arithmetic_triples(&modulus, count)?.into_iter()
+                .map(|(a, b, c)| (Wrapping(a), Wrapping(b), Wrapping(c)))
+                .collect())
+        }
+
+        pub fn bigint_triples(&mut self, count: usize, big_int_prime: &BigUint) -> Result<Vec<Triple<BigUint>>, MpcError> {
+            self.arithmetic_triples(&BigModulus(big_int_prime.clone()), count)
+        }
+
+        // Gilboa multiplication of this party's a with the other party's b', one OT per bit of a: the
+        // sender offers (m, m + 2^i b') through the keys of OT i, the receiver picks with bit i of a,
+        // and the picks sum to a * b' minus the sender's sum of m.
+        fn arithmetic_triples<R: OtRing>(&mut self, ring: &R, count: usize) -> Result<Vec<Triple<R::Element>>, MpcError> {
+            let bit_length = ring.bit_length();
+            let batch_size = (OT_BATCH_SIZE / bit_length).max(1);
+            let mut triples = Vec::with_capacity(count);
+            let mut rng = rand::thread_rng();
+            while triples.len() < count {
+                let batch = (count - triples.len()).min(batch_size);
+                let a: Vec<R::Element> = (0..batch).map(|_| ring.random(&mut rng)).collect();
+                let b: Vec<R::Element> = (0..batch).map(|_| ring.random(&mut rng)).collect();
+                let choices: Vec<u8> = a.iter().flat_map(|a| (0..bit_length).map(move |i| ring.bit(a, i))).collect();
+                let (pairs, chosen) = self.extend(&choices)?;
+
+                let mut c: Vec<R::Element> = (0..batch).map(|t| ring.multiply(&a[t], &b[t])).collect();
+                let mut corrections = Vec::with_capacity(batch * bit_length);
+                for t in 0..batch {
+                    for i in 0..bit_length {
+                        let (key0, key1) = &pairs[t * bit_length + i];
+                        let m = ring.key_element(key0);
+                        corrections.push(ring.subtract(&ring.add(&m, &ring.shift(&b[t], i)), &ring.key_element(key1)));
+                        c[t] = ring.subtract(&c[t], &m);
+                    }
+                }
+                let other_corrections = ring.payload_elements(self.exchange("gilboa", ring.to_payload(corrections))?)?;
+                let other_corrections = MpcError::check_length(other_corrections, batch * bit_length, &format!("ot:{}:gilboa", self.message_count - 1))?;
+                for (t, c) in c.iter_mut().enumerate() {
+                    for i in 0..bit_length {
+                        let index = t * bit_length + i;
+                        let mut picked = ring.key_element(&chosen[index]);
+                        if choices[index] == 1 {
+                            picked = ring.add(&picked, &other_corrections[index]);
+                        }
+                        *c = ring.add(c, &picked);
+                    }
+                }
+                triples.extend(a.into_iter().zip(b).zip(c).map(|((a, b), c)| (a, b, c)));
+            }
+            Ok(triples)
+        }
+
+        // One tree's worth of shares in place of the TI's. Equality shares need no interaction: each
+        // party picks its share at random, and the sum is zero only with probability 1 / big_int_prime.
+        pub fn dt_shares(&mut self, prime: u64, big_int_prime: &BigUint, counts: &ShareCounts) -> Result<DecisionTreeShares, MpcError> {
+            let additive_triples = self.additive_triples(counts.additive, Modulus::Prime(prime))?;
+            let additive_z64_triples = self.additive_triples(counts.additive_z64, Modulus::TwoToThe64)?;
+            let additive_bigint_triples = self.bigint_triples(counts.additive_bigint, big_int_prime)?;
+            let binary_triples = self.binary_triples(counts.binary)?;
+            let mut rng = rand::thread_rng();
+            let equality_shares = (0..counts.equality).map(|_| rng.gen_biguint_below(big_int_prime)).collect();
+            Ok(DecisionTreeShares {
+                additive_triples: Arc::new(additive_triples),
+                additive_z64_triples: Arc::new(additive_z64_triples),
+                additive_bigint_triples: Arc::new(additive_bigint_triples),
+                binary_triples: Arc::new(binary_triples),
+                equality_shares: Arc::new(equality_shares),
+                current_additive_index: Arc::new(Mutex::new(0)),
+                current_additive_z64_index: Arc::new(Mutex::new(0)),
+                current_additive_bigint_index: Arc::new(Mutex::new(0)),
+                current_equality_index: Arc::new(Mutex::new(0)),
+                current_binary_index: Arc::new(Mutex::new(0)),
+            })
+        }
+    }
+
+    /* the rings Gilboa multiplication runs in */
+    trait OtRing {
+        type Element: Clone;
+        fn bit_length(&self) -> usize;
+        fn bit(&self, x: &Self::Element, i: usize) -> u8;
+        fn random<G: Rng>(&self, rng: &mut G) -> Self::Element;
+        /* an element derived from an OT key, uniform up to a negligible bias */
+        fn key_element(&self, key: &OtKey) -> Self::Element;
+        fn add(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+        fn subtract(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+        fn multiply(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+        /* 2^i x */
+        fn shift(&self, x: &Self::Element, i: usize) -> Self::Element;
+        fn to_payload(&self, values: Vec<Self::Element>) -> Payload;
+        fn payload_elements(&self, payload: Payload) -> Result<Vec<Self::Element>, MpcError>;
+    }
+
+    /* the moduli of the u64 additive shares */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Modulus {
+        TwoToThe64,
+        Prime(u64),
+    }
+
+    impl OtRing for Modulus {
+        type Element = u64;
+
+        fn bit_length(&self) -> usize {
+            match self {
+                Modulus::TwoToThe64 => 64,
+                Modulus::Prime(prime) => 64 - prime.leading_zeros() as usize,
+            }
+        }
+
+        fn bit(&self, x: &u64, i: usize) -> u8 {
+            ((x >> i) & 1) as u8
+        }
+
+        fn random<G: Rng>(&self, rng: &mut G) -> u64 {
+            match self {
+                Modulus::TwoToThe64 => rng.gen(),
+                Modulus::Prime(prime) => rng.gen_range(0, *prime),
+            }
+        }
+
+        /* the low 64 key bits are already uniform mod 2^64; 128 bits reduced mod a prime below 2^64 */
+        fn key_element(&self, key: &OtKey) -> u64 {
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(&key[..16]);
+            let value = u128::from_le_bytes(bytes);
+            match self {
+                Modulus::TwoToThe64 => value as u64,
+                Modulus::Prime(prime) => (value % *prime as u128) as u64,
+            }
+        }
+
+        fn add(&self, x: &u64, y: &u64) -> u64 {
+            match self {
+                Modulus::TwoToThe64 => x.wrapping_add(*y),
+                Modulus::Prime(prime) => ((*x as u128 + *y as u128) % *prime as u128) as u64,
+            }
+        }
+
+        fn subtract(&self, x: &u64, y: &u64) -> u64 {
+            match self {
+                Modulus::TwoToThe64 => x.wrapping_sub(*y),
+                Modulus::Prime(prime) => ((*x as u128 + *prime as u128 - *y as u128) % *prime as u128) as u64,
+            }
+        }
+
+        fn multiply(&self, x: &u64, y: &u64) -> u64 {
+            match self {
+                Modulus::TwoToThe64 => x.wrapping_mul(*y),
+                Modulus::Prime(prime) => ((*x as u128 * *y as u128) % *prime as u128) as u64,
+            }
+        }
+
+        fn shift(&self, x: &u64, i: usize) -> u64 {
+            match self {
+                Modulus::TwoToThe64 => x.wrapping_shl(i as u32),
+                Modulus::Prime(prime) => (((*x as u128) << i) % *prime as u128) as u64,
+            }
+        }
+
+        fn to_payload(&self, values: Vec<u64>) -> Payload {
+            Payload::U64s(values)
+        }
+
+        fn payload_elements(&self, payload: Payload) -> Result<Vec<u64>, MpcError> {
+            payload.into_u64s()
+        }
+    }
+
+    /* big_int_prime */
+    struct BigModulus(BigUint);
+
+    impl OtRing for BigModulus {
+        type Element = BigUint;
+
+        fn bit_length(&self) -> usize {
+            self.0.bits()
+        }
+
+        fn bit(&self, x: &BigUint, i: usize) -> u8 {
+            if ((x >> i) & BigUint::one()).is_zero() { 0 } else { 1 }
+        }
+
+        fn random<G: Rng>(&self, rng: &mut G) -> BigUint {
+            rng.gen_biguint_below(&self.0)
+        }
+
+        // A 256-bit key reduced directly would be far from uniform once the prime nears 2^256, and the
+        // Gilboa corrections would leak the receiver's bits; the key seeds STATISTICAL_SECURITY extra bits.
+        fn key_element(&self, key: &OtKey) -> BigUint {
+            ChaChaRng::from_seed(*key).gen_biguint(self.0.bits() + STATISTICAL_SECURITY) % &self.0
+        }
+
+        fn add(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            (x + y) % &self.0
+        }
+
+        fn subtract(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            (x + &self.0 - y) % &self.0
+        }
+
+        fn multiply(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            x * y % &self.0
+        }
+
+        fn shift(&self, x: &BigUint, i: usize) -> BigUint {
+            (x << i) % &self.0
+        }
+
+        fn to_payload(&self, values: Vec<BigUint>) -> Payload {
+            Payload::BigUints(values)
+        }
+
+        fn payload_elements(&self, payload: Payload) -> Result<Vec<BigUint>, MpcError> {
+            payload.into_biguints()
+        }
+    }
+}
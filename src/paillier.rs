@@ -0,0 +1,190 @@
+pub mod paillier {
+    use crate::channel::channel::Channel;
+    use crate::error::error::MpcError;
+    use crate::message::message::{Payload, RFMessage};
+    use crate::parameters::parameters::{random_prime, STATISTICAL_SECURITY};
+    use num::bigint::{BigUint, RandBigInt};
+    use num::{Integer, One, Zero};
+    use rand::Rng;
+    use std::sync::Arc;
+
+    // Shares of products computed under Paillier encryption, for the BigUint protocols. Party 0
+    // holds the key pair and sends its factors encrypted; party 1 raises each ciphertext to its own
+    // factor, adds an encrypted mask that is statistically hiding over the integers, and keeps the
+    // negated mask as its share. Party 0's decryption, reduced mod the prime, is the other share.
+    // Security holds against semi-honest parties only.
+    pub const DEFAULT_MODULUS_BITS: usize = 2048;
+    /* products per round trip; bounds the message size */
+    pub const PAILLIER_BATCH_SIZE: usize = 1 << 12;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct PaillierPublicKey {
+        pub n: BigUint,
+        n_squared: BigUint,
+    }
+
+    pub struct PaillierKey {
+        pub public: PaillierPublicKey,
+        lambda: BigUint,
+        mu: BigUint,
+    }
+
+    impl PaillierKey {
+        pub fn generate<R: Rng>(rng: &mut R, modulus_bits: usize) -> PaillierKey {
+            loop {
+                let p = random_prime(rng, modulus_bits / 2);
+                let q = random_prime(rng, modulus_bits - modulus_bits / 2);
+                if p == q {
+                    continue;
+                }
+                let n = &p * &q;
+                let p_minus_one = p - BigUint::one();
+                let q_minus_one = q - BigUint::one();
+                let lambda = p_minus_one.lcm(&q_minus_one);
+                /* with g = n + 1, mu is lambda^-1 mod n, here by Euler as lambda^(phi(n) - 1) */
+                let phi = p_minus_one * q_minus_one;
+                let mu = lambda.modpow(&(phi - BigUint::one()), &n);
+                return PaillierKey {
+                    public: PaillierPublicKey::new(n),
+                    lambda,
+                    mu,
+                };
+            }
+        }
+
+        pub fn decrypt(&self, ciphertext: &BigUint) -> BigUint {
+            let n = &self.public.n;
+            let u = ciphertext.modpow(&self.lambda, &self.public.n_squared);
+            (u - BigUint::one()) / n * &self.mu % n
+        }
+    }
+
+    impl PaillierPublicKey {
+        pub fn new(n: BigUint) -> PaillierPublicKey {
+            let n_squared = &n * &n;
+            PaillierPublicKey { n, n_squared }
+        }
+
+        /* (1 + n)^m r^n = (1 + m n) r^n mod n^2 */
+        pub fn encrypt<R: Rng>(&self, rng: &mut R, plaintext: &BigUint) -> BigUint {
+            let r = rng.gen_biguint_range(&BigUint::one(), &self.n);
+            (BigUint::one() + plaintext * &self.n) % &self.n_squared * r.modpow(&self.n, &self.n_squared) % &self.n_squared
+        }
+
+        /* Enc(x) and Enc(y) to Enc(x + y) */
+        pub fn add(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            x * y % &self.n_squared
+        }
+
+        /* Enc(x) and k to Enc(k x) */
+        pub fn multiply_plain(&self, x: &BigUint, k: &BigUint) -> BigUint {
+            x.modpow(k, &self.n_squared)
+        }
+    }
+
+    pub struct PaillierSession {
+        party_id: u8,
+        channel: Arc<dyn Channel>,
+        message_count: u64,
+        /* party 0's key pair */
+        key: Option<PaillierKey>,
+        public: PaillierPublicKey,
+    }
+
+    impl PaillierSession {
+        /* party 0 generates a key pair and sends the public key; both parties call this at the same point */
+        pub fn setup(channel: Arc<dyn Channel>, party_id: u8, modulus_bits: usize) -> Result<PaillierSession, MpcError> {
+            let key = if party_id == 0 { Some(PaillierKey::generate(&mut rand::thread_rng(), modulus_bits)) } else { None };
+            let own_n = key.iter().map(|key| key.public.n.clone()).collect();
+            let mut session = PaillierSession {
+                party_id,
+                channel,
+                message_count: 0,
+                public: PaillierPublicKey::new(BigUint::zero()),
+                key,
+            };
+            let n = match (session.exchange("key", Payload::BigUints(own_n))?.into_biguints()?.pop(), &session.key) {
+                (Some(n), None) => n,
+                (_, Some(key)) => key.public.n.clone(),
+                (None, None) => return Err(MpcError::Protocol("party 0 sent no public key".to_string())),
+            };
+            session.public = PaillierPublicKey::new(n);
+            Ok(session)
+        }
+
+        fn exchange(&mut self, label: &str, payload: Payload) -> Result<Payload, MpcError> {
+            let message = RFMessage {
+                message_id: format!("paillier:{}:{}", self.message_count, label),
+                payload,
+            };
+            self.message_count += 1;
+            Ok(self.channel.exchange(&message, self.party_id)?.payload)
+        }
+
+        // Additive shares mod prime of x_k y_k, where x are party 0's factors and y party 1's; both
+        // pass their own factors, all below prime.
+        pub fn products(&mut self, factors: &[BigUint], prime: &BigUint) -> Result<Vec<BigUint>, MpcError> {
+            let mask_bits = 2 * prime.bits() + STATISTICAL_SECURITY;
+            if self.public.n.bits() <= mask_bits + 1 {
+                return Err(MpcError::Config {
+                    key: "paillier_modulus_bits".to_string(),
+                    problem: format!("a modulus of {} bits cannot hide products modulo a {}-bit prime, it needs more than {} bits",
+                                     self.public.n.bits(), prime.bits(), mask_bits + 1),
+                });
+            }
+            let mut rng = rand::thread_rng();
+            let mut shares = Vec::with_capacity(factors.len());
+            for batch in factors.chunks(PAILLIER_BATCH_SIZE) {
+                let own_inputs = if self.party_id == 0 {
+                    batch.iter().map(|x| self.public.encrypt(&mut rng, x)).collect()
+                } else {
+                    Vec::new()
+                };
+                let inputs = self.exchange("inputs", Payload::BigUints(own_inputs))?.into_biguints()?;
+
+                if self.party_id == 0 {
+                    let message_id = format!("paillier:{}:products", self.message_count);
+                    let products = self.exchange("products", Payload::BigUints(Vec::new()))?.into_biguints()?;
+                    let products = MpcError::check_length(products, batch.len(), &message_id)?;
+                    let key = self.key.as_ref().unwrap();
+                    shares.extend(products.iter().map(|product| key.decrypt(product) % prime));
+                } else {
+                    let message_id = format!("paillier:{}:inputs", self.message_count - 1);
+                    let inputs = MpcError::check_length(inputs, batch.len(), &message_id)?;
+                    let mut products = Vec::with_capacity(batch.len());
+                    for (x, y) in inputs.iter().zip(batch.iter()) {
+                        let mask = rng.gen_biguint(mask_bits);
+                        products.push(self.public.add(&self.public.multiply_plain(x, y), &self.public.encrypt(&mut rng, &mask)));
+                        shares.push((prime - mask % prime) % prime);
+                    }
+                    self.exchange("products", Payload::BigUints(products))?;
+                }
+            }
+            Ok(shares)
+        }
+
+        /* c = a b over both parties' a and b mod prime, from the cross terms a0 b1 and b0 a1 */
+        pub fn bigint_triples(&mut self, count: usize, prime: &BigUint) -> Result<Vec<(BigUint, BigUint, BigUint)>, MpcError> {
+            let mut rng = rand::thread_rng();
+            let a: Vec<BigUint> = (0..count).map(|_| rng.gen_biguint_below(prime)).collect();
+            let b: Vec<BigUint> = (0..count).map(|_| rng.gen_biguint_below(prime)).collect();
+            let factors: Vec<BigUint> = if self.party_id == 0 {
+                a.iter().chain(b.iter()).cloned().collect()
+            } else {
+                b.iter().chain(a.iter()).cloned().collect()
+            };
+            let cross = self.products(&factors, prime)?;
+            Ok(a.into_iter().zip(b).enumerate().map(|(k, (a, b))| {
+                let c = (&a * &b + &cross[k] + &cross[count + k]) % prime;
+                (a, b, c)
+            }).collect())
+        }
+
+        /* shares of r0 r1 for nonzero r0 and r1, hence of a nonzero value mod prime */
+        pub fn equality_shares(&mut self, count: usize, prime: &BigUint) -> Result<Vec<BigUint>, MpcError> {
+            let mut rng = rand::thread_rng();
+            let factors: Vec<BigUint> = (0..count).map(|_| rng.gen_biguint_range(&BigUint::one(), prime)).collect();
+            self.products(&factors, prime)
+        }
+    }
+}
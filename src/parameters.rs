@@ -0,0 +1,157 @@
+pub mod parameters {
+    use crate::error::error::ConfigProblem;
+    use crate::error::error::MpcError;
+    use num::bigint::{BigUint, RandBigInt};
+    use num::{Integer, One, Zero};
+    use rand::Rng;
+    use std::cmp::max;
+
+    // The moduli and widths the protocols depend on. prime holds the weighted counts, which sum to
+    // at most instance_count, and the forest's votes; big_int_prime holds the cross-multiplied Gini
+    // scores, whose sign the comparison reads off its top bit; bit_length is the width of the
+    // BigUint bit decompositions. Moduli from the settings are checked with Miller-Rabin, and
+    // safe_prime generates new ones.
+
+    /* bits by which a mask exceeds the value it hides */
+    pub const STATISTICAL_SECURITY: usize = 40;
+    const MILLER_RABIN_ROUNDS: usize = 40;
+
+    /* the widths and bounds a dataset of this shape needs */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Derived {
+        /* prime must exceed every count and vote */
+        pub min_prime: u64,
+        /* bits of the largest cross-multiplied Gini score */
+        pub score_bits: usize,
+        /* big_int_prime needs one bit for the sign of a score difference and one for its magnitude */
+        pub big_int_prime_bits: usize,
+        /* what the Paillier share source's modulus needs to mask products modulo such a big_int_prime */
+        pub paillier_modulus_bits: usize,
+    }
+
+    /* the modulus bounds for instance_count records of attr_value_count values each, scored with alpha */
+    pub fn derive(instance_count: usize, attr_value_count: usize, alpha: usize, tree_count: usize) -> Derived {
+        let score_bits = gini_score_bit_length(instance_count, attr_value_count, &BigUint::from(alpha));
+        let big_int_prime_bits = score_bits + 2;
+        Derived {
+            min_prime: max(instance_count, tree_count) as u64 + 1,
+            score_bits,
+            big_int_prime_bits,
+            paillier_modulus_bits: 2 * big_int_prime_bits + STATISTICAL_SECURITY + 2,
+        }
+    }
+
+    /* bits needed for the cross-multiplied Gini scores N_a * D_b, bounded via AM-GM over the alpha-scaled value counts */
+    pub fn gini_score_bit_length(instance_count: usize, attr_value_count: usize, alpha: &BigUint) -> usize {
+        let instance_count = BigUint::from(instance_count);
+        let value_count = BigUint::from(attr_value_count);
+        let total = alpha * &instance_count + &value_count;
+        let denominator_bound = num::pow((&total + &value_count - BigUint::one()) / &value_count, attr_value_count);
+        let others_bound = if attr_value_count > 1 {
+            let others = BigUint::from(attr_value_count - 1);
+            num::pow((&total - BigUint::one() + &others - BigUint::one()) / &others, attr_value_count - 1)
+        } else {
+            BigUint::one()
+        };
+        (&instance_count * &instance_count * others_bound * denominator_bound).bits()
+    }
+
+    pub fn is_probable_prime<R: Rng>(rng: &mut R, candidate: &BigUint) -> bool {
+        let two = BigUint::from(2u8);
+        if *candidate < two {
+            return false;
+        }
+        for small in [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37].iter() {
+            let small = BigUint::from(*small);
+            if *candidate == small {
+                return true;
+            }
+            if (candidate % &small).is_zero() {
+                return false;
+            }
+        }
+        let minus_one = candidate - BigUint::one();
+        let mut d = minus_one.clone();
+        let mut s = 0;
+        while d.is_even() {
+            d >>= 1;
+            s += 1;
+        }
+        'witness: for _ in 0..MILLER_RABIN_ROUNDS {
+            let mut x = rng.gen_biguint_range(&two, &minus_one).modpow(&d, candidate);
+            if x.is_one() || x == minus_one {
+                continue;
+            }
+            for _ in 1..s {
+                x = &x * &x % candidate;
+                if x == minus_one {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /* a prime of exactly bits bits, the top two set so that the product of two has 2 * bits */
+    pub fn random_prime<R: Rng>(rng: &mut R, bits: usize) -> BigUint {
+        let top = (BigUint::one() << (bits - 1)) | (BigUint::one() << (bits - 2));
+        loop {
+            let candidate = rng.gen_biguint(bits) | &top | BigUint::one();
+            if is_probable_prime(rng, &candidate) {
+                return candidate;
+            }
+        }
+    }
+
+    /* a prime p = 2q + 1 with q prime, of exactly bits bits */
+    pub fn safe_prime<R: Rng>(rng: &mut R, bits: usize) -> Result<BigUint, MpcError> {
+        if bits < 16 {
+            return Err(MpcError::Usage(format!("a safe prime of {} bits is of no use as a modulus, ask for at least 16", bits)));
+        }
+        loop {
+            let q = random_prime(rng, bits - 1);
+            let p = (&q << 1) + BigUint::one();
+            if is_probable_prime(rng, &p) {
+                return Ok(p);
+            }
+        }
+    }
+
+    pub fn is_safe_prime<R: Rng>(rng: &mut R, candidate: &BigUint) -> bool {
+        candidate.is_odd() && is_probable_prime(rng, candidate) && is_probable_prime(rng, &(candidate >> 1))
+    }
+
+    /* what is wrong with the modulus given as key, if anything */
+    pub fn check_modulus(key: &str, modulus: &BigUint) -> Option<ConfigProblem> {
+        let problem = if *modulus < BigUint::from(3u8) {
+            format!("a modulus must be an odd prime, got {}", modulus)
+        } else if modulus.is_even() {
+            format!("{} is even, so not prime", modulus)
+        } else if !is_probable_prime(&mut rand::thread_rng(), modulus) {
+            format!("{} is composite (Miller-Rabin)", modulus)
+        } else {
+            return None;
+        };
+        Some(ConfigProblem { key: key.to_string(), problem })
+    }
+
+    /* prime and big_int_prime against the bounds of a dataset of this shape */
+    pub fn check_moduli(prime: u64, big_int_prime: &BigUint, derived: &Derived) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        if prime < derived.min_prime {
+            problems.push(ConfigProblem {
+                key: "prime".to_string(),
+                problem: format!("must exceed every count and vote, so be at least {}, got {}", derived.min_prime, prime),
+            });
+        }
+        if big_int_prime.bits() < derived.big_int_prime_bits {
+            problems.push(ConfigProblem {
+                key: "big_int_prime".to_string(),
+                problem: format!("has {} bits, the Gini scores need {}; generate one with `prime {}`",
+                                 big_int_prime.bits(), derived.big_int_prime_bits, derived.big_int_prime_bits),
+            });
+        }
+        problems
+    }
+}
@@ -0,0 +1,68 @@
+pub mod pipeline {
+    use std::cmp::max;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::mpsc::{sync_channel, Receiver};
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::thread;
+
+    struct Turn {
+        /* the next index a worker takes up */
+        next_index: usize,
+        /* the next index to enter the queue */
+        next_to_send: usize,
+        failed: bool,
+    }
+
+    // Produces produce(0), ..., produce(count - 1) on worker_count background threads and delivers
+    // them in index order. Up to depth finished items wait in the queue, and every worker holds at
+    // most one more, so production stays that far ahead of the consumer. The receiver reports a
+    // disconnect once all items are delivered, or early if produce panicked.
+    pub fn prefetch<T, F>(count: usize, worker_count: usize, depth: usize, produce: F) -> Receiver<T>
+        where T: Send + 'static, F: Fn(usize) -> T + Send + Sync + 'static {
+        let (sender, receiver) = sync_channel(depth);
+        let produce = Arc::new(produce);
+        let turn = Arc::new((Mutex::new(Turn { next_index: 0, next_to_send: 0, failed: false }), Condvar::new()));
+        for _ in 0..max(worker_count, 1) {
+            let sender = sender.clone();
+            let produce = Arc::clone(&produce);
+            let turn = Arc::clone(&turn);
+            thread::spawn(move || {
+                let (lock, turn_taken) = &*turn;
+                loop {
+                    let index = {
+                        let mut state = lock.lock().unwrap();
+                        if state.failed || state.next_index >= count {
+                            return;
+                        }
+                        state.next_index += 1;
+                        state.next_index - 1
+                    };
+                    let item = panic::catch_unwind(AssertUnwindSafe(|| produce(index)));
+
+                    let mut state = lock.lock().unwrap();
+                    while state.next_to_send != index && !state.failed {
+                        state = turn_taken.wait(state).unwrap();
+                    }
+                    /* only the worker whose turn it is sends, so the lock can be released meanwhile */
+                    let failed = state.failed;
+                    drop(state);
+                    let sent = match item {
+                        Ok(item) if !failed => sender.send(item).is_ok(),
+                        _ => false,
+                    };
+                    let mut state = lock.lock().unwrap();
+                    if sent {
+                        state.next_to_send += 1;
+                    } else {
+                        state.failed = true;
+                    }
+                    turn_taken.notify_all();
+                    if !sent {
+                        return;
+                    }
+                }
+            });
+        }
+        receiver
+    }
+}
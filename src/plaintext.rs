@@ -0,0 +1,205 @@
+pub mod plaintext {
+    use crate::computing_party::computing_party::load_dt_training_file;
+    use crate::error::error::MpcError;
+    use crate::random_forest::random_forest::{tree_sample, MaxFeatures};
+    use crate::reveal::reveal::{RevealedNode, RevealedTree};
+    use num::bigint::BigUint;
+    use num::{One, Zero};
+    use std::cmp::min;
+
+    // The reconstructed (or never shared) dataset in the one-hot layout of load_dt_training_file:
+    // attr_values[k][j][t] = [instance t has value j for attribute k], class_values[i][t] = [t has class i].
+    #[derive(Clone, Debug)]
+    pub struct PlaintextData {
+        pub class_value_count: usize,
+        pub attribute_count: usize,
+        pub attr_value_count: usize,
+        pub instance_count: usize,
+        pub attr_values: Vec<Vec<Vec<u8>>>,
+        pub class_values: Vec<Vec<u8>>,
+    }
+
+    impl PlaintextData {
+        pub fn from_one_hot(class_value_count: usize, attribute_count: usize, attr_value_count: usize, instance_count: usize, one_hot_encoding: &[Vec<u8>]) -> PlaintextData {
+            let attr_values = (0..attribute_count)
+                .map(|k| one_hot_encoding[k * attr_value_count..(k + 1) * attr_value_count].to_vec())
+                .collect();
+            let class_values = (0..class_value_count)
+                .map(|i| match one_hot_encoding.get(attribute_count * attr_value_count + i) {
+                    Some(row) => row.clone(),
+                    None => vec![0u8; instance_count],
+                })
+                .collect();
+            PlaintextData {
+                class_value_count,
+                attribute_count,
+                attr_value_count,
+                instance_count,
+                attr_values,
+                class_values,
+            }
+        }
+
+        /* a plaintext file in the share file format, e.g. the output of `reveal binary` */
+        pub fn load(path: &String) -> Result<PlaintextData, MpcError> {
+            let (class_value_count, attribute_count, attr_value_count, instance_count, one_hot_encoding) = load_dt_training_file(path)?;
+            Ok(PlaintextData::from_one_hot(class_value_count, attribute_count, attr_value_count, instance_count, &one_hot_encoding))
+        }
+    }
+
+    /* first index of the maximum, the tie-break of protocol::arg_max */
+    fn first_max_index<T: PartialOrd>(values: &[T]) -> usize {
+        let mut best = 0;
+        for i in 1..values.len() {
+            if values[i] > values[best] {
+                best = i;
+            }
+        }
+        best
+    }
+
+    fn weighted_count(subset: &[u8], weights: &[u64], indicator: &[u8]) -> u64 {
+        (0..subset.len()).map(|t| (subset[t] & indicator[t]) as u64 * weights[t]).sum()
+    }
+
+    /* the majority class of the weighted subset and the subset's weighted size */
+    pub fn majority_class(data: &PlaintextData, subset: &[u8], weights: &[u64]) -> (usize, u64) {
+        let counts: Vec<u64> = data.class_values.iter().map(|class| weighted_count(subset, weights, class)).collect();
+        (first_max_index(&counts), counts.iter().sum())
+    }
+
+    // The attribute maximising G_k = sum_j (sum_i x_kji^2) / (alpha * |T_kj| + 1), where masked-out
+    // attributes score zero. Scores are compared as the fractions N_k / D_k the secure path builds.
+    pub fn best_attribute(data: &PlaintextData, subset: &[u8], weights: &[u64], attribute_mask: &[u8], alpha: u64) -> usize {
+        let mut numerators = Vec::new();
+        let mut denominators = Vec::new();
+        for (attribute, masked_in) in data.attr_values.iter().zip(attribute_mask.iter()) {
+            let mut value_denominators = Vec::new();
+            let mut value_numerators = Vec::new();
+            for value in attribute.iter() {
+                let mut square_sum = BigUint::zero();
+                let mut value_count = 0u64;
+                for class in data.class_values.iter() {
+                    let indicator: Vec<u8> = (0..data.instance_count).map(|t| value[t] & class[t]).collect();
+                    let count = weighted_count(subset, weights, &indicator);
+                    square_sum += BigUint::from(count) * BigUint::from(count);
+                    value_count += count;
+                }
+                value_numerators.push(square_sum);
+                value_denominators.push(BigUint::from(alpha) * BigUint::from(value_count) + BigUint::one());
+            }
+            let mut numerator = BigUint::zero();
+            for (j, value_numerator) in value_numerators.iter().enumerate() {
+                let others = (0..data.attr_value_count).filter(|l| *l != j)
+                    .fold(BigUint::one(), |product, l| product * &value_denominators[l]);
+                numerator += value_numerator * others;
+            }
+            if *masked_in == 0 {
+                numerator = BigUint::zero();
+            }
+            numerators.push(numerator);
+            denominators.push(value_denominators.iter().fold(BigUint::one(), |product, d| product * d));
+        }
+
+        let mut best = 0;
+        for k in 1..data.attribute_count {
+            if &numerators[k] * &denominators[best] > &numerators[best] * &denominators[k] {
+                best = k;
+            }
+        }
+        best
+    }
+
+    struct TreeGrowth<'a> {
+        data: &'a PlaintextData,
+        alpha: u64,
+        cutoff_transaction_set_size: usize,
+        weights: &'a Vec<u64>,
+    }
+
+    // Grows the same complete tree as decision_tree::train, including the nodes below leaves, so the
+    // result can be compared node by node with a reconstructed model.
+    pub fn train_tree(data: &PlaintextData, max_depth: usize, alpha: u64, cutoff_transaction_set_size: usize, weights: &Vec<u64>, attribute_mask: &[u8]) -> RevealedTree {
+        let depth_limit = min(max_depth, data.attribute_count);
+        let node_count = (0..depth_limit + 1).map(|depth| data.attr_value_count.pow(depth as u32)).sum();
+        let mut tree = RevealedTree {
+            attr_value_count: data.attr_value_count,
+            max_depth: depth_limit,
+            nodes: (0..node_count).map(|index| RevealedNode {
+                index,
+                depth: 0,
+                split_attribute: None,
+                is_leaf: false,
+                class_index: None,
+            }).collect(),
+        };
+        let growth = TreeGrowth { data, alpha, cutoff_transaction_set_size, weights };
+        growth.train_node(&mut tree, &vec![1u8; data.instance_count], attribute_mask, 0, 0, None);
+        tree
+    }
+
+    impl<'a> TreeGrowth<'a> {
+        /* parent holds the parent's is_leaf and class */
+        fn train_node(&self, tree: &mut RevealedTree, subset: &[u8], attribute_mask: &[u8], node_index: usize, depth: usize, parent: Option<(bool, usize)>) {
+            let data = self.data;
+            let (major_class, transaction_count) = majority_class(data, subset, self.weights);
+            let below_cutoff = transaction_count < self.cutoff_transaction_set_size as u64;
+            let empty = transaction_count < 1;
+            let parent_leaf = matches!(parent, Some((true, _)));
+            let is_leaf = depth == tree.max_depth || parent_leaf || below_cutoff;
+            let class_index = match parent {
+                Some((_, parent_class)) if parent_leaf || empty => parent_class,
+                _ => major_class,
+            };
+            tree.nodes[node_index].depth = depth;
+            tree.nodes[node_index].is_leaf = is_leaf;
+            tree.nodes[node_index].class_index = Some(class_index);
+
+            if depth < tree.max_depth {
+                let split_attribute = best_attribute(data, subset, self.weights, attribute_mask, self.alpha);
+                tree.nodes[node_index].split_attribute = Some(split_attribute);
+                let mut child_attribute_mask = attribute_mask.to_vec();
+                child_attribute_mask[split_attribute] = 0;
+                for j in 0..data.attr_value_count {
+                    let child_subset: Vec<u8> = (0..data.instance_count).map(|t| subset[t] & data.attr_values[split_attribute][j][t]).collect();
+                    let child_index = node_index * data.attr_value_count + j + 1;
+                    self.train_node(tree, &child_subset, &child_attribute_mask, child_index, depth + 1, Some((is_leaf, class_index)));
+                }
+            }
+        }
+    }
+
+    /* random_forest::train with the tree seeds a trained model records, one tree per seed */
+    pub fn train_forest(data: &PlaintextData, seeds: &[[u8; 32]], max_depth: usize, alpha: u64, epsilon: f64, bootstrap: bool, max_features: &MaxFeatures)
+                        -> Vec<RevealedTree> {
+        let cutoff_transaction_set_size = (epsilon * data.instance_count as f64) as usize;
+        seeds.iter().map(|seed| {
+            let sample = tree_sample(*seed, data.instance_count, data.attribute_count, bootstrap, max_features, max_depth);
+            train_tree(data, sample.max_depth, alpha, cutoff_transaction_set_size, &sample.instance_weights, &sample.attribute_mask)
+        }).collect()
+    }
+
+    /* the class at the max_depth node instance t reaches, as evaluated by inference::evaluate_tree */
+    pub fn tree_vote(tree: &RevealedTree, data: &PlaintextData, t: usize) -> Option<usize> {
+        let mut index = 0;
+        while tree.nodes[index].depth < tree.max_depth {
+            let attribute = tree.nodes[index].split_attribute?;
+            let value = (0..tree.attr_value_count).position(|j| data.attr_values[attribute][j][t] == 1)?;
+            index = index * tree.attr_value_count + value + 1;
+        }
+        tree.nodes[index].class_index
+    }
+
+    /* majority vote of the trees for every instance, ties to the lowest class */
+    pub fn predict(trees: &[RevealedTree], data: &PlaintextData) -> Vec<usize> {
+        (0..data.instance_count).map(|t| {
+            let mut votes = vec![0usize; data.class_value_count];
+            for tree in trees.iter() {
+                if let Some(class) = tree_vote(tree, data, t) {
+                    votes[class] += 1;
+                }
+            }
+            first_max_index(&votes)
+        }).collect()
+    }
+}
@@ -0,0 +1,110 @@
+pub mod preprocessing {
+    use crate::computing_party::computing_party::ComputingParty;
+    use crate::error::error::MpcError;
+    use crate::pipeline::pipeline::prefetch;
+    use crate::share_stream::share_stream::{write_seeded_shares, SHARE_STREAM_VERSION};
+    use crate::ti::ti::{generate_seeded_shares, TI};
+    use serde::{Deserialize, Serialize};
+    use std::fs::{self, File};
+    use std::io::{BufWriter, Write};
+    use std::path::Path;
+
+    pub const MANIFEST_FILE: &str = "manifest.json";
+
+    // What a directory of preprocessed shares holds: tree_count share streams per party, in the order
+    // the parties consume them, generated for the listed moduli and per-tree counts. File names are
+    // relative to the directory so it can be moved or copied to the parties as a whole.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct ShareManifest {
+        pub format_version: u16,
+        pub tree_count: usize,
+        pub prime: u64,
+        pub big_int_prime: String,
+        pub add_shares_per_tree: usize,
+        /* absent from older manifests, which then load and are refused by format_version */
+        #[serde(default)]
+        pub add_shares_z64_per_tree: usize,
+        pub add_shares_bigint_per_tree: usize,
+        pub binary_shares_per_tree: usize,
+        pub equality_shares_per_tree: usize,
+        /* party_files[party_id][tree] */
+        pub party_files: Vec<Vec<String>>,
+    }
+
+    // The offline phase on its own: one seeded share stream per party and tree, then the manifest.
+    // The manifest is written last, so a directory without one is an interrupted run.
+    pub fn write_preprocessed_shares(ctx: &TI, dir: &str) -> Result<ShareManifest, MpcError> {
+        let mut party_files = vec![Vec::new(), Vec::new()];
+        for party_id in 0..2 {
+            fs::create_dir_all(Path::new(dir).join(format!("party{}", party_id)))
+                .map_err(|error| MpcError::io(format!("failed to create {}", dir), error))?;
+        }
+        let ti = ctx.clone();
+        let generated = prefetch(ctx.tree_count, ctx.thread_count, ctx.prefetch_trees, move |i| generate_seeded_shares(&ti, i));
+        for (i, (share0, share1)) in generated.iter().enumerate() {
+            for (party_id, shares) in [share0, share1].iter().enumerate() {
+                let file_name = format!("party{}/tree_{}.rfts", party_id, i);
+                let path = Path::new(dir).join(&file_name);
+                let file = File::create(&path)
+                    .map_err(|error| MpcError::io(format!("failed to create {}", path.display()), error))?;
+                let mut writer = BufWriter::new(file);
+                write_seeded_shares(&mut writer, shares)
+                    .and_then(|_| writer.flush())
+                    .map_err(|error| MpcError::io(format!("failed to write {}", path.display()), error))?;
+                party_files[party_id].push(file_name);
+            }
+        }
+
+        let manifest = ShareManifest {
+            format_version: SHARE_STREAM_VERSION,
+            tree_count: ctx.tree_count,
+            prime: ctx.prime,
+            big_int_prime: ctx.big_int_prime.to_string(),
+            add_shares_per_tree: ctx.add_shares_per_tree,
+            add_shares_z64_per_tree: ctx.add_shares_z64_per_tree,
+            add_shares_bigint_per_tree: ctx.add_shares_bigint_per_tree,
+            binary_shares_per_tree: ctx.binary_shares_per_tree,
+            equality_shares_per_tree: ctx.equality_shares_per_tree,
+            party_files,
+        };
+        let path = Path::new(dir).join(MANIFEST_FILE);
+        fs::write(&path, serde_json::to_string_pretty(&manifest).unwrap())
+            .map_err(|error| MpcError::io(format!("failed to write {}", path.display()), error))?;
+        Ok(manifest)
+    }
+
+    impl ShareManifest {
+        pub fn load(dir: &str) -> Result<ShareManifest, MpcError> {
+            let path = Path::new(dir).join(MANIFEST_FILE);
+            let text = fs::read_to_string(&path)
+                .map_err(|error| MpcError::io(format!("failed to read {}", path.display()), error))?;
+            serde_json::from_str(&text)
+                .map_err(|error| MpcError::Deserialize(format!("{}: {}", path.display(), error)))
+        }
+
+        /* the shares are only usable by a party computing modulo the same primes */
+        pub fn check_party(&self, ctx: &ComputingParty) -> Result<(), MpcError> {
+            let mut problems = Vec::new();
+            if self.format_version != SHARE_STREAM_VERSION {
+                problems.push(format!("format version {} (expected {})", self.format_version, SHARE_STREAM_VERSION));
+            }
+            if self.prime != ctx.dt_training.prime {
+                problems.push(format!("prime {} (the party uses {})", self.prime, ctx.dt_training.prime));
+            }
+            if self.big_int_prime != ctx.dt_training.big_int_prime.to_string() {
+                problems.push(format!("big_int_prime {} (the party uses {})", self.big_int_prime, ctx.dt_training.big_int_prime));
+            }
+            if self.party_files.len() <= ctx.party_id as usize {
+                problems.push(format!("no files for party {}", ctx.party_id));
+            }
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(MpcError::Config {
+                    key: "preprocessed_shares_dir".to_string(),
+                    problem: format!("the shares do not match this party: {}", problems.join(", ")),
+                })
+            }
+        }
+    }
+}
@@ -2,128 +2,99 @@ pub mod protocol {
     /**
     ** @author Davis.R, James.S
     **/
-    use std::num::Wrapping;
     use crate::computing_party::computing_party::ComputingParty;
-    use crate::constants::constants::{BATCH_SIZE, U8S_PER_TX, BUF_SIZE, U64S_PER_TX, BINARY_PRIME};
-    use std::io::{Read, Write, BufReader, BufRead};
-    use threadpool::ThreadPool;
-    use std::collections::HashMap;
-    use std::sync::{Arc, Mutex};
-    use std::cmp::{min, max};
-    use num::integer::*;
-    use num::bigint::{BigUint, ToBigUint, ToBigInt};
-    use num::{Zero, One, FromPrimitive, abs, BigInt};
-    use crate::utils::utils::*;
-    use serde::{Serialize, Deserialize, Serializer};
-    use std::net::TcpStream;
-    use std::ops::{Add, Mul};
-    use crate::multiplication::multiplication::{batch_multiplication_byte, parallel_multiplication, multi_thread_batch_mul_byte};
-    use crate::comparison::comparison::comparison;
+    use crate::ring::ring::{AdditiveShare, BigZp, Z2};
+    use crate::utils::utils::get_current_equality_share;
+    use crate::multiplication::multiplication::{batch_parallel_multiplication, multiplication_bigint};
+    use crate::comparison::comparison::batch_comparison;
+    use crate::message::message::{Payload, RFMessage};
+    use crate::error::error::MpcError;
+    use rand::Rng;
+    use sha2::{Sha256, Digest};
 
-    pub fn arg_max(bit_shares: &Vec<Vec<u8>>, ctx: &mut ComputingParty) -> Vec<u8> {
+    pub fn arg_max(bit_shares: &[Vec<AdditiveShare<Z2>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<Z2>>, MpcError> {
         ctx.thread_hierarchy.push("arg_max".to_string());
         let number_count = bit_shares.len();
 
-        let mut result = Vec::new();
-        if number_count == 1 {
-            result.push(1);
-        } else {
-            let mut bit_length = 0;
-            for item in bit_shares.iter(){
-                bit_length = max(bit_length, item.len());
+        //computeComparisons in JAVA Lynx, only for i < j as [x_j > x_i] is the negation of [x_i >= x_j]
+        let mut x_lists = Vec::new();
+        let mut y_lists = Vec::new();
+        for i in 0..number_count {
+            for j in i + 1..number_count {
+                x_lists.push(bit_shares[i].clone());
+                y_lists.push(bit_shares[j].clone());
             }
-            let mut w_intermediate = HashMap::new();
-
-            for i in 0..number_count {
-                let mut list = Vec::new();
-                w_intermediate.insert(i, list);
-            }
-            //computeComparisons in JAVA Lynx
-            let ti_count = 2 * bit_length + (bit_length * (bit_length - 1) / 2);
-            let thread_pool = ThreadPool::new(ctx.thread_count);
-            let mut output_map = Arc::new(Mutex::new((HashMap::new())));
-            let mut key = 0;
-            for i in 0..number_count {
-                for j in 0..number_count {
-                    if i != j {
-                        let mut output_map = Arc::clone(&output_map);
-                        let mut ctx_copied = ctx.clone();
-                        ctx_copied.thread_hierarchy.push(format!("{}",i * number_count + j));
-                        let mut bit_shares = bit_shares.clone();
-                        thread_pool.execute(move || {
-                            key = i * number_count + j;
-                            let comparison_result = comparison(&bit_shares[i], &bit_shares[j], &mut ctx_copied);
-                            let mut output_map = output_map.lock().unwrap();
-                            (*output_map).insert(key, comparison_result);
-                        });
-                    }
-                }
-            }
-            thread_pool.join();
-            let output_map = output_map.lock().unwrap();
-            for i in 0..number_count * (number_count - 1) {
-                let mut comparison = *output_map.get(&i).unwrap();
-                let key = i / (number_count - 1);
-                w_intermediate.get_mut(&key).unwrap().push(comparison);
-            }
-
-            let mut output_map = Arc::new(Mutex::new((HashMap::new())));
-            //multi-threaded parallel multiplication
-            ctx.thread_hierarchy.push("parallel_multiplication".to_string());
-            for i in 0..number_count {
-                let mut vec = Vec::new();
-                for item in w_intermediate.get(&i).unwrap().iter() {
-                    vec.push(*item);
-                }
-                let mut output_map = Arc::clone(&output_map);
-                let mut ctx_copied = ctx.clone();
-                ctx_copied.thread_hierarchy.push(format!("{}",i));
-                thread_pool.execute(move || {
-                    let multi_result = parallel_multiplication(&vec, &mut ctx_copied);
-                    let mut output_map = output_map.lock().unwrap();
-                    (*output_map).insert(i, multi_result);
-                });
-            }
-            thread_pool.join();
-            ctx.thread_hierarchy.pop();
-            let output_map = &*(output_map.lock().unwrap());
-            for i in 0..number_count {
-                let multi_result = output_map.get(&i).unwrap();
-                result[i] = *multi_result;
+        }
+        let comparison_results = batch_comparison(&x_lists, &y_lists, ctx)?;
+        let mut comparisons = vec![vec![AdditiveShare::zero(&Z2); number_count]; number_count];
+        let mut key = 0;
+        for (i, row) in comparisons.iter_mut().enumerate() {
+            for comparison in row.iter_mut().skip(i + 1) {
+                *comparison = comparison_results[key];
+                key += 1;
             }
         }
 
+        let result = arg_max_by_comparisons(&comparisons, ctx)?;
         ctx.thread_hierarchy.pop();
-        result
+        Ok(result)
     }
 
-    pub fn equality_big_integer(x: &BigUint, y: &BigUint, ctx: &mut ComputingParty) -> BigUint {
-        let equality_share = get_current_equality_share(ctx);
-        let bigint_share = get_current_bigint_share(ctx);
-        let prime = &ctx.dt_training.big_int_prime;
-        let diff = big_uint_subtract(x, y, prime);
-        let mut diff_list = Vec::new();
-        diff_list.push(big_uint_subtract(&diff, &bigint_share.0, prime));
-        diff_list.push(big_uint_subtract(equality_share, &bigint_share.1, prime));
-        let mut in_stream = ctx.in_stream.try_clone()
-            .expect("failed cloning tcp o_stream");
+    // comparisons[i][j] (i < j) holds a binary share of [x_i >= x_j]. The one-hot result marks the first
+    // index that is not beaten by any other, so ties resolve to the lowest index.
+    pub fn arg_max_by_comparisons(comparisons: &[Vec<AdditiveShare<Z2>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<Z2>>, MpcError> {
+        let number_count = comparisons.len();
+        let w_intermediate: Vec<Vec<AdditiveShare<Z2>>> = (0..number_count)
+            .map(|i| (0..number_count).filter(|j| *j != i)
+                .map(|j| if j < i { comparisons[j][i].complement(ctx.asymmetric_bit) } else { comparisons[i][j] })
+                .collect())
+            .collect();
+        batch_parallel_multiplication(&w_intermediate, ctx)
+    }
 
-        let mut o_stream = ctx.o_stream.try_clone()
-            .expect("failed cloning tcp o_stream");
+    // Coin tossing by commit-then-reveal: each party commits to 32 random bytes with SHA-256 before seeing
+    // the other's, so the XOR of the two is uniform as long as either party is honest.
+    pub fn joint_random_seed(ctx: &mut ComputingParty) -> Result<[u8; 32], MpcError> {
+        ctx.thread_hierarchy.push("joint_random_seed".to_string());
+        let mut own_seed = [0u8; 32];
+        rand::thread_rng().fill(&mut own_seed);
+        let commitment = Sha256::digest(&own_seed).to_vec();
 
-        let mut message = serialize_biguint_vec(diff_list);
-        o_stream.write((message + "\n").as_bytes());
+        ctx.thread_hierarchy.push("commit".to_string());
+        let received_commitment = exchange_bytes(commitment, ctx)?;
+        ctx.thread_hierarchy.pop();
+        ctx.thread_hierarchy.push("reveal".to_string());
+        let received_seed = exchange_bytes(own_seed.to_vec(), ctx)?;
+        let received_seed = MpcError::check_length(received_seed, 32, &ctx.thread_hierarchy.join(":"))?;
+        ctx.thread_hierarchy.pop();
 
-        let mut reader = BufReader::new(in_stream);
-        let mut diff_list_message = String::new();
-        reader.read_line(&mut diff_list_message).expect("fail to read diff list message");
+        if Sha256::digest(&received_seed).to_vec() != received_commitment {
+            return Err(MpcError::Protocol("the other party opened a seed it did not commit to".to_string()));
+        }
+        let mut seed = [0u8; 32];
+        for i in 0..32 {
+            seed[i] = own_seed[i] ^ received_seed[i];
+        }
+        ctx.thread_hierarchy.pop();
+        Ok(seed)
+    }
 
-        let mut diff_list = deserialize_biguint_vec(diff_list_message);
+    fn exchange_bytes(bytes: Vec<u8>, ctx: &mut ComputingParty) -> Result<Vec<u8>, MpcError> {
+        let message = RFMessage {
+            message_id: ctx.thread_hierarchy.join(":"),
+            payload: Payload::Bytes(bytes),
+        };
+        ctx.channel.exchange(&message, ctx.asymmetric_bit)?.payload.into_bytes()
+    }
 
-        let d = big_uint_subtract(&diff, &bigint_share.0, prime).add(&diff_list[0]).mod_floor(prime);
-        let e = big_uint_subtract(&diff, &bigint_share.0, prime).add(&diff_list[0]).mod_floor(prime);
-        let product = big_uint_clone(&bigint_share.2).add(&big_uint_clone(&d).mul(&big_uint_clone(&bigint_share.1)))
-                  .add(big_uint_clone(&bigint_share.0).mul(big_uint_clone(&e))).add(big_uint_clone(&d).mul(&big_uint_clone(&e)).mul(BigUint::from(ctx.asymmetric_bit)));
-        product
+    /* multiplies x - y by the TI's random equality share, so the product is zero iff x == y */
+    pub fn equality_big_integer(x: &AdditiveShare<BigZp>, y: &AdditiveShare<BigZp>, ctx: &mut ComputingParty) -> Result<AdditiveShare<BigZp>, MpcError> {
+        ctx.thread_hierarchy.push("equality_big_integer".to_string());
+        let equality_share = get_current_equality_share(ctx)?;
+        let ring = BigZp::of(ctx);
+        let diff = x.subtract(&ring, y);
+        let product = multiplication_bigint(&diff, &equality_share, ctx)?;
+        ctx.thread_hierarchy.pop();
+        Ok(product)
     }
-}
\ No newline at end of file
+}
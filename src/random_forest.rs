@@ -1,109 +1,247 @@
 pub mod random_forest {
-    use crate::computing_party::computing_party::{ComputingParty, get_formatted_address, try_setup_socket, initialize_party_context, ti_receive, reset_share_indices};
+    use crate::computing_party::computing_party::{ComputingParty, reset_share_indices};
+    use crate::budget::budget::TripleBudget;
     use crate::decision_tree::decision_tree;
-    use std::sync::{Arc, Mutex};
+    use std::sync::Arc;
     use threadpool::ThreadPool;
     use crate::field_change::field_change::{change_binary_to_bigint_field, change_binary_to_decimal_field};
-    use std::thread::current;
-    use crate::message::message::MessageManager;
-    use std::collections::HashMap;
+    use crate::model::model::{SecretSharedForest, SecretSharedTree};
+    use crate::ring::ring::{AdditiveShare, Z2};
+    use crate::inference::inference;
+    use crate::pipeline::pipeline::prefetch;
+    use crate::protocol::protocol::joint_random_seed;
+    use crate::error::error::MpcError;
+    use rand::{Rng, SeedableRng};
+    use rand::prng::chacha::ChaChaRng;
+    use rand::seq::sample_indices;
+    use std::cmp::{min, max};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    /* number of attributes each tree may split on */
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum MaxFeatures {
+        All,
+        Sqrt,
+        Log2,
+        Fraction(f64),
+        Absolute(usize),
+    }
+
+    impl MaxFeatures {
+        pub fn parse(value: &str) -> Result<MaxFeatures, MpcError> {
+            let invalid = |problem: String| Err(MpcError::Config { key: "max_features".to_string(), problem });
+            match value.trim() {
+                "all" => Ok(MaxFeatures::All),
+                "sqrt" => Ok(MaxFeatures::Sqrt),
+                "log2" => Ok(MaxFeatures::Log2),
+                value if value.contains('.') => match value.parse::<f64>() {
+                    Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(MaxFeatures::Fraction(fraction)),
+                    _ => invalid(format!("a fraction must lie in (0, 1], got {}", value)),
+                },
+                value => match value.parse::<usize>() {
+                    Ok(count) if count > 0 => Ok(MaxFeatures::Absolute(count)),
+                    _ => invalid(format!("must be sqrt, log2, all, a fraction or a positive count, got {}", value)),
+                },
+            }
+        }
+
+        pub fn feature_count(&self, attribute_count: usize) -> usize {
+            let count = match self {
+                MaxFeatures::All => attribute_count,
+                MaxFeatures::Sqrt => (attribute_count as f64).sqrt().round() as usize,
+                MaxFeatures::Log2 => (attribute_count as f64).log2().round() as usize,
+                MaxFeatures::Fraction(fraction) => (fraction * attribute_count as f64).round() as usize,
+                MaxFeatures::Absolute(count) => *count,
+            };
+            min(max(count, 1), attribute_count)
+        }
+    }
 
 
-    pub fn train(ctx: &mut ComputingParty) {
+    // Trains up to tree_training_batch_size trees at a time, each on its own thread. The trees share
+    // the channel to the other party: every message is tagged with the tree's index in the hierarchy,
+    // so the trees' rounds interleave freely. Shares are still received in tree order, so both parties
+    // hand tree k the same shares.
+    pub fn train(ctx: &mut ComputingParty) -> Result<SecretSharedForest, MpcError> {
         ctx.thread_hierarchy.push("RF".to_string());
-        let thread_pool = ThreadPool::with_name(format!("{}", "RF"), ctx.thread_count);
-        let mut remainder = ctx.tree_count;
-        let mut current_p0_port = ctx.party0_port + 1;
-        let mut current_p1_port = ctx.party1_port + 1;
-
-        for current_tree_index in 0..remainder {
-            let dt_shares = ti_receive(
-                ctx.ti_stream.try_clone().expect("failed to clone ti recvr"));
-            let mut dt_ctx = ctx.clone();
-            dt_ctx.dt_shares = dt_shares;
+        let concurrent_trees = max(ctx.tree_training_batch_size, 1);
+        let thread_pool = ThreadPool::with_name("RF".to_string(), concurrent_trees);
+        let (sender, finished) = channel();
+        let mut trees = Vec::with_capacity(ctx.tree_count);
+        let mut forest = SecretSharedForest::new(ctx.party_id);
 
-//            thread_pool.execute(move || {
-//                dt_ctx.party0_port = current_p0_port;
-//                dt_ctx.party1_port = current_p1_port;
-//                reset_share_indices(&mut dt_ctx);
-//                let (internal_addr, external_addr) = get_formatted_address(dt_ctx.party_id, &dt_ctx.party0_ip, dt_ctx.party0_port, &dt_ctx.party1_ip, dt_ctx.party1_port);
-//                let (in_stream, o_stream) = try_setup_socket(&internal_addr, &external_addr, &dt_ctx.message_manager);
-//                dt_ctx.in_stream = in_stream;
-//                dt_ctx.o_stream = o_stream;
-//
-//                dt_ctx.thread_hierarchy.push(format!("{}", current_tree_index));
-//                //init in java Lynx
-//                let mut attr_values = Vec::new();
-//                let mut class_values = Vec::new();
-//                let mut attr_values_bigint = Vec::new();
-//                let mut class_values_bigint = Vec::new();
-//                let mut attr_values_bytes = dt_ctx.dt_data.attr_values_bytes.clone();
-//                for item in attr_values_bytes.iter() {
-//                    let mut attr_data_item = Vec::new();
-//                    let mut attr_data_bigint_item = Vec::new();
-//                    for data_item in item.iter() {
-//                        attr_data_item.push(change_binary_to_decimal_field(data_item, &mut dt_ctx));
-//                        attr_data_bigint_item.push(change_binary_to_bigint_field(data_item, &mut dt_ctx));
-//                    }
-//                    attr_values.push(attr_data_item);
-//                    attr_values_bigint.push(attr_data_bigint_item);
-//                }
-//                dt_ctx.dt_data.attr_values = attr_values;
-//                dt_ctx.dt_data.attr_values_big_integer = attr_values_bigint;
-//
-//                let mut class_value_bytes = dt_ctx.dt_data.class_values_bytes.clone();
-//                for item in class_value_bytes.iter() {
-//                    class_values.push(change_binary_to_decimal_field(item, &mut dt_ctx));
-//                    class_values_bigint.push(change_binary_to_bigint_field(item, &mut dt_ctx));
-//                }
-//
-//                dt_ctx.dt_data.class_values = class_values;
-//                dt_ctx.dt_data.class_values_big_integer = class_values_bigint;
-//                let dt_training = decision_tree::train(&mut dt_ctx);
-//            });
-
-            dt_ctx.party0_port = current_p0_port;
-            dt_ctx.party1_port = current_p1_port;
-            reset_share_indices(&mut dt_ctx);
-            let (internal_addr, external_addr) = get_formatted_address(dt_ctx.party_id, &dt_ctx.party0_ip, dt_ctx.party0_port, &dt_ctx.party1_ip, dt_ctx.party1_port);
-            let (in_stream, o_stream) = try_setup_socket(&internal_addr, &external_addr, &mut dt_ctx);
-            dt_ctx.in_stream = in_stream;
-            dt_ctx.o_stream = o_stream;
+        //the shares of the next prefetch_trees trees are fetched in the background while trees train
+        let prefetched = if ctx.prefetch_trees > 0 {
+            let share_source = Arc::clone(&ctx.share_source);
+            Some(prefetch(ctx.tree_count, 1, ctx.prefetch_trees - 1, move |_| share_source.receive_shares()))
+        } else {
+            None
+        };
 
-            dt_ctx.thread_hierarchy.push(format!("{}", current_tree_index));
-            //init in java Lynx
-            let mut attr_values = Vec::new();
-            let mut class_values = Vec::new();
-            let mut attr_values_bigint = Vec::new();
-            let mut class_values_bigint = Vec::new();
-            let mut attr_values_bytes = dt_ctx.dt_data.attr_values_bytes.clone();
-            for item in attr_values_bytes.iter() {
-                let mut attr_data_item = Vec::new();
-                let mut attr_data_bigint_item = Vec::new();
-                for data_item in item.iter() {
-                    attr_data_item.push(change_binary_to_decimal_field(data_item, &mut dt_ctx));
-                    attr_data_bigint_item.push(change_binary_to_bigint_field(data_item, &mut dt_ctx));
+        //the first failure stops new trees; the trees in flight still finish, so that neither party
+        //leaves the other waiting on them and no tree thread outlives train
+        let mut in_flight = 0;
+        let mut failure = None;
+        for current_tree_index in 0..ctx.tree_count {
+            //a tree starts once one of the trees in flight is done
+            if in_flight >= concurrent_trees {
+                in_flight -= 1;
+                match next_tree(&finished) {
+                    Ok(tree) => trees.push(tree),
+                    Err(error) => {
+                        failure = Some(error);
+                        break;
+                    }
                 }
-                attr_values.push(attr_data_item);
-                attr_values_bigint.push(attr_data_bigint_item);
             }
-            dt_ctx.dt_data.attr_values = attr_values;
-            dt_ctx.dt_data.attr_values_big_integer = attr_values_bigint;
+            let dt_shares = match &prefetched {
+                Some(prefetched) => prefetched.recv()
+                    .map_err(|_| MpcError::Protocol("the share source failed while prefetching".to_string()))
+                    .and_then(|shares| shares),
+                None => ctx.share_source.receive_shares(),
+            };
+            let dt_shares = match dt_shares.and_then(|dt_shares| TripleBudget::for_party(ctx).check(&dt_shares).map(|_| dt_shares)) {
+                Ok(dt_shares) => dt_shares,
+                Err(error) => {
+                    failure = Some(error);
+                    break;
+                }
+            };
+            let mut dt_ctx = ctx.clone();
+            //share sources hand over fresh shares with every index at 0
+            dt_ctx.dt_shares = dt_shares;
+            //the tree index in the hierarchy keeps each tree's messages apart on the shared channel
+            dt_ctx.thread_hierarchy.push(format!("{}", current_tree_index));
 
-            let mut class_value_bytes = dt_ctx.dt_data.class_values_bytes.clone();
-            for item in class_value_bytes.iter() {
-                class_values.push(change_binary_to_decimal_field(item, &mut dt_ctx));
-                class_values_bigint.push(change_binary_to_bigint_field(item, &mut dt_ctx));
+            let sender = sender.clone();
+            thread_pool.execute(move || {
+                let tree = panic::catch_unwind(AssertUnwindSafe(|| train_tree(&mut dt_ctx)));
+                let _ = sender.send((current_tree_index, tree));
+            });
+            in_flight += 1;
+        }
+        for _ in 0..in_flight {
+            match next_tree(&finished) {
+                Ok(tree) => trees.push(tree),
+                Err(error) => failure = failure.or(Some(error)),
             }
+        }
+        thread_pool.join();
+        ctx.thread_hierarchy.pop();
+        if let Some(error) = failure {
+            return Err(error);
+        }
 
-            dt_ctx.dt_data.class_values = class_values;
-            dt_ctx.dt_data.class_values_big_integer = class_values_bigint;
-            let dt_training = decision_tree::train(&mut dt_ctx);
-            current_p0_port += 1;
-            current_p1_port += 1;
+        trees.sort_by_key(|(index, _)| *index);
+        forest.trees = trees.into_iter().map(|(_, tree)| tree).collect();
+        Ok(forest)
+    }
+
+    /* the next tree to finish, or why it did not */
+    fn next_tree(finished: &Receiver<(usize, thread::Result<Result<SecretSharedTree, MpcError>>)>) -> Result<(usize, SecretSharedTree), MpcError> {
+        tree_result(finished.recv().map_err(|_| MpcError::Protocol("a tree thread exited without reporting".to_string()))?)
+    }
+    fn tree_result((index, tree): (usize, thread::Result<Result<SecretSharedTree, MpcError>>)) -> Result<(usize, SecretSharedTree), MpcError> {
+        match tree {
+            Ok(tree) => Ok((index, tree?)),
+            Err(_) => Err(MpcError::Protocol(format!("tree {} panicked", index))),
         }
+    }
 
-        thread_pool.join();
+    /* the public choices a tree draws from its joint seed */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct TreeSample {
+        pub instance_weights: Vec<u64>,
+        pub attribute_mask: Vec<u8>,
+        pub max_depth: usize,
+    }
+
+    // Both parties draw the same sample from the seed, and plaintext::train_forest draws it again from
+    // the seed a model records, so the secure and the reference tree see the same weights and attributes.
+    pub fn tree_sample(seed: [u8; 32], instance_count: usize, attribute_count: usize, bootstrap: bool, max_features: &MaxFeatures,
+                       max_depth: usize) -> TreeSample {
+        let mut rng = ChaChaRng::from_seed(seed);
+        let instance_weights = if bootstrap {
+            bootstrap_weights(&mut rng, instance_count)
+        } else {
+            vec![1u64; instance_count]
+        };
+        let feature_count = max_features.feature_count(attribute_count);
+        TreeSample {
+            instance_weights,
+            attribute_mask: attribute_mask(&mut rng, attribute_count, feature_count),
+            max_depth: min(max_depth, feature_count),
+        }
+    }
+
+    fn train_tree(dt_ctx: &mut ComputingParty) -> Result<SecretSharedTree, MpcError> {
+        let seed = joint_random_seed(dt_ctx)?;
+        let sample = tree_sample(seed, dt_ctx.dt_data.instance_count, dt_ctx.dt_data.attribute_count, dt_ctx.bootstrap, &dt_ctx.max_features,
+                                 dt_ctx.dt_training.max_depth);
+        dt_ctx.dt_training.instance_weights = sample.instance_weights;
+        dt_ctx.dt_training.attribute_bit_vector = sample.attribute_mask.iter().map(|bit| AdditiveShare::public(&Z2, bit, dt_ctx.asymmetric_bit)).collect();
+        dt_ctx.dt_training.max_depth = sample.max_depth;
+        //init in java Lynx
+        let mut attr_values = Vec::new();
+        let mut class_values = Vec::new();
+        let mut attr_values_bigint = Vec::new();
+        let mut class_values_bigint = Vec::new();
+        let attr_values_bytes = dt_ctx.dt_data.attr_values_bytes.clone();
+        for item in attr_values_bytes.iter() {
+            let mut attr_data_item = Vec::new();
+            let mut attr_data_bigint_item = Vec::new();
+            for data_item in item.iter() {
+                attr_data_item.push(change_binary_to_decimal_field(data_item, dt_ctx)?);
+                attr_data_bigint_item.push(change_binary_to_bigint_field(data_item, dt_ctx)?);
+            }
+            attr_values.push(attr_data_item);
+            attr_values_bigint.push(attr_data_bigint_item);
+        }
+        dt_ctx.dt_data.attr_values = attr_values;
+        dt_ctx.dt_data.attr_values_big_integer = attr_values_bigint;
+
+        let class_value_bytes = dt_ctx.dt_data.class_values_bytes.clone();
+        for item in class_value_bytes.iter() {
+            class_values.push(change_binary_to_decimal_field(item, dt_ctx)?);
+            class_values_bigint.push(change_binary_to_bigint_field(item, dt_ctx)?);
+        }
+
+        dt_ctx.dt_data.class_values = class_values;
+        dt_ctx.dt_data.class_values_big_integer = class_values_bigint;
+        let mut tree = decision_tree::train(dt_ctx)?;
+        tree.seed = Some(seed);
+        Ok(tree)
+    }
+
+    /* how often each instance is drawn when sampling instance_count times with replacement */
+    pub fn bootstrap_weights(rng: &mut ChaChaRng, instance_count: usize) -> Vec<u64> {
+        let mut weights = vec![0u64; instance_count];
+        for _ in 0..instance_count {
+            weights[rng.gen_range(0, instance_count)] += 1;
+        }
+        weights
+    }
+
+    /* the public mask selecting feature_count attributes */
+    pub fn attribute_mask(rng: &mut ChaChaRng, attribute_count: usize, feature_count: usize) -> Vec<u8> {
+        let mut mask = vec![0u8; attribute_count];
+        for k in sample_indices(rng, attribute_count, feature_count) {
+            mask[k] = 1;
+        }
+        mask
+    }
+
+    /* shares of the predicted class for every query record loaded in place of the training data */
+    pub fn predict(ctx: &mut ComputingParty, forest: &SecretSharedForest) -> Result<Vec<Vec<AdditiveShare<Z2>>>, MpcError> {
+        ctx.thread_hierarchy.push("RF".to_string());
+        ctx.dt_shares = ctx.share_source.receive_shares()?;
+        let mut dt_ctx = ctx.clone();
+        reset_share_indices(&mut dt_ctx);
+
+        let predictions = inference::predict(forest, &mut dt_ctx)?;
         ctx.thread_hierarchy.pop();
+        Ok(predictions)
     }
 }
\ No newline at end of file
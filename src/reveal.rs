@@ -0,0 +1,264 @@
+pub mod reveal {
+    use crate::error::error::MpcError;
+    use crate::model::model::{SecretSharedForest, SecretSharedTree};
+    use crate::ring::ring::{AdditiveShare, Ring, Z2, Z64};
+    use crate::utils::utils::mod_add;
+    use num::bigint::BigUint;
+    use num::integer::Integer;
+    use num::Zero;
+    use std::num::Wrapping;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::str::FromStr;
+
+    /* the two parties' shares of one value must pair up */
+    fn check_pairs(len0: usize, len1: usize, what: &str) -> Result<(), MpcError> {
+        if len0 == len1 {
+            Ok(())
+        } else {
+            Err(MpcError::Protocol(format!("the parties hold {} and {} {}", len0, len1, what)))
+        }
+    }
+
+    pub fn reveal_binary(x_list: &[u8], y_list: &[u8]) -> Result<Vec<u8>, MpcError> {
+        check_pairs(x_list.len(), y_list.len(), "shares")?;
+        Ok(x_list.iter().zip(y_list.iter()).map(|(x, y)| x ^ y).collect())
+    }
+
+    pub fn reveal_bit_shares(x_list: &[AdditiveShare<Z2>], y_list: &[AdditiveShare<Z2>]) -> Result<Vec<u8>, MpcError> {
+        check_pairs(x_list.len(), y_list.len(), "shares")?;
+        Ok(x_list.iter().zip(y_list.iter()).map(|(x, y)| *(*x ^ *y).value()).collect())
+    }
+
+    /* what additive shares add up modulo: a prime, or 2^64 for the Wrapping<u64> shares of Z64 */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum AdditiveModulus {
+        Prime(u64),
+        TwoToThe64,
+    }
+
+    impl AdditiveModulus {
+        /* reveal's modulus argument, 2^64 when it is omitted */
+        pub fn parse(modulus: Option<&str>) -> Result<AdditiveModulus, MpcError> {
+            match modulus.map(str::trim) {
+                None | Some("2^64") => Ok(AdditiveModulus::TwoToThe64),
+                Some(modulus) => match modulus.parse::<u64>() {
+                    Ok(prime) => AdditiveModulus::Prime(prime).checked(),
+                    Err(error) => Err(MpcError::config("modulus", error)),
+                },
+            }
+        }
+
+        fn checked(self) -> Result<AdditiveModulus, MpcError> {
+            match self {
+                AdditiveModulus::Prime(0) => Err(MpcError::Usage("the modulus must be positive, or 2^64".to_string())),
+                modulus => Ok(modulus),
+            }
+        }
+    }
+
+    pub fn reveal_additive(x_list: &[Wrapping<u64>], y_list: &[Wrapping<u64>], modulus: AdditiveModulus) -> Result<Vec<u64>, MpcError> {
+        check_pairs(x_list.len(), y_list.len(), "shares")?;
+        let pairs = x_list.iter().zip(y_list.iter());
+        Ok(match modulus.checked()? {
+            AdditiveModulus::Prime(prime) => pairs.map(|(x, y)| mod_add(x.0 % prime, y.0 % prime, prime)).collect(),
+            AdditiveModulus::TwoToThe64 => pairs.map(|(x, y)| Z64.add(x, y).0).collect(),
+        })
+    }
+
+    pub fn reveal_bigint(x_list: &[BigUint], y_list: &[BigUint], big_int_prime: &BigUint) -> Result<Vec<BigUint>, MpcError> {
+        check_pairs(x_list.len(), y_list.len(), "shares")?;
+        if big_int_prime.is_zero() {
+            return Err(MpcError::Usage("the modulus must be positive".to_string()));
+        }
+        Ok(x_list.iter().zip(y_list.iter()).map(|(x, y)| (x + y).mod_floor(big_int_prime)).collect())
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct RevealedNode {
+        pub index: usize,
+        pub depth: usize,
+        pub split_attribute: Option<usize>,
+        pub is_leaf: bool,
+        pub class_index: Option<usize>,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct RevealedTree {
+        pub attr_value_count: usize,
+        pub max_depth: usize,
+        pub nodes: Vec<RevealedNode>,
+    }
+
+    fn one_hot_index(bits: &[u8]) -> Option<usize> {
+        bits.iter().position(|x| *x == 1)
+    }
+
+    pub fn reveal_tree(tree0: &SecretSharedTree, tree1: &SecretSharedTree) -> Result<RevealedTree, MpcError> {
+        check_pairs(tree0.nodes.len(), tree1.nodes.len(), "tree nodes")?;
+        let nodes = tree0.nodes.iter().zip(tree1.nodes.iter()).map(|(node0, node1)| Ok(RevealedNode {
+            index: node0.index,
+            depth: node0.depth,
+            split_attribute: one_hot_index(&reveal_bit_shares(&node0.split_attribute, &node1.split_attribute)?),
+            is_leaf: *(node0.is_leaf ^ node1.is_leaf).value() == 1,
+            class_index: one_hot_index(&reveal_bit_shares(&node0.class_index, &node1.class_index)?),
+        })).collect::<Result<Vec<RevealedNode>, MpcError>>()?;
+        Ok(RevealedTree {
+            attr_value_count: tree0.attr_value_count,
+            max_depth: tree0.max_depth,
+            nodes,
+        })
+    }
+
+    pub fn reveal_forest(forest0: &SecretSharedForest, forest1: &SecretSharedForest) -> Result<Vec<RevealedTree>, MpcError> {
+        check_pairs(forest0.trees.len(), forest1.trees.len(), "trees")?;
+        forest0.trees.iter().zip(forest1.trees.iter()).map(|(tree0, tree1)| reveal_tree(tree0, tree1)).collect()
+    }
+
+    impl RevealedTree {
+        /* the class a plaintext record is assigned, stopping at the first leaf on its path */
+        pub fn classify(&self, record: &[usize]) -> Option<usize> {
+            let mut index = 0;
+            loop {
+                let node = self.nodes.get(index)?;
+                if node.is_leaf || node.depth == self.max_depth {
+                    return node.class_index;
+                }
+                let value = *record.get(node.split_attribute?)?;
+                if value >= self.attr_value_count {
+                    return None;
+                }
+                index = index * self.attr_value_count + value + 1;
+            }
+        }
+
+        /* indented text with the subtrees below leaves pruned */
+        pub fn format(&self) -> String {
+            let mut lines = Vec::new();
+            self.format_node(0, 0, &mut lines);
+            lines.join("\n")
+        }
+
+        fn format_node(&self, index: usize, indent: usize, lines: &mut Vec<String>) {
+            let padding = "  ".repeat(indent);
+            let node = match self.nodes.get(index) {
+                Some(node) => node,
+                None => return lines.push(format!("{}missing node {}", padding, index)),
+            };
+            let class = match node.class_index {
+                Some(class) => format!("class {}", class),
+                None => "no class".to_string(),
+            };
+            match node.split_attribute {
+                Some(attribute) if !node.is_leaf && node.depth < self.max_depth => {
+                    for j in 0..self.attr_value_count {
+                        lines.push(format!("{}attribute {} = {}:", padding, attribute, j));
+                        self.format_node(index * self.attr_value_count + j + 1, indent + 1, lines);
+                    }
+                }
+                _ => lines.push(format!("{}{}", padding, class)),
+            }
+        }
+    }
+
+    fn read_rows(path: &String) -> Result<Vec<Vec<String>>, MpcError> {
+        let file = File::open(path).map_err(|e| MpcError::io(format!("failed to open share file {}", path), e))?;
+        let mut rows = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| MpcError::io(format!("failed to read share file {}", path), e))?;
+            if !line.trim().is_empty() {
+                rows.push(line.split(",").map(|x| x.trim().to_string()).collect());
+            }
+        }
+        Ok(rows)
+    }
+
+    type Rows = Vec<Vec<String>>;
+
+    /* both files' rows, which must pair up */
+    fn read_row_pairs(path0: &String, path1: &String) -> Result<(Rows, Rows), MpcError> {
+        let rows0 = read_rows(path0)?;
+        let rows1 = read_rows(path1)?;
+        if rows0.len() != rows1.len() {
+            return Err(MpcError::Deserialize(format!("{} and {}: the share files hold {} and {} rows", path0, path1, rows0.len(), rows1.len())));
+        }
+        Ok((rows0, rows1))
+    }
+
+    /* one row of path, each share parsed by parse */
+    fn parse_row<T>(path: &String, row: &[String], kind: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, MpcError> {
+        row.iter().map(|x| parse(x).ok_or_else(|| MpcError::Deserialize(format!("{}: {} is not a {} share", path, x, kind)))).collect()
+    }
+
+    // The four header lines of a load_dt_training_file share are public and identical in both files;
+    // they are copied through instead of XOR-ed.
+    fn header_line_count(rows0: &[Vec<String>], rows1: &[Vec<String>]) -> usize {
+        if rows0.len() < 4 || rows0[..4] != rows1[..4] || rows0[..4].iter().any(|row| row.len() != 1) {
+            return 0;
+        }
+        let header: Vec<usize> = match rows0[..4].iter().map(|row| row[0].parse::<usize>()).collect() {
+            Ok(header) => header,
+            Err(_) => return 0,
+        };
+        let (class_value_count, attribute_count, attr_value_count, instance_count) = (header[0], header[1], header[2], header[3]);
+        let row_count = rows0.len() - 4;
+        let matches_layout = row_count == attribute_count * attr_value_count + class_value_count
+            || row_count == attribute_count * attr_value_count;
+        if matches_layout && rows0[4..].iter().all(|row| row.len() == instance_count) { 4 } else { 0 }
+    }
+
+    pub fn reveal_binary_file(path0: &String, path1: &String) -> Result<String, MpcError> {
+        let (rows0, rows1) = read_row_pairs(path0, path1)?;
+        let header_count = header_line_count(&rows0, &rows1);
+        let mut lines: Vec<String> = rows0[..header_count].iter().map(|row| row.join(",")).collect();
+        for (row0, row1) in rows0[header_count..].iter().zip(rows1[header_count..].iter()) {
+            let binary = |x: &str| x.parse::<u8>().ok().filter(|bit| *bit < 2);
+            let x_list = parse_row(path0, row0, "binary", binary)?;
+            let y_list = parse_row(path1, row1, "binary", binary)?;
+            let line: Vec<String> = reveal_binary(&x_list, &y_list)?.iter().map(|x| x.to_string()).collect();
+            lines.push(line.join(","));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    pub fn reveal_additive_file(path0: &String, path1: &String, modulus: AdditiveModulus) -> Result<String, MpcError> {
+        let (rows0, rows1) = read_row_pairs(path0, path1)?;
+        let mut lines = Vec::new();
+        for (row0, row1) in rows0.iter().zip(rows1.iter()) {
+            let additive = |x: &str| x.parse().ok().map(Wrapping);
+            let x_list = parse_row(path0, row0, "u64", additive)?;
+            let y_list = parse_row(path1, row1, "u64", additive)?;
+            let line: Vec<String> = reveal_additive(&x_list, &y_list, modulus)?.iter().map(|x| x.to_string()).collect();
+            lines.push(line.join(","));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    pub fn reveal_bigint_file(path0: &String, path1: &String, big_int_prime: &BigUint) -> Result<String, MpcError> {
+        let (rows0, rows1) = read_row_pairs(path0, path1)?;
+        let mut lines = Vec::new();
+        for (row0, row1) in rows0.iter().zip(rows1.iter()) {
+            let bigint = |x: &str| BigUint::from_str(x).ok();
+            let x_list = parse_row(path0, row0, "decimal integer", bigint)?;
+            let y_list = parse_row(path1, row1, "decimal integer", bigint)?;
+            let line: Vec<String> = reveal_bigint(&x_list, &y_list, big_int_prime)?.iter().map(|x| x.to_str_radix(10)).collect();
+            lines.push(line.join(","));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    pub fn format_forest(trees: &[RevealedTree]) -> String {
+        let mut lines = Vec::new();
+        for (i, tree) in trees.iter().enumerate() {
+            lines.push(format!("tree {}", i));
+            lines.push(tree.format());
+        }
+        lines.join("\n")
+    }
+
+    pub fn reveal_model_file(path0: &String, path1: &String) -> Result<String, MpcError> {
+        let forest0 = SecretSharedForest::load(path0)?;
+        let forest1 = SecretSharedForest::load(path1)?;
+        Ok(format_forest(&reveal_forest(&forest0, &forest1)?))
+    }
+}
@@ -0,0 +1,302 @@
+pub mod ring {
+    use crate::computing_party::computing_party::ComputingParty;
+    use crate::error::error::MpcError;
+    use crate::message::message::Payload;
+    use crate::utils::utils::{big_uint_subtract, get_current_additive_share, get_current_additive_z64_share, get_current_bigint_share,
+                              get_current_binary_share, mod_add, mod_multiply, mod_subtract, reserve_shares};
+    use num::bigint::BigUint;
+    use num::integer::Integer;
+    use num::Zero;
+    use serde::{Deserialize, Serialize};
+    use std::fmt::Debug;
+    use std::num::Wrapping;
+    use std::ops::{BitXor, BitXorAssign};
+
+    // The rings values are secret shared in: bits in Z_2, fixed-point numbers in Z_2^64, counts in
+    // Z_p for prime and Gini scores in Z_p for big_int_prime. A Ring says how its elements add and
+    // multiply, where the dealt Beaver triples over it are found and how its elements travel, so
+    // multiplication, dot_product and or_xor are written once and a new modulus needs only a type
+    // and the TI dealing for it.
+    // A party's share of a value is an AdditiveShare<R>: the compiler keeps it apart from plaintext
+    // elements and from shares of other rings, and only the local operations valid on shares are
+    // defined on it.
+
+    /* a party's shares (u, v, w) of a Beaver triple, w = uv */
+    pub type Triple<E> = (E, E, E);
+
+    pub trait Ring: Clone + Debug + Send + Sync + 'static {
+        type Element: Clone + Debug + PartialEq + Send + Sync + 'static;
+
+        fn zero(&self) -> Self::Element;
+        fn add(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+        fn subtract(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+        fn multiply(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+
+        /* the party's shares of the next dealt triple */
+        fn triple(&self, ctx: &ComputingParty) -> Result<Triple<Self::Element>, MpcError>;
+        /* hands batch_ctx the next count triples of ctx, which skips past them */
+        fn reserve(&self, ctx: &ComputingParty, batch_ctx: &mut ComputingParty, count: usize);
+
+        fn to_payload(&self, values: Vec<Self::Element>) -> Payload;
+        fn elements(&self, payload: Payload) -> Result<Vec<Self::Element>, MpcError>;
+    }
+
+    /* bits, added by XOR and multiplied by AND, with the binary triples */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Z2;
+
+    /* integers modulo 2^64, for the fixed-point values of utils::truncate_local */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Z64;
+
+    /* integers modulo prime, with the additive triples */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Zp {
+        pub prime: u64,
+    }
+
+    /* integers modulo big_int_prime, with the BigUint triples */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct BigZp {
+        pub prime: BigUint,
+    }
+
+    impl Zp {
+        pub fn of(ctx: &ComputingParty) -> Zp {
+            Zp { prime: ctx.dt_training.prime }
+        }
+    }
+
+    impl BigZp {
+        pub fn of(ctx: &ComputingParty) -> BigZp {
+            BigZp { prime: ctx.dt_training.big_int_prime.clone() }
+        }
+    }
+
+    impl Ring for Z2 {
+        type Element = u8;
+
+        fn zero(&self) -> u8 {
+            0
+        }
+
+        fn add(&self, x: &u8, y: &u8) -> u8 {
+            x ^ y
+        }
+
+        fn subtract(&self, x: &u8, y: &u8) -> u8 {
+            x ^ y
+        }
+
+        fn multiply(&self, x: &u8, y: &u8) -> u8 {
+            x & y
+        }
+
+        fn triple(&self, ctx: &ComputingParty) -> Result<Triple<u8>, MpcError> {
+            Ok(*get_current_binary_share(ctx)?)
+        }
+
+        fn reserve(&self, ctx: &ComputingParty, batch_ctx: &mut ComputingParty, count: usize) {
+            batch_ctx.dt_shares.current_binary_index = reserve_shares(&ctx.dt_shares.current_binary_index, count);
+        }
+
+        fn to_payload(&self, values: Vec<u8>) -> Payload {
+            Payload::Bits(values)
+        }
+
+        fn elements(&self, payload: Payload) -> Result<Vec<u8>, MpcError> {
+            payload.into_bits()
+        }
+    }
+
+    impl Ring for Z64 {
+        type Element = Wrapping<u64>;
+
+        fn zero(&self) -> Wrapping<u64> {
+            Wrapping(0)
+        }
+
+        fn add(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            x + y
+        }
+
+        fn subtract(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            x - y
+        }
+
+        fn multiply(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            x * y
+        }
+
+        fn triple(&self, ctx: &ComputingParty) -> Result<Triple<Wrapping<u64>>, MpcError> {
+            Ok(*get_current_additive_z64_share(ctx)?)
+        }
+
+        fn reserve(&self, ctx: &ComputingParty, batch_ctx: &mut ComputingParty, count: usize) {
+            batch_ctx.dt_shares.current_additive_z64_index = reserve_shares(&ctx.dt_shares.current_additive_z64_index, count);
+        }
+
+        fn to_payload(&self, values: Vec<Wrapping<u64>>) -> Payload {
+            Payload::U64s(values.into_iter().map(|value| value.0).collect())
+        }
+
+        fn elements(&self, payload: Payload) -> Result<Vec<Wrapping<u64>>, MpcError> {
+            Ok(payload.into_u64s()?.into_iter().map(Wrapping).collect())
+        }
+    }
+
+    impl Ring for Zp {
+        type Element = Wrapping<u64>;
+
+        fn zero(&self) -> Wrapping<u64> {
+            Wrapping(0)
+        }
+
+        fn add(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            Wrapping(mod_add(x.0, y.0, self.prime))
+        }
+
+        fn subtract(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            Wrapping(mod_subtract(x.0, y.0, self.prime))
+        }
+
+        fn multiply(&self, x: &Wrapping<u64>, y: &Wrapping<u64>) -> Wrapping<u64> {
+            Wrapping(mod_multiply(x.0, y.0, self.prime))
+        }
+
+        fn triple(&self, ctx: &ComputingParty) -> Result<Triple<Wrapping<u64>>, MpcError> {
+            Ok(*get_current_additive_share(ctx)?)
+        }
+
+        fn reserve(&self, ctx: &ComputingParty, batch_ctx: &mut ComputingParty, count: usize) {
+            batch_ctx.dt_shares.current_additive_index = reserve_shares(&ctx.dt_shares.current_additive_index, count);
+        }
+
+        fn to_payload(&self, values: Vec<Wrapping<u64>>) -> Payload {
+            Payload::U64s(values.into_iter().map(|value| value.0).collect())
+        }
+
+        fn elements(&self, payload: Payload) -> Result<Vec<Wrapping<u64>>, MpcError> {
+            Ok(payload.into_u64s()?.into_iter().map(Wrapping).collect())
+        }
+    }
+
+    impl Ring for BigZp {
+        type Element = BigUint;
+
+        fn zero(&self) -> BigUint {
+            BigUint::zero()
+        }
+
+        fn add(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            (x + y).mod_floor(&self.prime)
+        }
+
+        fn subtract(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            big_uint_subtract(x, y, &self.prime)
+        }
+
+        fn multiply(&self, x: &BigUint, y: &BigUint) -> BigUint {
+            (x * y).mod_floor(&self.prime)
+        }
+
+        fn triple(&self, ctx: &ComputingParty) -> Result<Triple<BigUint>, MpcError> {
+            Ok(get_current_bigint_share(ctx)?.clone())
+        }
+
+        fn reserve(&self, ctx: &ComputingParty, batch_ctx: &mut ComputingParty, count: usize) {
+            batch_ctx.dt_shares.current_additive_bigint_index = reserve_shares(&ctx.dt_shares.current_additive_bigint_index, count);
+        }
+
+        fn to_payload(&self, values: Vec<BigUint>) -> Payload {
+            Payload::BigUints(values)
+        }
+
+        fn elements(&self, payload: Payload) -> Result<Vec<BigUint>, MpcError> {
+            payload.into_biguints()
+        }
+    }
+
+    /* a party's additive share of an element of R; the other party holds the rest */
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(transparent, bound(serialize = "R::Element: Serialize", deserialize = "R::Element: Deserialize<'de>"))]
+    pub struct AdditiveShare<R: Ring>(R::Element);
+
+    impl<R: Ring> AdditiveShare<R> {
+        /* this party's shares as it loaded them, from a share file or a dealer */
+        pub fn wrap(values: Vec<R::Element>) -> Vec<AdditiveShare<R>> {
+            values.into_iter().map(AdditiveShare).collect()
+        }
+
+        pub fn zero(ring: &R) -> AdditiveShare<R> {
+            AdditiveShare(ring.zero())
+        }
+
+        /* a value one party knows in the clear: the holder's share is the value, the other's zero */
+        pub fn input(ring: &R, value: &R::Element, holder: bool) -> AdditiveShare<R> {
+            AdditiveShare(if holder { value.clone() } else { ring.zero() })
+        }
+
+        /* the party with the asymmetric bit holds a public value, the other zero */
+        pub fn public(ring: &R, value: &R::Element, asymmetric_bit: u8) -> AdditiveShare<R> {
+            AdditiveShare::input(ring, value, asymmetric_bit == 1)
+        }
+
+        /* this party's share of xy from its triple (u, v, w) of x and y and the opened d = x - u, e = y - v */
+        pub fn beaver_product(ring: &R, triple: &Triple<R::Element>, d: &R::Element, e: &R::Element, asymmetric_bit: u8) -> AdditiveShare<R> {
+            let (u, v, w) = triple;
+            let product = ring.add(&ring.add(w, &ring.multiply(d, v)), &ring.multiply(u, e));
+            AdditiveShare(if asymmetric_bit == 1 { ring.add(&product, &ring.multiply(d, e)) } else { product })
+        }
+
+        pub fn value(&self) -> &R::Element {
+            &self.0
+        }
+
+        pub fn into_value(self) -> R::Element {
+            self.0
+        }
+
+        pub fn add(&self, ring: &R, other: &AdditiveShare<R>) -> AdditiveShare<R> {
+            AdditiveShare(ring.add(&self.0, &other.0))
+        }
+
+        pub fn subtract(&self, ring: &R, other: &AdditiveShare<R>) -> AdditiveShare<R> {
+            AdditiveShare(ring.subtract(&self.0, &other.0))
+        }
+
+        /* a share of the product with a public constant */
+        pub fn scale(&self, ring: &R, constant: &R::Element) -> AdditiveShare<R> {
+            AdditiveShare(ring.multiply(&self.0, constant))
+        }
+
+        pub fn sum(ring: &R, shares: &[AdditiveShare<R>]) -> AdditiveShare<R> {
+            AdditiveShare(shares.iter().fold(ring.zero(), |sum, share| ring.add(&sum, &share.0)))
+        }
+    }
+
+    // Bit shares are XOR-ed far more than anything else, so they are Copy and take ^ for the
+    // addition of Z_2.
+    impl Copy for AdditiveShare<Z2> {}
+
+    impl BitXor for AdditiveShare<Z2> {
+        type Output = AdditiveShare<Z2>;
+
+        fn bitxor(self, other: AdditiveShare<Z2>) -> AdditiveShare<Z2> {
+            AdditiveShare(self.0 ^ other.0)
+        }
+    }
+
+    impl BitXorAssign for AdditiveShare<Z2> {
+        fn bitxor_assign(&mut self, other: AdditiveShare<Z2>) {
+            self.0 ^= other.0;
+        }
+    }
+
+    impl AdditiveShare<Z2> {
+        /* a share of NOT x */
+        pub fn complement(self, asymmetric_bit: u8) -> AdditiveShare<Z2> {
+            AdditiveShare(self.0 ^ asymmetric_bit)
+        }
+    }
+}
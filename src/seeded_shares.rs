@@ -0,0 +1,211 @@
+pub mod seeded_shares {
+    use crate::decision_tree::decision_tree::DecisionTreeShares;
+    use crate::share_source::share_source::ShareCounts;
+    use crate::utils::utils::{big_uint_subtract, mod_add, mod_multiply, mod_subtract};
+    use num::bigint::{BigUint, RandBigInt};
+    use rand::prng::chacha::ChaChaRng;
+    use rand::{Rng, SeedableRng};
+    use sha2::{Digest, Sha256};
+    use std::num::Wrapping;
+    use std::sync::{Arc, Mutex};
+
+    // Correlated randomness in compressed form. Everything a party could pick at random is expanded
+    // from a PRG seed: party 0's whole triples (a0, b0, c0) and equality shares r0, and party 1's a1
+    // and b1. Only what depends on both parties' values travels explicitly, i.e. party 1's
+    // c1 = (a0 + a1)(b0 + b1) - c0 and its equality share r1 = r - r0.
+    pub struct SeededShares {
+        pub party_id: u8,
+        pub seed: [u8; 32],
+        pub prime: u64,
+        pub big_int_prime: BigUint,
+        pub additive_count: usize,
+        pub additive_z64_count: usize,
+        pub additive_bigint_count: usize,
+        pub binary_count: usize,
+        pub equality_count: usize,
+        /* party 1's explicit values, empty for party 0 */
+        pub additive_corrections: Vec<u64>,
+        pub additive_z64_corrections: Vec<u64>,
+        pub additive_bigint_corrections: Vec<BigUint>,
+        pub binary_corrections: Vec<u8>,
+        pub equality_corrections: Vec<BigUint>,
+    }
+
+    const ADDITIVE_STREAM: u8 = 0;
+    const ADDITIVE_BIGINT_STREAM: u8 = 1;
+    const BINARY_STREAM: u8 = 2;
+    const EQUALITY_STREAM: u8 = 3;
+    const ADDITIVE_Z64_STREAM: u8 = 4;
+
+    /* every kind of share draws from its own PRG, so no expansion depends on another's count */
+    fn stream_rng(seed: &[u8; 32], stream: u8) -> ChaChaRng {
+        let mut hasher = Sha256::new();
+        hasher.input(seed);
+        hasher.input([stream]);
+        let mut stream_seed = [0u8; 32];
+        stream_seed.copy_from_slice(&hasher.result());
+        ChaChaRng::from_seed(stream_seed)
+    }
+
+    /* (a, b, c) for party 0 and (a, b, 0) for party 1, whose c is a correction */
+    pub fn expand_additive(seed: &[u8; 32], party_id: u8, prime: u64, count: usize) -> Vec<(u64, u64, u64)> {
+        let mut rng = stream_rng(seed, ADDITIVE_STREAM);
+        (0..count).map(|_| {
+            let a = rng.gen_range(0, prime);
+            let b = rng.gen_range(0, prime);
+            let c = if party_id == 0 { rng.gen_range(0, prime) } else { 0 };
+            (a, b, c)
+        }).collect()
+    }
+
+    pub fn expand_additive_z64(seed: &[u8; 32], party_id: u8, count: usize) -> Vec<(u64, u64, u64)> {
+        let mut rng = stream_rng(seed, ADDITIVE_Z64_STREAM);
+        (0..count).map(|_| {
+            let a: u64 = rng.gen();
+            let b: u64 = rng.gen();
+            let c: u64 = if party_id == 0 { rng.gen() } else { 0 };
+            (a, b, c)
+        }).collect()
+    }
+
+    pub fn expand_additive_bigint(seed: &[u8; 32], party_id: u8, big_int_prime: &BigUint, count: usize) -> Vec<(BigUint, BigUint, BigUint)> {
+        let mut rng = stream_rng(seed, ADDITIVE_BIGINT_STREAM);
+        (0..count).map(|_| {
+            let a = rng.gen_biguint_below(big_int_prime);
+            let b = rng.gen_biguint_below(big_int_prime);
+            let c = if party_id == 0 { rng.gen_biguint_below(big_int_prime) } else { BigUint::from(0u8) };
+            (a, b, c)
+        }).collect()
+    }
+
+    fn random_bits(rng: &mut ChaChaRng, count: usize) -> Vec<u8> {
+        let mut bits = Vec::with_capacity(count);
+        while bits.len() < count {
+            let word: u64 = rng.gen();
+            let take = (count - bits.len()).min(64);
+            bits.extend((0..take).map(|i| ((word >> i) & 1) as u8));
+        }
+        bits
+    }
+
+    pub fn expand_binary(seed: &[u8; 32], party_id: u8, count: usize) -> Vec<(u8, u8, u8)> {
+        let mut rng = stream_rng(seed, BINARY_STREAM);
+        let a = random_bits(&mut rng, count);
+        let b = random_bits(&mut rng, count);
+        let c = if party_id == 0 { random_bits(&mut rng, count) } else { vec![0u8; count] };
+        (0..count).map(|i| (a[i], b[i], c[i])).collect()
+    }
+
+    pub fn expand_equality(seed: &[u8; 32], big_int_prime: &BigUint, count: usize) -> Vec<BigUint> {
+        let mut rng = stream_rng(seed, EQUALITY_STREAM);
+        (0..count).map(|_| rng.gen_biguint_below(big_int_prime)).collect()
+    }
+
+    // The dealer's side: party 0's view is its seed alone; party 1's view is its seed plus the values
+    // that make the reconstructed triples multiplicative and the equality masks nonzero.
+    pub fn deal<R: Rng>(rng: &mut R, prime: u64, big_int_prime: &BigUint, counts: &ShareCounts) -> (SeededShares, SeededShares) {
+        let seed0: [u8; 32] = rng.gen();
+        let seed1: [u8; 32] = rng.gen();
+        let ShareCounts { additive: additive_count, additive_z64: additive_z64_count, additive_bigint: additive_bigint_count,
+                          binary: binary_count, equality: equality_count } = *counts;
+
+        let additive0 = expand_additive(&seed0, 0, prime, additive_count);
+        let additive1 = expand_additive(&seed1, 1, prime, additive_count);
+        let additive_corrections = additive0.iter().zip(additive1.iter()).map(|(t0, t1)| {
+            let a = mod_add(t0.0, t1.0, prime);
+            let b = mod_add(t0.1, t1.1, prime);
+            mod_subtract(mod_multiply(a, b, prime), t0.2, prime)
+        }).collect();
+
+        let z64_0 = expand_additive_z64(&seed0, 0, additive_z64_count);
+        let z64_1 = expand_additive_z64(&seed1, 1, additive_z64_count);
+        let additive_z64_corrections = z64_0.iter().zip(z64_1.iter())
+            .map(|(t0, t1)| t0.0.wrapping_add(t1.0).wrapping_mul(t0.1.wrapping_add(t1.1)).wrapping_sub(t0.2))
+            .collect();
+
+        let bigint0 = expand_additive_bigint(&seed0, 0, big_int_prime, additive_bigint_count);
+        let bigint1 = expand_additive_bigint(&seed1, 1, big_int_prime, additive_bigint_count);
+        let additive_bigint_corrections = bigint0.iter().zip(bigint1.iter()).map(|(t0, t1)| {
+            let c = ((&t0.0 + &t1.0) * (&t0.1 + &t1.1)) % big_int_prime;
+            big_uint_subtract(&c, &t0.2, big_int_prime)
+        }).collect();
+
+        let binary0 = expand_binary(&seed0, 0, binary_count);
+        let binary1 = expand_binary(&seed1, 1, binary_count);
+        let binary_corrections = binary0.iter().zip(binary1.iter())
+            .map(|(t0, t1)| ((t0.0 ^ t1.0) & (t0.1 ^ t1.1)) ^ t0.2)
+            .collect();
+
+        let equality0 = expand_equality(&seed0, big_int_prime, equality_count);
+        let equality_corrections = equality0.iter()
+            .map(|r0| big_uint_subtract(&rng.gen_biguint_range(&BigUint::from(1u8), big_int_prime), r0, big_int_prime))
+            .collect();
+
+        let shares = |party_id: u8, seed: [u8; 32]| SeededShares {
+            party_id,
+            seed,
+            prime,
+            big_int_prime: big_int_prime.clone(),
+            additive_count,
+            additive_z64_count,
+            additive_bigint_count,
+            binary_count,
+            equality_count,
+            additive_corrections: Vec::new(),
+            additive_z64_corrections: Vec::new(),
+            additive_bigint_corrections: Vec::new(),
+            binary_corrections: Vec::new(),
+            equality_corrections: Vec::new(),
+        };
+        let share0 = shares(0, seed0);
+        let share1 = SeededShares {
+            additive_corrections,
+            additive_z64_corrections,
+            additive_bigint_corrections,
+            binary_corrections,
+            equality_corrections,
+            ..shares(1, seed1)
+        };
+        (share0, share1)
+    }
+
+    impl SeededShares {
+        /* the party's triples as the protocols consume them, every index at 0 */
+        pub fn expand(&self) -> DecisionTreeShares {
+            let mut additive_triples = expand_additive(&self.seed, self.party_id, self.prime, self.additive_count);
+            let mut additive_z64_triples = expand_additive_z64(&self.seed, self.party_id, self.additive_z64_count);
+            let mut additive_bigint_triples = expand_additive_bigint(&self.seed, self.party_id, &self.big_int_prime, self.additive_bigint_count);
+            let mut binary_triples = expand_binary(&self.seed, self.party_id, self.binary_count);
+            let equality_shares = if self.party_id == 0 {
+                expand_equality(&self.seed, &self.big_int_prime, self.equality_count)
+            } else {
+                for (triple, c) in additive_triples.iter_mut().zip(self.additive_corrections.iter()) {
+                    triple.2 = *c;
+                }
+                for (triple, c) in additive_z64_triples.iter_mut().zip(self.additive_z64_corrections.iter()) {
+                    triple.2 = *c;
+                }
+                for (triple, c) in additive_bigint_triples.iter_mut().zip(self.additive_bigint_corrections.iter()) {
+                    triple.2 = c.clone();
+                }
+                for (triple, c) in binary_triples.iter_mut().zip(self.binary_corrections.iter()) {
+                    triple.2 = *c;
+                }
+                self.equality_corrections.clone()
+            };
+
+            DecisionTreeShares {
+                additive_triples: Arc::new(additive_triples.into_iter().map(|(a, b, c)| (Wrapping(a), Wrapping(b), Wrapping(c))).collect()),
+                additive_z64_triples: Arc::new(additive_z64_triples.into_iter().map(|(a, b, c)| (Wrapping(a), Wrapping(b), Wrapping(c))).collect()),
+                additive_bigint_triples: Arc::new(additive_bigint_triples),
+                binary_triples: Arc::new(binary_triples),
+                equality_shares: Arc::new(equality_shares),
+                current_additive_index: Arc::new(Mutex::new(0)),
+                current_additive_z64_index: Arc::new(Mutex::new(0)),
+                current_additive_bigint_index: Arc::new(Mutex::new(0)),
+                current_equality_index: Arc::new(Mutex::new(0)),
+                current_binary_index: Arc::new(Mutex::new(0)),
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+pub mod settings {
+    use crate::error::error::{ConfigProblem, MpcError};
+    use crate::paillier::paillier::DEFAULT_MODULUS_BITS;
+    use crate::parameters::parameters::{check_modulus, STATISTICAL_SECURITY};
+    use crate::random_forest::random_forest::MaxFeatures;
+    use crate::share_source::share_source::ShareSourceKind;
+    use crate::share_source::share_source::ShareCounts;
+    use crate::budget::budget::{TripleBudget, SHAPE_KEYS};
+    use num::bigint::BigUint;
+    use serde::de::{DeserializeOwned, Error};
+    use serde::{Deserialize, Deserializer};
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    // The typed sections of a settings file. Loading one first checks every field of the section's
+    // struct on its own, then deserialises the section and checks the options against each other, so
+    // that a run reports all of a file's problems together rather than the first. Options no section
+    // knows are left alone: the files also carry options of other roles.
+
+    /* the settings file, overridden by APP_* environment variables */
+    pub fn load_settings(settings_file: &str) -> Result<config::Config, MpcError> {
+        let mut settings = config::Config::default();
+        settings
+            .merge(config::File::with_name(settings_file)).map_err(|error| MpcError::config(settings_file, error))?
+            .merge(config::Environment::with_prefix("APP")).map_err(|error| MpcError::config("APP_*", error))?;
+        Ok(settings)
+    }
+
+    /* a section struct that can check each of its fields on its own */
+    pub trait Section: DeserializeOwned {
+        fn field_problems(settings: &config::Config) -> Vec<ConfigProblem>;
+    }
+
+    // Declares a section struct and its Section impl. Each field is checked by deserialising a struct
+    // of that field alone, with the field's type and serde attributes, so whether an option is required,
+    // has a default or may be left out is read off the section itself.
+    macro_rules! section {
+        ($(#[$meta:meta])* pub struct $name:ident { $($(#[$field_meta:meta])* pub $field:ident: $type:ty,)* }) => {
+            $(#[$meta])*
+            pub struct $name {
+                $($(#[$field_meta])* pub $field: $type,)*
+            }
+
+            impl Section for $name {
+                fn field_problems(settings: &config::Config) -> Vec<ConfigProblem> {
+                    let mut problems = Vec::new();
+                    $({
+                        #[derive(Deserialize)]
+                        #[allow(dead_code)]
+                        struct Field {
+                            $(#[$field_meta])* $field: $type,
+                        }
+                        problems.extend(check::<Field, $type>(settings, stringify!($field)));
+                    })*
+                    problems
+                }
+            }
+        };
+    }
+
+    section! {
+        /* the options a computing party reads, save the network section */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct PartyConfig {
+            #[serde(default)]
+            pub debug_output: bool,
+            pub output_path: String,
+            #[serde(default = "default_decimal_precision")]
+            pub decimal_precision: u32,
+            #[serde(default = "default_integer_precision")]
+            pub integer_precision: u32,
+            #[serde(default = "one")]
+            pub thread_count: usize,
+            pub tree_count: usize,
+            #[serde(default = "default_batch_size")]
+            pub batch_size: usize,
+            #[serde(default = "one")]
+            pub tree_training_batch_size: usize,
+            #[serde(default = "yes")]
+            pub bootstrap: bool,
+            #[serde(default = "all_features", deserialize_with = "max_features")]
+            pub max_features: MaxFeatures,
+            #[serde(default = "one")]
+            pub prefetch_trees: usize,
+            pub max_depth: usize,
+            pub alpha: usize,
+            pub epsilon: f64,
+            pub prime: u64,
+            #[serde(deserialize_with = "big_uint")]
+            pub big_int_prime: BigUint,
+            /* the bits of big_int_prime unless set */
+            pub bit_length: Option<u64>,
+            /* 0 waits for the other party indefinitely */
+            #[serde(default)]
+            pub message_timeout_ms: u64,
+            #[serde(default = "ti_source", deserialize_with = "share_source")]
+            pub share_source: ShareSourceKind,
+            #[serde(default, deserialize_with = "directory")]
+            pub preprocessed_shares_dir: Option<String>,
+            #[serde(default = "default_modulus_bits")]
+            pub paillier_modulus_bits: usize,
+            /* keys the session handshake's MACs */
+            #[serde(default)]
+            pub session_key: Option<String>,
+            /* the share counts OT and Paillier generate, loaded as a section of their own */
+            #[serde(skip)]
+            pub shares: ShareCountConfig,
+        }
+    }
+
+    section! {
+        /* where a computing party, the other party and the TI listen, and the party's share file */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct PartyNetworkConfig {
+            pub party_id: u8,
+            pub ti_ip: String,
+            pub ti_port0: u16,
+            pub ti_port1: u16,
+            pub party0_ip: String,
+            pub party0_port: u16,
+            pub party1_ip: String,
+            pub party1_port: u16,
+            pub x_input_path: String,
+        }
+    }
+
+    section! {
+        /* the options the TI reads, save the network section */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct TiConfig {
+            pub tree_count: usize,
+            #[serde(default = "default_batch_size")]
+            pub batch_size: usize,
+            #[serde(default = "one")]
+            pub tree_training_batch_size: usize,
+            #[serde(default = "one")]
+            pub thread_count: usize,
+            #[serde(default = "one")]
+            pub prefetch_trees: usize,
+            pub prime: u64,
+            #[serde(deserialize_with = "big_uint")]
+            pub big_int_prime: BigUint,
+            /* the bits of big_int_prime unless set */
+            pub bigint_bit_size: Option<usize>,
+            /* with the shape, compute the share counts left unset */
+            pub max_depth: Option<usize>,
+            pub bit_length: Option<usize>,
+            #[serde(default = "all_features", deserialize_with = "max_features")]
+            pub max_features: MaxFeatures,
+            /* the TI writes the shares there, and the parties read them, instead of serving them; empty is unset */
+            #[serde(default, deserialize_with = "directory")]
+            pub preprocessed_shares_dir: Option<String>,
+            #[serde(default)]
+            pub session_key: Option<String>,
+            /* loaded as a section of its own */
+            #[serde(skip)]
+            pub shares: ShareCountConfig,
+        }
+    }
+
+    section! {
+        /* the share counts per tree, and the dataset shape that computes those left unset */
+        #[derive(Clone, Debug, Default, Deserialize)]
+        pub struct ShareCountConfig {
+            pub add_shares_per_tree: Option<usize>,
+            /* triples modulo 2^64; training multiplies nothing modulo 2^64, so none unless set */
+            pub add_shares_z64_per_tree: Option<usize>,
+            pub add_shares_bigint_per_tree: Option<usize>,
+            pub equality_shares_per_tree: Option<usize>,
+            pub binary_shares_per_tree: Option<usize>,
+            pub instance_count: Option<usize>,
+            pub attribute_count: Option<usize>,
+            pub attr_value_count: Option<usize>,
+            pub class_value_count: Option<usize>,
+        }
+    }
+
+    section! {
+        /* where the TI serves each party */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct TiNetworkConfig {
+            pub ti_ip: String,
+            pub ti_port0: u16,
+            pub ti_port1: u16,
+        }
+    }
+
+    section! {
+        /* what the process runs: the TI, or a computing party that trains or, with predict, predicts */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct RunConfig {
+            pub ti: bool,
+            #[serde(default)]
+            pub predict: bool,
+            /* the model share a predicting party loads */
+            pub model_path: Option<String>,
+        }
+    }
+
+    section! {
+        /* the share files of the two parties simulate trains on, in place of x_input_path */
+        #[derive(Clone, Debug, Deserialize)]
+        pub struct SimulationConfig {
+            pub party0_input_path: String,
+            pub party1_input_path: String,
+        }
+    }
+
+    /* options older settings files carry although the parties derive them */
+    const DERIVED_OPTIONS: [(&str, &str); 3] = [
+        ("dataset_size_prime", "the parameters module derives the bounds from the instance count"),
+        ("dataset_size_bit_length", "the parameters module derives the bounds from the instance count"),
+        ("big_int_ti_index", "it is always 0"),
+    ];
+
+    impl PartyConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<PartyConfig, MpcError> {
+            let (mut config, shares) = both(load::<PartyConfig>(settings, true), load::<ShareCountConfig>(settings, false))?;
+            config.shares = shares;
+            validated(config, PartyConfig::validate)
+        }
+
+        pub fn validate(&self) -> Vec<ConfigProblem> {
+            let mut problems = Vec::new();
+            check_counts(&mut problems, &[("tree_count", self.tree_count), ("thread_count", self.thread_count),
+                ("batch_size", self.batch_size), ("tree_training_batch_size", self.tree_training_batch_size)]);
+            check_primes(&mut problems, self.prime, &self.big_int_prime);
+            if let Some(bit_length) = self.bit_length {
+                check_covers(&mut problems, "bit_length", bit_length as usize, &self.big_int_prime);
+            }
+            if !(0.0..=1.0).contains(&self.epsilon) {
+                problems.push(problem("epsilon", format!("a fraction of the instances must lie in [0, 1], got {}", self.epsilon)));
+            }
+            let mask_bits = 2 * self.big_int_prime.bits() + STATISTICAL_SECURITY;
+            if self.share_source == ShareSourceKind::Paillier && self.paillier_modulus_bits <= mask_bits + 1 {
+                problems.push(problem("paillier_modulus_bits", format!("a modulus must have more than {} bits to mask products modulo big_int_prime, got {}",
+                                                                       mask_bits + 1, self.paillier_modulus_bits)));
+            }
+            if self.share_source != ShareSourceKind::Ti {
+                problems.extend(self.shares.counts(self.budget()).err().unwrap_or_default());
+            }
+            problems
+        }
+
+        /* bit_length, or the bits of big_int_prime */
+        pub fn bit_length(&self) -> u64 {
+            self.bit_length.unwrap_or(self.big_int_prime.bits() as u64)
+        }
+
+        /* message_timeout_ms bounds every wait for the other party's message */
+        pub fn message_timeout(&self) -> Option<Duration> {
+            match self.message_timeout_ms {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            }
+        }
+
+        /* None unless the settings describe the dataset */
+        pub fn budget(&self) -> Option<TripleBudget> {
+            TripleBudget::for_shape(&self.shares, self.max_depth, &self.max_features, self.prime, self.bit_length() as usize)
+        }
+
+        /* the shares per tree OT and Paillier generate */
+        pub fn share_counts(&self) -> Result<ShareCounts, MpcError> {
+            self.shares.counts(self.budget()).map_err(MpcError::Settings)
+        }
+    }
+
+    impl PartyNetworkConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<PartyNetworkConfig, MpcError> {
+            validated(load(settings, false)?, PartyNetworkConfig::validate)
+        }
+
+        pub fn validate(&self) -> Vec<ConfigProblem> {
+            let mut problems = Vec::new();
+            if self.party_id > 1 {
+                problems.push(problem("party_id", format!("must be 0 or 1, got {}", self.party_id)));
+            }
+            let addresses = [
+                ("party0_ip", "party0_port", &self.party0_ip, self.party0_port, "party 0"),
+                ("party1_ip", "party1_port", &self.party1_ip, self.party1_port, "party 1"),
+                ("ti_ip", "ti_port0", &self.ti_ip, self.ti_port0, "the TI's server for party 0"),
+                ("ti_ip", "ti_port1", &self.ti_ip, self.ti_port1, "the TI's server for party 1"),
+            ];
+            check_addresses(&mut problems, &addresses);
+            problems
+        }
+
+        /* the addresses this party listens on, connects to and reaches the TI at */
+        pub fn addresses(&self) -> (String, String, String) {
+            if self.party_id == 0 {
+                (format!("{}:{}", self.party0_ip, self.party0_port), format!("{}:{}", self.party1_ip, self.party1_port),
+                 format!("{}:{}", self.ti_ip, self.ti_port0))
+            } else {
+                (format!("{}:{}", self.party1_ip, self.party1_port), format!("{}:{}", self.party0_ip, self.party0_port),
+                 format!("{}:{}", self.ti_ip, self.ti_port1))
+            }
+        }
+    }
+
+    impl TiConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<TiConfig, MpcError> {
+            let (mut config, shares) = both(load::<TiConfig>(settings, true), load::<ShareCountConfig>(settings, false))?;
+            config.shares = shares;
+            validated(config, TiConfig::validate)
+        }
+
+        pub fn validate(&self) -> Vec<ConfigProblem> {
+            let mut problems = Vec::new();
+            check_counts(&mut problems, &[("tree_count", self.tree_count), ("thread_count", self.thread_count),
+                ("batch_size", self.batch_size), ("tree_training_batch_size", self.tree_training_batch_size)]);
+            check_primes(&mut problems, self.prime, &self.big_int_prime);
+            if let Some(bigint_bit_size) = self.bigint_bit_size {
+                check_covers(&mut problems, "bigint_bit_size", bigint_bit_size, &self.big_int_prime);
+            }
+            if let Some(bit_length) = self.bit_length {
+                check_covers(&mut problems, "bit_length", bit_length, &self.big_int_prime);
+            }
+            problems.extend(self.shares.counts(self.budget()).err().unwrap_or_default());
+            problems
+        }
+
+        /* None unless the settings describe the dataset, max_depth and bit_length */
+        pub fn budget(&self) -> Option<TripleBudget> {
+            TripleBudget::for_shape(&self.shares, self.max_depth?, &self.max_features, self.prime, self.bit_length?)
+        }
+
+        pub fn share_counts(&self) -> Result<ShareCounts, MpcError> {
+            self.shares.counts(self.budget()).map_err(MpcError::Settings)
+        }
+    }
+
+    impl ShareCountConfig {
+        /* the counts set, and those left unset from the budget */
+        pub fn counts(&self, budget: Option<TripleBudget>) -> Result<ShareCounts, Vec<ConfigProblem>> {
+            let budget = budget.map(|budget| budget.per_tree());
+            let mut problems = Vec::new();
+            let mut count = |key: &str, set: Option<usize>, computed: fn(&ShareCounts) -> usize| {
+                set.or_else(|| budget.as_ref().map(computed)).unwrap_or_else(|| {
+                    problems.push(problem(key, format!("missing; set it, or {}, max_depth and bit_length to compute it", SHAPE_KEYS.join(", "))));
+                    0
+                })
+            };
+            let counts = ShareCounts {
+                additive: count("add_shares_per_tree", self.add_shares_per_tree, |counts| counts.additive),
+                additive_z64: self.add_shares_z64_per_tree.unwrap_or(0),
+                additive_bigint: count("add_shares_bigint_per_tree", self.add_shares_bigint_per_tree, |counts| counts.additive_bigint),
+                equality: count("equality_shares_per_tree", self.equality_shares_per_tree, |counts| counts.equality),
+                binary: count("binary_shares_per_tree", self.binary_shares_per_tree, |counts| counts.binary),
+            };
+            if problems.is_empty() {
+                Ok(counts)
+            } else {
+                Err(problems)
+            }
+        }
+    }
+
+    impl TiNetworkConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<TiNetworkConfig, MpcError> {
+            validated(load(settings, false)?, TiNetworkConfig::validate)
+        }
+
+        pub fn validate(&self) -> Vec<ConfigProblem> {
+            let mut problems = Vec::new();
+            check_addresses(&mut problems, &[
+                ("ti_ip", "ti_port0", &self.ti_ip, self.ti_port0, "the server for party 0"),
+                ("ti_ip", "ti_port1", &self.ti_ip, self.ti_port1, "the server for party 1"),
+            ]);
+            problems
+        }
+    }
+
+    impl RunConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<RunConfig, MpcError> {
+            validated(load(settings, false)?, RunConfig::validate)
+        }
+
+        pub fn validate(&self) -> Vec<ConfigProblem> {
+            if self.predict && !self.ti && self.model_path.is_none() {
+                vec![problem("model_path", "missing; predict loads the model share from it".to_string())]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    impl SimulationConfig {
+        pub fn from_settings(settings: &config::Config) -> Result<SimulationConfig, MpcError> {
+            load(settings, false)
+        }
+    }
+
+    /* both sections, or the problems of either, each reported once */
+    pub fn both<A, B>(first: Result<A, MpcError>, second: Result<B, MpcError>) -> Result<(A, B), MpcError> {
+        match (first, second) {
+            (Ok(first), Ok(second)) => Ok((first, second)),
+            (Err(MpcError::Settings(mut problems)), Err(MpcError::Settings(more))) => {
+                for problem in more {
+                    if !problems.contains(&problem) {
+                        problems.push(problem);
+                    }
+                }
+                Err(MpcError::Settings(problems))
+            }
+            (Err(error), _) | (_, Err(error)) => Err(error),
+        }
+    }
+
+    fn load<T: Section>(settings: &config::Config, warn_derived: bool) -> Result<T, MpcError> {
+        let problems = T::field_problems(settings);
+        if warn_derived {
+            for (key, reason) in deprecated_options(settings) {
+                eprintln!("settings:  {} is deprecated and ignored: {}", key, reason);
+            }
+        }
+        if !problems.is_empty() {
+            return Err(MpcError::Settings(problems));
+        }
+        settings.clone().try_into().map_err(|error| MpcError::Settings(vec![problem("settings", error.to_string())]))
+    }
+
+    fn validated<T>(section: T, validate: fn(&T) -> Vec<ConfigProblem>) -> Result<T, MpcError> {
+        let problems = validate(&section);
+        if problems.is_empty() {
+            Ok(section)
+        } else {
+            Err(MpcError::Settings(problems))
+        }
+    }
+
+    /* the options of DERIVED_OPTIONS settings carries, with why they are ignored */
+    pub fn deprecated_options(settings: &config::Config) -> Vec<(&'static str, &'static str)> {
+        DERIVED_OPTIONS.iter().cloned().filter(|(key, _)| settings.get::<config::Value>(key).is_ok()).collect()
+    }
+
+    /* what is wrong with the option key on its own, if anything, given Field, the struct of that field alone */
+    fn check<Field: DeserializeOwned, T: Setting>(settings: &config::Config, key: &str) -> Option<ConfigProblem> {
+        let mut option = HashMap::new();
+        match settings.get::<config::Value>(key) {
+            Ok(value) => {
+                if let Some(text) = T::out_of_range(&value) {
+                    return Some(problem(key, text));
+                }
+                option.insert(key.to_string(), value);
+            }
+            Err(config::ConfigError::NotFound(_)) => {}
+            Err(error) => return Some(problem(key, error.to_string())),
+        }
+        let set = !option.is_empty();
+        match config::Value::new(None, option).try_into::<Field>() {
+            Ok(_) => None,
+            Err(error) if set => Some(problem(key, error.to_string())),
+            Err(_) => Some(problem(key, "missing".to_string())),
+        }
+    }
+
+    // config deserialises an integer into any integer field with an `as` cast, so -1 reads as
+    // usize::MAX and 70000 as the port 4464. The integer types check the value before it does.
+    trait Setting {
+        fn out_of_range(_value: &config::Value) -> Option<String> {
+            None
+        }
+    }
+
+    macro_rules! bounded {
+        ($($type:ty),*) => {
+            $(impl Setting for $type {
+                fn out_of_range(value: &config::Value) -> Option<String> {
+                    match value.clone().into_int() {
+                        Ok(num) if num < 0 => Some(format!("must not be negative, got {}", num)),
+                        Ok(num) if <$type>::try_from(num).is_err() => Some(format!("must be at most {}, got {}", <$type>::MAX, num)),
+                        _ => None,
+                    }
+                }
+            })*
+        };
+    }
+
+    bounded!(u8, u16, u32, u64, usize);
+
+    impl Setting for bool {}
+
+    impl Setting for f64 {}
+
+    impl Setting for String {}
+
+    impl Setting for BigUint {}
+
+    impl Setting for MaxFeatures {}
+
+    impl Setting for ShareSourceKind {}
+
+    impl Setting for ShareCountConfig {}
+
+    impl<T: Setting> Setting for Option<T> {
+        fn out_of_range(value: &config::Value) -> Option<String> {
+            T::out_of_range(value)
+        }
+    }
+
+    fn check_counts(problems: &mut Vec<ConfigProblem>, counts: &[(&str, usize)]) {
+        for (key, count) in counts.iter() {
+            if *count == 0 {
+                problems.push(problem(key, "must be at least 1".to_string()));
+            }
+        }
+    }
+
+    fn check_primes(problems: &mut Vec<ConfigProblem>, prime: u64, big_int_prime: &BigUint) {
+        problems.extend(check_modulus("prime", &BigUint::from(prime)));
+        problems.extend(check_modulus("big_int_prime", big_int_prime));
+    }
+
+    /* the BigUint bit decompositions need a bit for every bit of big_int_prime */
+    fn check_covers(problems: &mut Vec<ConfigProblem>, key: &str, bits: usize, big_int_prime: &BigUint) {
+        if bits < big_int_prime.bits() {
+            problems.push(problem(key, format!("{} bits do not cover big_int_prime, which has {}", bits, big_int_prime.bits())));
+        }
+    }
+
+    /* every address parses, and no two servers share one */
+    fn check_addresses(problems: &mut Vec<ConfigProblem>, addresses: &[(&str, &str, &String, u16, &str)]) {
+        for (i, (ip_key, port_key, ip, port, server)) in addresses.iter().enumerate() {
+            if *port == 0 {
+                problems.push(problem(port_key, "0 is not a port".to_string()));
+                continue;
+            }
+            let address = format!("{}:{}", ip, port);
+            if address.parse::<SocketAddr>().is_err() {
+                let problem = problem(ip_key, format!("{:?} is not an IP address", ip));
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+                continue;
+            }
+            if let Some((_, _, _, _, other)) = addresses[..i].iter().find(|(_, _, other_ip, other_port, _)| other_ip == ip && other_port == port) {
+                problems.push(problem(port_key, format!("{} would listen on {}, as does {}", server, address, other)));
+            }
+        }
+    }
+
+    fn problem(key: &str, problem: String) -> ConfigProblem {
+        ConfigProblem { key: key.to_string(), problem }
+    }
+
+    /* the problem of an error raised for a single option */
+    fn problem_text(error: MpcError) -> String {
+        match error {
+            MpcError::Config { problem, .. } => problem,
+            error => error.to_string(),
+        }
+    }
+
+    fn big_uint<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        BigUint::from_str(&value).map_err(D::Error::custom)
+    }
+
+    fn max_features<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MaxFeatures, D::Error> {
+        MaxFeatures::parse(&String::deserialize(deserializer)?).map_err(|error| D::Error::custom(problem_text(error)))
+    }
+
+    fn share_source<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ShareSourceKind, D::Error> {
+        ShareSourceKind::parse(&String::deserialize(deserializer)?).map_err(|error| D::Error::custom(problem_text(error)))
+    }
+
+    /* an empty directory is none */
+    fn directory<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        Ok(Option::<String>::deserialize(deserializer)?.filter(|dir| !dir.is_empty()))
+    }
+
+    fn default_decimal_precision() -> u32 { 10 }
+
+    fn default_integer_precision() -> u32 { 15 }
+
+    fn default_batch_size() -> usize { 176 }
+
+    fn default_modulus_bits() -> usize { DEFAULT_MODULUS_BITS }
+
+    fn one() -> usize { 1 }
+
+    fn yes() -> bool { true }
+
+    fn all_features() -> MaxFeatures { MaxFeatures::All }
+
+    fn ti_source() -> ShareSourceKind { ShareSourceKind::Ti }
+}
@@ -0,0 +1,121 @@
+pub mod share {
+    use crate::error::error::MpcError;
+    use serde::{Serialize, Deserialize};
+    use std::fs::File;
+    use std::io::{Write, BufReader, BufWriter};
+    use rand::Rng;
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct AttributeSchema {
+        pub name: String,
+        /* categorical values in one-hot order; without them the column must hold 0..attr_value_count */
+        pub values: Option<Vec<String>>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct DatasetSchema {
+        pub attr_value_count: usize,
+        pub class_value_count: usize,
+        pub attributes: Vec<AttributeSchema>,
+        pub class_column: String,
+        pub classes: Option<Vec<String>>,
+    }
+
+    pub fn load_schema(path: &String) -> Result<DatasetSchema, MpcError> {
+        let file = File::open(path).map_err(|e| MpcError::io(format!("failed to open schema file {}", path), e))?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| MpcError::Deserialize(format!("schema file {}: {}", path, e)))
+    }
+
+    /* a failed read of the csv file stays an io error, a malformed record is a decoding problem */
+    fn csv_error(csv_path: &String, error: csv::Error) -> MpcError {
+        match error.into_kind() {
+            csv::ErrorKind::Io(e) => MpcError::io(format!("failed to read {}", csv_path), e),
+            kind => MpcError::Deserialize(format!("{}: {:?}", csv_path, kind)),
+        }
+    }
+
+    fn value_index(value: &str, values: &Option<Vec<String>>, value_count: usize, column: &str) -> Result<usize, String> {
+        let index = match values {
+            Some(values) => values.iter().position(|x| x == value)
+                .ok_or_else(|| format!("value {} of column {} is not listed in the schema", value, column))?,
+            None => value.parse::<usize>()
+                .map_err(|_| format!("value {} of column {} is not a value index", value, column))?,
+        };
+        if index >= value_count {
+            return Err(format!("value {} of column {} exceeds the value count {}", value, column, value_count));
+        }
+        Ok(index)
+    }
+
+    // Rows follow load_dt_training_file: one row per (attribute, value) pair, then one per class,
+    // each holding the indicator bit of every instance.
+    pub fn one_hot_encode(csv_path: &String, schema: &DatasetSchema) -> Result<Vec<Vec<u8>>, MpcError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_path(csv_path)
+            .map_err(|e| csv_error(csv_path, e))?;
+        let headers = reader.headers().map_err(|e| csv_error(csv_path, e))?.clone();
+        let column = |name: &String| headers.iter().position(|x| x == name)
+            .ok_or_else(|| MpcError::Deserialize(format!("{}: column {} not found", csv_path, name)));
+        let attribute_columns = schema.attributes.iter().map(|x| column(&x.name)).collect::<Result<Vec<usize>, MpcError>>()?;
+        let class_column = column(&schema.class_column)?;
+        let invalid = |problem: String, line: usize| MpcError::Deserialize(format!("{} line {}: {}", csv_path, line, problem));
+
+        let attribute_count = schema.attributes.len();
+        let mut one_hot_encoding = vec![Vec::new(); attribute_count * schema.attr_value_count + schema.class_value_count];
+        for (i, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| csv_error(csv_path, e))?;
+            // the header is line 1
+            let line = i + 2;
+            for (k, attribute) in schema.attributes.iter().enumerate() {
+                let index = value_index(record[attribute_columns[k]].trim(), &attribute.values, schema.attr_value_count, &attribute.name)
+                    .map_err(|problem| invalid(problem, line))?;
+                for j in 0..schema.attr_value_count {
+                    one_hot_encoding[k * schema.attr_value_count + j].push((j == index) as u8);
+                }
+            }
+            let index = value_index(record[class_column].trim(), &schema.classes, schema.class_value_count, &schema.class_column)
+                .map_err(|problem| invalid(problem, line))?;
+            for i in 0..schema.class_value_count {
+                one_hot_encoding[attribute_count * schema.attr_value_count + i].push((i == index) as u8);
+            }
+        }
+        Ok(one_hot_encoding)
+    }
+
+    pub fn xor_share(one_hot_encoding: &[Vec<u8>]) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let mut rng = rand::thread_rng();
+        let mut share0 = Vec::new();
+        let mut share1 = Vec::new();
+        for row in one_hot_encoding.iter() {
+            let mask: Vec<u8> = row.iter().map(|_| rng.gen_range(0, 2)).collect();
+            share1.push(row.iter().zip(mask.iter()).map(|(x, r)| x ^ r).collect());
+            share0.push(mask);
+        }
+        (share0, share1)
+    }
+
+    pub fn write_dt_training_file(file_path: &String, class_value_count: usize, attribute_count: usize, attr_value_count: usize, instance_count: usize, one_hot_encoding: &[Vec<u8>])
+                                  -> Result<(), MpcError> {
+        let failed = |e| MpcError::io(format!("failed to write {}", file_path), e);
+        let file = File::create(file_path).map_err(failed)?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}\n{}\n{}\n{}", class_value_count, attribute_count, attr_value_count, instance_count).map_err(failed)?;
+        for row in one_hot_encoding.iter() {
+            let line: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+            writeln!(writer, "{}", line.join(",")).map_err(failed)?;
+        }
+        writer.flush().map_err(failed)
+    }
+
+    pub fn share_dataset(csv_path: &String, schema_path: &String, party0_path: &String, party1_path: &String) -> Result<(), MpcError> {
+        let schema = load_schema(schema_path)?;
+        let one_hot_encoding = one_hot_encode(csv_path, &schema)?;
+        // a schema without attributes or classes leaves no row to count the instances of
+        let instance_count = one_hot_encoding.first().map_or(0, |row| row.len());
+        let (share0, share1) = xor_share(&one_hot_encoding);
+        let attribute_count = schema.attributes.len();
+        write_dt_training_file(party0_path, schema.class_value_count, attribute_count, schema.attr_value_count, instance_count, &share0)?;
+        write_dt_training_file(party1_path, schema.class_value_count, attribute_count, schema.attr_value_count, instance_count, &share1)
+    }
+}
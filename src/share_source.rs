@@ -0,0 +1,234 @@
+pub mod share_source {
+    use crate::channel::channel::Channel;
+    use crate::computing_party::computing_party::ti_receive;
+    use crate::decision_tree::decision_tree::DecisionTreeShares;
+    use crate::error::error::MpcError;
+    use crate::handshake::handshake::{handshake_over_stream, party_role, SessionParameters, TI_ROLE};
+    use crate::message::message::MessageError;
+    use crate::ot::ot::OtSession;
+    use crate::paillier::paillier::PaillierSession;
+    use crate::settings::settings::PartyConfig;
+    use crate::preprocessing::preprocessing::ShareManifest;
+    use crate::share_stream::share_stream::read_dt_shares;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::net::TcpStream;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use num::bigint::BigUint;
+
+    /* where a computing party obtains the correlated randomness for one tree */
+    pub trait ShareSource: Send + Sync {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError>;
+
+        /* compares the session parameters with whoever produces the shares; only a remote TI has its own */
+        fn handshake(&self, _party_id: u8, _parameters: &SessionParameters, _session_key: Option<&str>) -> Result<(), MpcError> {
+            Ok(())
+        }
+    }
+
+    /* the share_source option; the TI is the default */
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ShareSourceKind {
+        /* a trusted initializer, over TCP or from preprocessed_shares_dir */
+        Ti,
+        /* OT extension between the computing parties */
+        Ot,
+        /* Paillier encryption between the computing parties for the BigUint shares, OT extension for the rest */
+        Paillier,
+    }
+
+    impl ShareSourceKind {
+        pub fn parse(kind: &str) -> Result<ShareSourceKind, MpcError> {
+            match kind {
+                "ti" => Ok(ShareSourceKind::Ti),
+                "ot" => Ok(ShareSourceKind::Ot),
+                "paillier" => Ok(ShareSourceKind::Paillier),
+                kind => Err(MpcError::Config {
+                    key: "share_source".to_string(),
+                    problem: format!("unknown source {:?}, expected \"ti\", \"ot\" or \"paillier\"", kind),
+                }),
+            }
+        }
+    }
+
+    /* how much of each kind of correlated randomness one tree consumes */
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ShareCounts {
+        pub additive: usize,
+        pub additive_z64: usize,
+        pub additive_bigint: usize,
+        pub binary: usize,
+        pub equality: usize,
+    }
+
+    /* the TI server reached over TCP */
+    pub struct TcpShareSource {
+        ti_stream: Mutex<TcpStream>,
+    }
+
+    impl TcpShareSource {
+        pub fn new(ti_stream: TcpStream) -> TcpShareSource {
+            TcpShareSource {
+                ti_stream: Mutex::new(ti_stream),
+            }
+        }
+    }
+
+    impl ShareSource for TcpShareSource {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+            let ti_stream = self.ti_stream.lock().unwrap();
+            ti_receive(ti_stream.try_clone().map_err(|error| MpcError::io("failed to clone the TI stream", error))?)
+        }
+
+        fn handshake(&self, party_id: u8, parameters: &SessionParameters, session_key: Option<&str>) -> Result<(), MpcError> {
+            let mut ti_stream = self.ti_stream.lock().unwrap();
+            handshake_over_stream(&mut ti_stream, &party_role(party_id), TI_ROLE, parameters, session_key)
+        }
+    }
+
+    /* a TI thread in the same process; it keeps the sender */
+    pub struct InMemoryShareSource {
+        receiver: Mutex<Receiver<DecisionTreeShares>>,
+    }
+
+    impl InMemoryShareSource {
+        pub fn new() -> (Sender<DecisionTreeShares>, InMemoryShareSource) {
+            let (sender, receiver) = channel();
+            (sender, InMemoryShareSource { receiver: Mutex::new(receiver) })
+        }
+    }
+
+    impl ShareSource for InMemoryShareSource {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+            /* the TI stopped before sending all shares */
+            self.receiver.lock().unwrap().recv()
+                .map_err(|_| MpcError::PeerAbort(MessageError::Disconnected { message_id: "ti:shares".to_string() }))
+        }
+    }
+
+    // Shares the TI wrote ahead of time (see preprocessing). Every run starts again from the first
+    // tree, so the same material can be replayed while developing the online phase; outside
+    // development each run needs freshly generated shares.
+    pub struct FileShareSource {
+        dir: PathBuf,
+        files: Vec<String>,
+        next_tree: Mutex<usize>,
+    }
+
+    impl FileShareSource {
+        pub fn new(dir: &str, party_id: u8, manifest: &ShareManifest) -> FileShareSource {
+            FileShareSource {
+                dir: Path::new(dir).to_path_buf(),
+                files: manifest.party_files.get(party_id as usize).cloned().unwrap_or_default(),
+                next_tree: Mutex::new(0),
+            }
+        }
+    }
+
+    impl ShareSource for FileShareSource {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+            let mut next_tree = self.next_tree.lock().unwrap();
+            let file_name = match self.files.get(*next_tree) {
+                Some(file_name) => file_name,
+                None => return Err(MpcError::Config {
+                    key: "preprocessed_shares_dir".to_string(),
+                    problem: format!("the preprocessed shares in {} cover only {} trees", self.dir.display(), self.files.len()),
+                }),
+            };
+            let path = self.dir.join(file_name);
+            let file = File::open(&path)
+                .map_err(|error| MpcError::io(format!("failed to open {}", path.display()), error))?;
+            let shares = read_dt_shares(&mut BufReader::new(file))
+                .map_err(|error| MpcError::io(format!("failed to read {}", path.display()), error))?;
+            *next_tree += 1;
+            Ok(shares)
+        }
+    }
+
+    // Triples the two parties generate between themselves with OT extension (see ot), over the
+    // channel they train on. The base OTs run when the first tree asks for its shares.
+    pub struct OtShareSource {
+        channel: Arc<dyn Channel>,
+        party_id: u8,
+        prime: u64,
+        big_int_prime: BigUint,
+        counts: ShareCounts,
+        session: Mutex<Option<OtSession>>,
+    }
+
+    impl OtShareSource {
+        pub fn new(channel: Arc<dyn Channel>, party_id: u8, prime: u64, big_int_prime: BigUint, counts: ShareCounts) -> OtShareSource {
+            OtShareSource {
+                channel,
+                party_id,
+                prime,
+                big_int_prime,
+                counts,
+                session: Mutex::new(None),
+            }
+        }
+
+        /* the party's primes and share counts */
+        pub fn from_config(config: &PartyConfig, channel: Arc<dyn Channel>, party_id: u8) -> Result<OtShareSource, MpcError> {
+            Ok(OtShareSource::new(channel, party_id, config.prime, config.big_int_prime.clone(), config.share_counts()?))
+        }
+    }
+
+    impl ShareSource for OtShareSource {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+            let mut session = self.session.lock().unwrap();
+            if session.is_none() {
+                *session = Some(OtSession::setup(Arc::clone(&self.channel), self.party_id)?);
+            }
+            let session = session.as_mut().unwrap();
+            session.dt_shares(self.prime, &self.big_int_prime, &self.counts)
+        }
+    }
+
+    // The additively homomorphic alternative (see paillier): the BigUint triples and equality shares
+    // come from Paillier products, everything else from an OT extension on the same channel. Party
+    // 0's key pair and the base OTs are set up when the first tree asks for its shares.
+    pub struct PaillierShareSource {
+        ot: OtShareSource,
+        counts: ShareCounts,
+        modulus_bits: usize,
+        session: Mutex<Option<PaillierSession>>,
+    }
+
+    impl PaillierShareSource {
+        pub fn new(channel: Arc<dyn Channel>, party_id: u8, prime: u64, big_int_prime: BigUint, counts: ShareCounts, modulus_bits: usize) -> PaillierShareSource {
+            let ot_counts = ShareCounts { additive_bigint: 0, equality: 0, ..counts.clone() };
+            PaillierShareSource {
+                ot: OtShareSource::new(channel, party_id, prime, big_int_prime, ot_counts),
+                counts,
+                modulus_bits,
+                session: Mutex::new(None),
+            }
+        }
+
+        /* as OtShareSource::from_config, plus paillier_modulus_bits */
+        pub fn from_config(config: &PartyConfig, channel: Arc<dyn Channel>, party_id: u8) -> Result<PaillierShareSource, MpcError> {
+            Ok(PaillierShareSource::new(channel, party_id, config.prime, config.big_int_prime.clone(),
+                                        config.share_counts()?, config.paillier_modulus_bits))
+        }
+    }
+
+    impl ShareSource for PaillierShareSource {
+        fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+            let mut session = self.session.lock().unwrap();
+            let ot = &self.ot;
+            if session.is_none() {
+                *session = Some(PaillierSession::setup(Arc::clone(&ot.channel), ot.party_id, self.modulus_bits)?);
+            }
+            let session = session.as_mut().unwrap();
+            let shares = ot.receive_shares()?;
+            Ok(DecisionTreeShares {
+                additive_bigint_triples: Arc::new(session.bigint_triples(self.counts.additive_bigint, &ot.big_int_prime)?),
+                equality_shares: Arc::new(session.equality_shares(self.counts.equality, &ot.big_int_prime)?),
+                ..shares
+            })
+        }
+    }
+}
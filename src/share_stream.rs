@@ -0,0 +1,319 @@
+pub mod share_stream {
+    use crate::decision_tree::decision_tree::DecisionTreeShares;
+    use crate::seeded_shares::seeded_shares::SeededShares;
+    use num::bigint::BigUint;
+    use std::error::Error;
+    use std::fmt;
+    use std::io::{self, Read, Write};
+    use std::num::Wrapping;
+    use std::sync::{Arc, Mutex};
+
+    // The correlated randomness of one tree as the TI streams it to a party:
+    //   magic "RFTS" | u16 version | u8 form
+    // followed by the shares in full (FORM_FULL)
+    //   u64 count | count additive triples, three u64 each
+    //   u64 count | count triples modulo 2^64, three u64 each
+    //   u64 count | count bigint triples, three length-prefixed values each
+    //   u64 count | count binary triples as three bit planes (all u, then all v, then all w),
+    //               each packed 64 bits to a u64 word, least significant bit first
+    //   u64 count | count length-prefixed equality shares
+    // or by a seed to expand them from (FORM_SEEDED, see seeded_shares)
+    //   u8 party id | 32 byte seed | u64 prime | length-prefixed big_int_prime
+    //   u64 additive count | u64 modulo 2^64 count | u64 bigint count | u64 binary count | u64 equality count
+    // and, for party 1 only, its corrections
+    //   additive count u64s | modulo 2^64 count u64s | bigint count length-prefixed values
+    //   binary count bits packed as one bit plane | equality count length-prefixed values
+    // with every integer little-endian. A length-prefixed value is a u16 byte count followed by
+    // the value's little-endian bytes.
+    pub const SHARE_STREAM_MAGIC: &[u8; 4] = b"RFTS";
+    pub const SHARE_STREAM_VERSION: u16 = 3;
+    pub const FORM_FULL: u8 = 0;
+    pub const FORM_SEEDED: u8 = 1;
+    /* counts come off the stream, so a corrupt one must not reserve memory up front */
+    const PREALLOCATED_LIMIT: usize = 1 << 20;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ShareStreamError {
+        BadMagic([u8; 4]),
+        UnsupportedVersion(u16),
+        UnknownForm(u8),
+        UnknownParty(u8),
+        /* a binary triple component other than 0 or 1 cannot be bit-packed */
+        NotABit(u8),
+        /* a big integer wider than a u16 byte count can describe */
+        ValueTooWide(usize),
+    }
+
+    impl fmt::Display for ShareStreamError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ShareStreamError::BadMagic(magic) => write!(f, "not a share stream (starts with {:?})", magic),
+                ShareStreamError::UnsupportedVersion(version) =>
+                    write!(f, "share stream version {} is not supported, expected {}", version, SHARE_STREAM_VERSION),
+                ShareStreamError::UnknownForm(form) => write!(f, "unknown share stream form {}", form),
+                ShareStreamError::UnknownParty(party_id) => write!(f, "seeded shares for unknown party {}", party_id),
+                ShareStreamError::NotABit(value) => write!(f, "binary share {} is not a bit", value),
+                ShareStreamError::ValueTooWide(bytes) => write!(f, "a {} byte integer does not fit a share stream", bytes),
+            }
+        }
+    }
+
+    impl Error for ShareStreamError {}
+
+    fn invalid(error: ShareStreamError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+
+    fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+        writer.write_all(&value.to_le_bytes())
+    }
+
+    fn write_biguint<W: Write>(writer: &mut W, value: &BigUint) -> io::Result<()> {
+        let bytes = value.to_bytes_le();
+        if bytes.len() > u16::MAX as usize {
+            return Err(invalid(ShareStreamError::ValueTooWide(bytes.len())));
+        }
+        writer.write_all(&(bytes.len() as u16).to_le_bytes())?;
+        writer.write_all(&bytes)
+    }
+
+    fn write_bit_plane<W: Write>(writer: &mut W, bits: impl Iterator<Item = u8>) -> io::Result<()> {
+        let mut word = 0u64;
+        let mut filled = 0;
+        for bit in bits {
+            if bit > 1 {
+                return Err(invalid(ShareStreamError::NotABit(bit)));
+            }
+            word |= (bit as u64) << filled;
+            filled += 1;
+            if filled == 64 {
+                write_u64(writer, word)?;
+                word = 0;
+                filled = 0;
+            }
+        }
+        if filled > 0 {
+            write_u64(writer, word)?;
+        }
+        Ok(())
+    }
+
+    fn write_header<W: Write>(writer: &mut W, form: u8) -> io::Result<()> {
+        writer.write_all(SHARE_STREAM_MAGIC)?;
+        writer.write_all(&SHARE_STREAM_VERSION.to_le_bytes())?;
+        writer.write_all(&[form])
+    }
+
+    /* wrap the writer in a BufWriter; every value is written on its own */
+    pub fn write_dt_shares<W: Write>(writer: &mut W, shares: &DecisionTreeShares) -> io::Result<()> {
+        write_header(writer, FORM_FULL)?;
+
+        write_u64(writer, shares.additive_triples.len() as u64)?;
+        for (u, v, w) in shares.additive_triples.iter() {
+            write_u64(writer, u.0)?;
+            write_u64(writer, v.0)?;
+            write_u64(writer, w.0)?;
+        }
+
+        write_u64(writer, shares.additive_z64_triples.len() as u64)?;
+        for (u, v, w) in shares.additive_z64_triples.iter() {
+            write_u64(writer, u.0)?;
+            write_u64(writer, v.0)?;
+            write_u64(writer, w.0)?;
+        }
+
+        write_u64(writer, shares.additive_bigint_triples.len() as u64)?;
+        for (u, v, w) in shares.additive_bigint_triples.iter() {
+            write_biguint(writer, u)?;
+            write_biguint(writer, v)?;
+            write_biguint(writer, w)?;
+        }
+
+        let binary_triples = &shares.binary_triples;
+        write_u64(writer, binary_triples.len() as u64)?;
+        write_bit_plane(writer, binary_triples.iter().map(|triple| triple.0))?;
+        write_bit_plane(writer, binary_triples.iter().map(|triple| triple.1))?;
+        write_bit_plane(writer, binary_triples.iter().map(|triple| triple.2))?;
+
+        write_u64(writer, shares.equality_shares.len() as u64)?;
+        for share in shares.equality_shares.iter() {
+            write_biguint(writer, share)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_seeded_shares<W: Write>(writer: &mut W, shares: &SeededShares) -> io::Result<()> {
+        write_header(writer, FORM_SEEDED)?;
+        writer.write_all(&[shares.party_id])?;
+        writer.write_all(&shares.seed)?;
+        write_u64(writer, shares.prime)?;
+        write_biguint(writer, &shares.big_int_prime)?;
+        for count in [shares.additive_count, shares.additive_z64_count, shares.additive_bigint_count, shares.binary_count, shares.equality_count].iter() {
+            write_u64(writer, *count as u64)?;
+        }
+        if shares.party_id == 1 {
+            for c in shares.additive_corrections.iter() {
+                write_u64(writer, *c)?;
+            }
+            for c in shares.additive_z64_corrections.iter() {
+                write_u64(writer, *c)?;
+            }
+            for c in shares.additive_bigint_corrections.iter() {
+                write_biguint(writer, c)?;
+            }
+            write_bit_plane(writer, shares.binary_corrections.iter().cloned())?;
+            for r in shares.equality_corrections.iter() {
+                write_biguint(writer, r)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_count<R: Read>(reader: &mut R) -> io::Result<usize> {
+        Ok(read_u64(reader)? as usize)
+    }
+
+    fn read_biguint<R: Read>(reader: &mut R) -> io::Result<BigUint> {
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let mut bytes = vec![0u8; u16::from_le_bytes(length) as usize];
+        reader.read_exact(&mut bytes)?;
+        Ok(BigUint::from_bytes_le(&bytes))
+    }
+
+    fn read_bit_plane<R: Read>(reader: &mut R, bit_count: usize) -> io::Result<Vec<u8>> {
+        let mut bits = Vec::with_capacity(bit_count.min(PREALLOCATED_LIMIT));
+        for _ in 0..bit_count.div_ceil(64) {
+            let word = read_u64(reader)?;
+            let remaining = (bit_count - bits.len()).min(64);
+            bits.extend((0..remaining).map(|i| ((word >> i) & 1) as u8));
+        }
+        Ok(bits)
+    }
+
+    fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /* the shares come back with every index at 0, expanded if the TI sent a seed */
+    pub fn read_dt_shares<R: Read>(reader: &mut R) -> io::Result<DecisionTreeShares> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != SHARE_STREAM_MAGIC {
+            return Err(invalid(ShareStreamError::BadMagic(magic)));
+        }
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != SHARE_STREAM_VERSION {
+            return Err(invalid(ShareStreamError::UnsupportedVersion(version)));
+        }
+        match read_u8(reader)? {
+            FORM_FULL => read_full_shares(reader),
+            FORM_SEEDED => Ok(read_seeded_shares(reader)?.expand()),
+            form => Err(invalid(ShareStreamError::UnknownForm(form))),
+        }
+    }
+
+    fn read_full_shares<R: Read>(reader: &mut R) -> io::Result<DecisionTreeShares> {
+        let count = read_count(reader)?;
+        let mut additive_triples = Vec::with_capacity(count.min(PREALLOCATED_LIMIT));
+        for _ in 0..count {
+            additive_triples.push((Wrapping(read_u64(reader)?), Wrapping(read_u64(reader)?), Wrapping(read_u64(reader)?)));
+        }
+
+        let count = read_count(reader)?;
+        let mut additive_z64_triples = Vec::with_capacity(count.min(PREALLOCATED_LIMIT));
+        for _ in 0..count {
+            additive_z64_triples.push((Wrapping(read_u64(reader)?), Wrapping(read_u64(reader)?), Wrapping(read_u64(reader)?)));
+        }
+
+        let count = read_count(reader)?;
+        let mut additive_bigint_triples = Vec::with_capacity(count.min(PREALLOCATED_LIMIT));
+        for _ in 0..count {
+            additive_bigint_triples.push((read_biguint(reader)?, read_biguint(reader)?, read_biguint(reader)?));
+        }
+
+        let count = read_count(reader)?;
+        let u = read_bit_plane(reader, count)?;
+        let v = read_bit_plane(reader, count)?;
+        let w = read_bit_plane(reader, count)?;
+        let binary_triples = (0..count).map(|i| (u[i], v[i], w[i])).collect();
+
+        let count = read_count(reader)?;
+        let mut equality_shares = Vec::with_capacity(count.min(PREALLOCATED_LIMIT));
+        for _ in 0..count {
+            equality_shares.push(read_biguint(reader)?);
+        }
+
+        Ok(DecisionTreeShares {
+            additive_triples: Arc::new(additive_triples),
+            additive_z64_triples: Arc::new(additive_z64_triples),
+            additive_bigint_triples: Arc::new(additive_bigint_triples),
+            binary_triples: Arc::new(binary_triples),
+            equality_shares: Arc::new(equality_shares),
+            current_additive_index: Arc::new(Mutex::new(0)),
+            current_additive_z64_index: Arc::new(Mutex::new(0)),
+            current_additive_bigint_index: Arc::new(Mutex::new(0)),
+            current_equality_index: Arc::new(Mutex::new(0)),
+            current_binary_index: Arc::new(Mutex::new(0)),
+        })
+    }
+
+    fn read_seeded_shares<R: Read>(reader: &mut R) -> io::Result<SeededShares> {
+        let party_id = read_u8(reader)?;
+        if party_id > 1 {
+            return Err(invalid(ShareStreamError::UnknownParty(party_id)));
+        }
+        let mut seed = [0u8; 32];
+        reader.read_exact(&mut seed)?;
+        let prime = read_u64(reader)?;
+        let big_int_prime = read_biguint(reader)?;
+        let additive_count = read_count(reader)?;
+        let additive_z64_count = read_count(reader)?;
+        let additive_bigint_count = read_count(reader)?;
+        let binary_count = read_count(reader)?;
+        let equality_count = read_count(reader)?;
+
+        let mut shares = SeededShares {
+            party_id,
+            seed,
+            prime,
+            big_int_prime,
+            additive_count,
+            additive_z64_count,
+            additive_bigint_count,
+            binary_count,
+            equality_count,
+            additive_corrections: Vec::new(),
+            additive_z64_corrections: Vec::new(),
+            additive_bigint_corrections: Vec::new(),
+            binary_corrections: Vec::new(),
+            equality_corrections: Vec::new(),
+        };
+        if party_id == 1 {
+            for _ in 0..additive_count {
+                shares.additive_corrections.push(read_u64(reader)?);
+            }
+            for _ in 0..additive_z64_count {
+                shares.additive_z64_corrections.push(read_u64(reader)?);
+            }
+            for _ in 0..additive_bigint_count {
+                shares.additive_bigint_corrections.push(read_biguint(reader)?);
+            }
+            shares.binary_corrections = read_bit_plane(reader, binary_count)?;
+            for _ in 0..equality_count {
+                shares.equality_corrections.push(read_biguint(reader)?);
+            }
+        }
+        Ok(shares)
+    }
+}
@@ -0,0 +1,104 @@
+pub mod simulation {
+    use crate::channel::channel::{Channel, InMemoryChannel};
+    use crate::computing_party::computing_party::party_context_from_config;
+    use crate::error::error::MpcError;
+    use crate::handshake::handshake::start_session;
+    use crate::model::model::SecretSharedForest;
+    use crate::random_forest::random_forest;
+    use crate::ring::ring::{AdditiveShare, Z2};
+    use crate::reveal::reveal::{format_forest, reveal_forest, RevealedTree};
+    use crate::settings::settings::{both, load_settings, PartyConfig, SimulationConfig};
+    use crate::share_source::share_source::{InMemoryShareSource, OtShareSource, PaillierShareSource, ShareSource, ShareSourceKind};
+    use crate::ti::ti::{run_ti_in_memory, ti_context_from_settings, TI};
+    use std::fs::File;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+
+    type ShareSources = (Arc<dyn ShareSource>, Arc<dyn ShareSource>, Option<JoinHandle<()>>);
+
+    /* the parties' share sources and the TI thread feeding them, if share_source leaves one in charge */
+    fn share_sources(config: &PartyConfig, ti_ctx: TI, channel0: &Arc<dyn Channel>, channel1: &Arc<dyn Channel>)
+                     -> Result<ShareSources, MpcError> {
+        Ok(match config.share_source {
+            ShareSourceKind::Ti => {
+                let (sender0, share_source0) = InMemoryShareSource::new();
+                let (sender1, share_source1) = InMemoryShareSource::new();
+                let ti = thread::spawn(move || run_ti_in_memory(&ti_ctx, vec![sender0, sender1]));
+                (Arc::new(share_source0), Arc::new(share_source1), Some(ti))
+            }
+            ShareSourceKind::Ot => (
+                Arc::new(OtShareSource::from_config(config, Arc::clone(channel0), 0)?),
+                Arc::new(OtShareSource::from_config(config, Arc::clone(channel1), 1)?),
+                None,
+            ),
+            ShareSourceKind::Paillier => (
+                Arc::new(PaillierShareSource::from_config(config, Arc::clone(channel0), 0)?),
+                Arc::new(PaillierShareSource::from_config(config, Arc::clone(channel1), 1)?),
+                None,
+            ),
+        })
+    }
+
+    // Runs the TI and both computing parties as threads of this process. The settings hold the
+    // union of the party and TI options; party0_input_path and party1_input_path replace x_input_path.
+    // simulate additionally writes the reconstructed forest to output_path.
+    pub fn train_in_memory(settings: &config::Config, party0_input_path: &String, party1_input_path: &String) -> Result<(SecretSharedForest, SecretSharedForest), MpcError> {
+        let (ti_ctx, config) = both(ti_context_from_settings(settings), PartyConfig::from_settings(settings))?;
+        train(ti_ctx, &config, party0_input_path, party1_input_path)
+    }
+
+    fn train(ti_ctx: TI, config: &PartyConfig, party0_input_path: &String, party1_input_path: &String) -> Result<(SecretSharedForest, SecretSharedForest), MpcError> {
+        let (channel0, channel1) = InMemoryChannel::pair(config.message_timeout());
+        let (channel0, channel1): (Arc<dyn Channel>, Arc<dyn Channel>) = (Arc::new(channel0), Arc::new(channel1));
+        let (share_source0, share_source1, ti) = share_sources(config, ti_ctx, &channel0, &channel1)?;
+        let mut ctx0 = party_context_from_config(config, 0, party0_input_path, channel0, share_source0)?;
+        let mut ctx1 = party_context_from_config(config, 1, party1_input_path, channel1, share_source1)?;
+
+        let party0 = thread::spawn(move || start_session(&ctx0).and_then(|_| random_forest::train(&mut ctx0)));
+        let party1 = thread::spawn(move || start_session(&ctx1).and_then(|_| random_forest::train(&mut ctx1)));
+
+        let forest0 = party0.join().expect("party 0 failed");
+        let forest1 = party1.join().expect("party 1 failed");
+        if let Some(ti) = ti {
+            ti.join().expect("the TI failed");
+        }
+        Ok((forest0?, forest1?))
+    }
+
+    /* party 0's and party 1's shares of the predictions */
+    type PredictionShares = (Vec<Vec<AdditiveShare<Z2>>>, Vec<Vec<AdditiveShare<Z2>>>);
+
+    /* shares of the predicted classes for the queries held in the two query share files */
+    pub fn predict_in_memory(settings: &config::Config, forest0: SecretSharedForest, forest1: SecretSharedForest, party0_query_path: &String, party1_query_path: &String) -> Result<PredictionShares, MpcError> {
+        let (mut ti_ctx, config) = both(ti_context_from_settings(settings), PartyConfig::from_settings(settings))?;
+        ti_ctx.tree_count = 1;
+        let (channel0, channel1) = InMemoryChannel::pair(config.message_timeout());
+        let (channel0, channel1): (Arc<dyn Channel>, Arc<dyn Channel>) = (Arc::new(channel0), Arc::new(channel1));
+        let (share_source0, share_source1, ti) = share_sources(&config, ti_ctx, &channel0, &channel1)?;
+        let mut ctx0 = party_context_from_config(&config, 0, party0_query_path, channel0, share_source0)?;
+        let mut ctx1 = party_context_from_config(&config, 1, party1_query_path, channel1, share_source1)?;
+
+        let party0 = thread::spawn(move || start_session(&ctx0).and_then(|_| random_forest::predict(&mut ctx0, &forest0)));
+        let party1 = thread::spawn(move || start_session(&ctx1).and_then(|_| random_forest::predict(&mut ctx1, &forest1)));
+
+        let predictions0 = party0.join().expect("party 0 failed");
+        let predictions1 = party1.join().expect("party 1 failed");
+        if let Some(ti) = ti {
+            ti.join().expect("the TI failed");
+        }
+        Ok((predictions0?, predictions1?))
+    }
+
+    pub fn simulate(settings_file: &str) -> Result<Vec<RevealedTree>, MpcError> {
+        let settings = load_settings(settings_file)?;
+        let (simulation, (ti_ctx, config)) = both(SimulationConfig::from_settings(&settings),
+                                                  both(ti_context_from_settings(&settings), PartyConfig::from_settings(&settings)))?;
+        let (forest0, forest1) = train(ti_ctx, &config, &simulation.party0_input_path, &simulation.party1_input_path)?;
+        let output_path = config.output_path;
+        let trees = reveal_forest(&forest0, &forest1)?;
+        let mut file = File::create(&output_path).map_err(|error| MpcError::io(format!("failed to create {}", output_path), error))?;
+        writeln!(file, "{}", format_forest(&trees)).map_err(|error| MpcError::io(format!("failed to write {}", output_path), error))?;
+        Ok(trees)
+    }
+}
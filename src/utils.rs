@@ -1,22 +1,22 @@
 pub mod utils {
-    use num::bigint::{BigUint, ToBigUint, ToBigInt, RandBigInt};
+    use num::bigint::{BigUint, ToBigInt};
     use num::integer::*;
     use std::ops::Sub;
     use std::num::Wrapping;
     use crate::computing_party::computing_party::ComputingParty;
+    use crate::error::error::{MpcError, Shortfall};
+    use crate::ring::ring::{AdditiveShare, BigZp, Triple, Z2};
     use std::sync::{Mutex, Arc};
 
     pub fn big_uint_subtract(x: &BigUint, y: &BigUint, big_int_prime: &BigUint) -> BigUint {
-        let result = x.to_bigint().unwrap().sub(y.to_bigint().unwrap()).mod_floor(&(big_int_prime.to_bigint().unwrap())).to_biguint().unwrap();
-        result
+        x.to_bigint().unwrap().sub(y.to_bigint().unwrap()).mod_floor(&(big_int_prime.to_bigint().unwrap())).to_biguint().unwrap()
     }
 
     pub fn big_uint_clone(x: &BigUint) -> BigUint {
-        let mut result = BigUint::from_bytes_le(&(x.to_bytes_le().clone()));
-        result
+        BigUint::from_bytes_le(&(x.to_bytes_le().clone()))
     }
 
-    pub fn big_uint_vec_clone(list: &Vec<BigUint>) -> Vec<BigUint> {
+    pub fn big_uint_vec_clone(list: &[BigUint]) -> Vec<BigUint> {
         let mut result = Vec::new();
         for item in list.iter() {
             result.push(big_uint_clone(item));
@@ -34,108 +34,89 @@ pub mod utils {
         Wrapping(x.0 >> decimal_precision)
     }
 
-    pub enum ShareType {
-        AdditiveShare,
-        AdditiveBigIntShare,
-        BinaryShare,
-        EqualityShare,
+    /* the JSON encoding the parties exchanged before the binary frames of wire */
+    pub fn serialize_biguint_vec(biguint_vec: Vec<BigUint>) -> Result<String, MpcError> {
+        let str_vec = biguint_vec.iter().map(serialize_biguint).collect::<Result<Vec<String>, MpcError>>()?;
+        Ok(str_vec.join(";"))
     }
 
-    pub fn serialize_biguint_vec(biguint_vec: Vec<BigUint>) -> String {
-        let mut str_vec = Vec::new();
-        for item in biguint_vec.iter() {
-            str_vec.push(serialize_biguint(item));
-        }
-        str_vec.join(";")
+    pub fn serialize_biguint(num: &BigUint) -> Result<String, MpcError> {
+        serde_json::to_string(&(num.to_bytes_le()))
+            .map_err(|error| MpcError::Protocol(format!("failed to encode a BigUint: {}", error)))
     }
 
-    pub fn serialize_biguint_triple_vec(biguint_triple_vec: Vec<(BigUint, BigUint, BigUint)>) -> String {
-        let mut str_vec: Vec<String> = Vec::new();
-        for item in biguint_triple_vec.iter() {
-            let mut tuple_vec = Vec::new();
-            tuple_vec.push(serialize_biguint(&item.0));
-            tuple_vec.push(serialize_biguint(&item.1));
-            tuple_vec.push(serialize_biguint(&item.2));
-            str_vec.push(format!("({})",tuple_vec.join(",")));
-        }
-        str_vec.join(";")
+    pub fn increment_current_share_index(index:Arc<Mutex<usize>>) {
+        let mut count = index.lock().unwrap();
+        *count += 1;
     }
 
+    /* hands out a private index starting at the next `count` shares so that a worker thread consumes
+       the same triples on both parties regardless of scheduling */
+    pub fn reserve_shares(index: &Arc<Mutex<usize>>, count: usize) -> Arc<Mutex<usize>> {
+        let mut current = index.lock().unwrap();
+        let start = *current;
+        *current += count;
+        Arc::new(Mutex::new(start))
+    }
 
-    pub fn serialize_biguint(num: &BigUint) -> String {
-        serde_json::to_string(&(num.to_bytes_le())).unwrap()
+    pub fn mod_add(x: u64, y: u64, prime: u64) -> u64 {
+        ((x as u128 + y as u128) % prime as u128) as u64
     }
 
-    pub fn deserialize_biguint(message: &str) -> BigUint {
-        BigUint::from_bytes_le(message.as_bytes())
+    pub fn mod_subtract(x: u64, y: u64, prime: u64) -> u64 {
+        ((x as u128 + prime as u128 - (y % prime) as u128) % prime as u128) as u64
     }
 
-    pub fn deserialize_biguint_vec(message: String) -> Vec<BigUint> {
-        let mut result = Vec::new();
-        let str_vec: Vec<&str> = message.split(";").collect();
-        for item in str_vec {
-            result.push(deserialize_biguint(item));
-        }
-        result
+    pub fn mod_multiply(x: u64, y: u64, prime: u64) -> u64 {
+        ((x as u128 * y as u128) % prime as u128) as u64
     }
 
+    /* LSB-first bits of a value; a public constant is shared by letting the asymmetric party hold its bits */
+    pub fn to_bits(value: u64, bit_length: usize) -> Vec<u8> {
+        (0..bit_length).map(|i| if i < 64 { ((value >> i) & 1) as u8 } else { 0 }).collect()
+    }
 
-//    pub fn increment_current_share_index(ctx: &mut ComputingParty, share_type: ShareType) {
-//        match share_type {
-//            ShareType::AdditiveShare => {
-//                let mut count = ctx.dt_shares.current_additive_index.lock().unwrap();
-//                *count += 1;
-//            }
-//            ShareType::AdditiveBigIntShare => {
-//                let mut count = ctx.dt_shares.current_additive_bigint_index.lock().unwrap();
-//                *count += 1;
-//            }
-//            ShareType::BinaryShare => {
-//                let mut count = ctx.dt_shares.current_binary_index.lock().unwrap();
-//                *count += 1;
-//            }
-//            ShareType::EqualityShare => {
-//                let mut count = ctx.dt_shares.current_equality_index.lock().unwrap();
-//                *count += 1;
-//            }
-//        }
-//    }
+    pub fn biguint_to_bits(value: &BigUint, bit_length: usize) -> Vec<u8> {
+        let bytes = value.to_bytes_le();
+        (0..bit_length).map(|i| if i / 8 < bytes.len() { (bytes[i / 8] >> (i % 8)) & 1 } else { 0 }).collect()
+    }
 
-    pub fn increment_current_share_index(index:Arc<Mutex<usize>>) {
-        let mut count = index.lock().unwrap();
-        *count += 1;
+    pub fn public_bit_shares(value: u64, bit_length: usize, asymmetric_bit: u8) -> Vec<AdditiveShare<Z2>> {
+        to_bits(value, bit_length).iter().map(|bit| AdditiveShare::public(&Z2, bit, asymmetric_bit)).collect()
     }
 
 
-    pub fn get_current_bigint_share(ctx: &ComputingParty) -> &(BigUint, BigUint, BigUint) {
-        let bigint_shares =  &ctx.dt_shares.additive_bigint_triples;
-        let current_index = *(ctx.dt_shares.current_additive_bigint_index.lock().unwrap());
-        let result = &bigint_shares[current_index];
-        increment_current_share_index(Arc::clone(&ctx.dt_shares.current_additive_bigint_index));
-        result
+    // Hands out the next share of a pool. Running past the end means the share source was configured
+    // for less than the computation needs, so the error names the pool and the option sizing it.
+    fn take_share<'a, T>(pool: &'a [T], index: &Arc<Mutex<usize>>, name: &'static str, key: &'static str) -> Result<&'a T, MpcError> {
+        let mut current = index.lock().unwrap();
+        match pool.get(*current) {
+            Some(share) => {
+                *current += 1;
+                Ok(share)
+            }
+            None => Err(MpcError::Exhausted(vec![Shortfall { pool: name, key, available: pool.len(), needed: None }])),
+        }
     }
 
-    pub fn get_current_equality_share(ctx: &ComputingParty) -> &BigUint {
-        let shares = &ctx.dt_shares.equality_shares;
-        let current_index = *(ctx.dt_shares.current_equality_index.lock().unwrap());
-        let result = &shares[current_index];
-        increment_current_share_index(Arc::clone(&ctx.dt_shares.current_equality_index));
-        result
+    pub fn get_current_bigint_share(ctx: &ComputingParty) -> Result<&(BigUint, BigUint, BigUint), MpcError> {
+        take_share(&ctx.dt_shares.additive_bigint_triples, &ctx.dt_shares.current_additive_bigint_index, "BigUint triples", "add_shares_bigint_per_tree")
     }
 
-    pub fn get_current_additive_share(ctx:&ComputingParty)->&(Wrapping<u64>,Wrapping<u64>,Wrapping<u64>){
-        let shares = &ctx.dt_shares.additive_triples;
-        let current_index = *(ctx.dt_shares.current_additive_index.lock().unwrap());
-        let result = &shares[current_index];
-        increment_current_share_index(Arc::clone(&ctx.dt_shares.current_additive_index));
-        result
+    pub fn get_current_equality_share(ctx: &ComputingParty) -> Result<AdditiveShare<BigZp>, MpcError> {
+        take_share(&ctx.dt_shares.equality_shares, &ctx.dt_shares.current_equality_index, "equality shares", "equality_shares_per_tree")
+            .map(|share| AdditiveShare::wrap(vec![share.clone()]).remove(0))
     }
 
-    pub fn get_current_binary_share(ctx:&ComputingParty)->&(u8,u8,u8){
-        let shares = &ctx.dt_shares.binary_triples;
-        let current_index = *(ctx.dt_shares.current_binary_index.lock().unwrap());
-        let result = &shares[current_index];
-//        increment_current_share_index(Arc::clone(&ctx.dt_shares.current_binary_index));
-        result
+    pub fn get_current_additive_share(ctx:&ComputingParty)->Result<&Triple<Wrapping<u64>>, MpcError>{
+        take_share(&ctx.dt_shares.additive_triples, &ctx.dt_shares.current_additive_index, "additive triples", "add_shares_per_tree")
+    }
+
+    pub fn get_current_additive_z64_share(ctx: &ComputingParty) -> Result<&Triple<Wrapping<u64>>, MpcError> {
+        take_share(&ctx.dt_shares.additive_z64_triples, &ctx.dt_shares.current_additive_z64_index, "triples modulo 2^64", "add_shares_z64_per_tree")
+    }
+
+    pub fn get_current_binary_share(ctx:&ComputingParty)->Result<&(u8,u8,u8), MpcError>{
+        take_share(&ctx.dt_shares.binary_triples, &ctx.dt_shares.current_binary_index, "binary triples", "binary_shares_per_tree")
     }
-}
\ No newline at end of file
+}
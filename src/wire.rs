@@ -0,0 +1,198 @@
+pub mod wire {
+    use crate::message::message::{Payload, RFMessage};
+    use num::bigint::BigUint;
+    use std::cmp::min;
+    use std::convert::TryFrom;
+    use std::error::Error;
+    use std::fmt;
+    use std::io::{self, Read, Write};
+
+    // A frame is
+    //   u32 length of the rest | u16 id length | id (UTF-8) | u8 type tag | payload
+    // with every integer little-endian. Payloads by tag:
+    //   0 bits:     u32 bit count | bits packed eight to a byte, least significant bit first
+    //   1 u64s:     u32 count | 8 bytes per value
+    //   2 biguints: u32 count | u16 width | width bytes per value, zero-padded
+    //   3 bytes:    the remaining bytes as they are
+    const TAG_BITS: u8 = 0;
+    const TAG_U64S: u8 = 1;
+    const TAG_BIGUINTS: u8 = 2;
+    const TAG_BYTES: u8 = 3;
+
+    /* the longest frame either side writes or reads, so a corrupt length cannot exhaust memory */
+    pub const MAX_FRAME_LENGTH: usize = 1 << 28;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum WireError {
+        Truncated,
+        UnknownTag(u8),
+        InvalidMessageId,
+        TrailingBytes(usize),
+        TooLong(usize),
+    }
+
+    impl fmt::Display for WireError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                WireError::Truncated => write!(f, "frame ends before its content"),
+                WireError::UnknownTag(tag) => write!(f, "unknown payload type tag {}", tag),
+                WireError::InvalidMessageId => write!(f, "message id is not UTF-8"),
+                WireError::TrailingBytes(count) => write!(f, "{} bytes left after the payload", count),
+                WireError::TooLong(length) => write!(f, "frame of {} bytes exceeds the {} byte limit", length, MAX_FRAME_LENGTH),
+            }
+        }
+    }
+
+    impl Error for WireError {}
+
+    pub fn pack_bits(bits: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, bit) in bits.iter().enumerate() {
+            debug_assert!(*bit <= 1, "binary share {} is not a bit", bit);
+            bytes[i / 8] |= (bit & 1) << (i % 8);
+        }
+        bytes
+    }
+
+    pub fn unpack_bits(bytes: &[u8], bit_count: usize) -> Vec<u8> {
+        (0..bit_count).map(|i| (bytes[i / 8] >> (i % 8)) & 1).collect()
+    }
+
+    /* a length that does not fit its field is a message this format cannot carry */
+    fn field<T: TryFrom<usize>>(value: usize, what: &str) -> io::Result<T> {
+        T::try_from(value).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{} of {} does not fit the frame", what, value)))
+    }
+
+    fn encode_payload(payload: &Payload, frame: &mut Vec<u8>) -> io::Result<()> {
+        match payload {
+            Payload::Bits(bits) => {
+                frame.push(TAG_BITS);
+                frame.extend_from_slice(&field::<u32>(bits.len(), "bit count")?.to_le_bytes());
+                frame.extend(pack_bits(bits));
+            }
+            Payload::U64s(values) => {
+                frame.push(TAG_U64S);
+                frame.extend_from_slice(&field::<u32>(values.len(), "value count")?.to_le_bytes());
+                for value in values.iter() {
+                    frame.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Payload::BigUints(values) => {
+                frame.push(TAG_BIGUINTS);
+                let width = values.iter().map(|value| value.bits().div_ceil(8)).max().unwrap_or(0);
+                frame.extend_from_slice(&field::<u32>(values.len(), "value count")?.to_le_bytes());
+                frame.extend_from_slice(&field::<u16>(width, "value width")?.to_le_bytes());
+                for value in values.iter() {
+                    let mut bytes = value.to_bytes_le();
+                    bytes.resize(width, 0);
+                    frame.extend(bytes);
+                }
+            }
+            Payload::Bytes(bytes) => {
+                frame.push(TAG_BYTES);
+                frame.extend_from_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /* the whole frame, length prefix included */
+    pub fn encode_message(message: &RFMessage) -> io::Result<Vec<u8>> {
+        let mut frame = vec![0u8; 4];
+        frame.extend_from_slice(&field::<u16>(message.message_id.len(), "message id length")?.to_le_bytes());
+        frame.extend_from_slice(message.message_id.as_bytes());
+        encode_payload(&message.payload, &mut frame)?;
+        let length = frame.len() - 4;
+        if length > MAX_FRAME_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, WireError::TooLong(length)));
+        }
+        frame[..4].copy_from_slice(&field::<u32>(length, "frame length")?.to_le_bytes());
+        Ok(frame)
+    }
+
+    struct Cursor<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl<'a> Cursor<'a> {
+        fn take(&mut self, count: usize) -> Result<&'a [u8], WireError> {
+            if self.bytes.len() < count {
+                return Err(WireError::Truncated);
+            }
+            let (taken, rest) = self.bytes.split_at(count);
+            self.bytes = rest;
+            Ok(taken)
+        }
+
+        fn u16(&mut self) -> Result<usize, WireError> {
+            let bytes = self.take(2)?;
+            Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+        }
+
+        fn u32(&mut self) -> Result<usize, WireError> {
+            let bytes = self.take(4)?;
+            Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        }
+    }
+
+    /* a frame without its length prefix */
+    pub fn decode_message(frame: &[u8]) -> Result<RFMessage, WireError> {
+        let mut cursor = Cursor { bytes: frame };
+        let id_length = cursor.u16()?;
+        let message_id = String::from_utf8(cursor.take(id_length)?.to_vec()).map_err(|_| WireError::InvalidMessageId)?;
+        let payload = match cursor.take(1)?[0] {
+            TAG_BITS => {
+                let bit_count = cursor.u32()?;
+                Payload::Bits(unpack_bits(cursor.take(bit_count.div_ceil(8))?, bit_count))
+            }
+            TAG_U64S => {
+                let count = cursor.u32()?;
+                let mut values = Vec::with_capacity(min(count, cursor.bytes.len() / 8));
+                for _ in 0..count {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(cursor.take(8)?);
+                    values.push(u64::from_le_bytes(bytes));
+                }
+                Payload::U64s(values)
+            }
+            TAG_BIGUINTS => {
+                let count = cursor.u32()?;
+                let width = cursor.u16()?;
+                let mut values = Vec::with_capacity(min(count, cursor.bytes.len() / width.max(1)));
+                for _ in 0..count {
+                    values.push(BigUint::from_bytes_le(cursor.take(width)?));
+                }
+                Payload::BigUints(values)
+            }
+            TAG_BYTES => Payload::Bytes(cursor.take(cursor.bytes.len())?.to_vec()),
+            tag => return Err(WireError::UnknownTag(tag)),
+        };
+        if !cursor.bytes.is_empty() {
+            return Err(WireError::TrailingBytes(cursor.bytes.len()));
+        }
+        Ok(RFMessage { message_id, payload })
+    }
+
+    pub fn write_message<W: Write>(writer: &mut W, message: &RFMessage) -> io::Result<()> {
+        writer.write_all(&encode_message(message)?)
+    }
+
+    /* the next message, or None once the stream has ended between frames */
+    pub fn read_message<R: Read>(reader: &mut R) -> io::Result<Option<RFMessage>> {
+        let mut length = [0u8; 4];
+        match reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+        let length = u32::from_le_bytes(length) as usize;
+        if length > MAX_FRAME_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, WireError::TooLong(length)));
+        }
+        let mut frame = vec![0u8; length];
+        reader.read_exact(&mut frame)?;
+        decode_message(&frame)
+            .map(Some)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
@@ -0,0 +1,199 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::budget::budget::TripleBudget;
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::party_context_from_settings;
+use random_forest_rust::decision_tree::decision_tree::DecisionTreeShares;
+use random_forest_rust::error::error::{MpcError, Shortfall};
+use random_forest_rust::random_forest::random_forest;
+use random_forest_rust::settings::settings::TiConfig;
+use random_forest_rust::share_source::share_source::{InMemoryShareSource, ShareCounts, ShareSource};
+use random_forest_rust::ti::ti::{generate_dt_shares, ti_context_from_settings};
+use random_forest_rust::utils::utils::get_current_binary_share;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/* keeps what it hands out; the clones share the consumption indices */
+struct RecordingShareSource {
+    source: InMemoryShareSource,
+    received: Arc<Mutex<Vec<DecisionTreeShares>>>,
+}
+
+impl ShareSource for RecordingShareSource {
+    fn receive_shares(&self) -> Result<DecisionTreeShares, MpcError> {
+        let shares = self.source.receive_shares()?;
+        self.received.lock().unwrap().push(shares.clone());
+        Ok(shares)
+    }
+}
+
+/* 12 instances, three binary attributes and three classes */
+fn write_dataset(tag: &str) -> (String, String) {
+    let mut one_hot_encoding = vec![Vec::new(); 9];
+    for i in 0..12 {
+        let values = [i % 2, i / 2 % 2, i / 4 % 2];
+        for k in 0..3 {
+            for j in 0..2 {
+                one_hot_encoding[2 * k + j].push((values[k] == j) as u8);
+            }
+        }
+        for c in 0..3 {
+            one_hot_encoding[6 + c].push((i % 3 == c) as u8);
+        }
+    }
+    common::write_shares(tag, 3, 3, 2, &one_hot_encoding)
+}
+
+fn shape_settings(overrides: &[(&str, &str)]) -> config::Config {
+    let mut keys = vec![("instance_count", "12"), ("attribute_count", "3"), ("attr_value_count", "2"), ("class_value_count", "3"), ("max_depth", "2")];
+    keys.extend(overrides.iter().cloned());
+    common::settings(&keys)
+}
+
+/* trains two trees on shares sized by counts and returns the shares each party received */
+fn train_with(counts: &ShareCounts, tag: &str) -> Vec<DecisionTreeShares> {
+    let (path0, path1) = write_dataset(tag);
+    let (additive, bigint, binary, equality) = (counts.additive.to_string(), counts.additive_bigint.to_string(), counts.binary.to_string(), counts.equality.to_string());
+    let settings = shape_settings(&[
+        ("tree_count", "2"), ("add_shares_per_tree", &additive), ("add_shares_bigint_per_tree", &bigint),
+        ("binary_shares_per_tree", &binary), ("equality_shares_per_tree", &equality),
+    ]);
+    let ti = ti_context_from_settings(&settings).unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let (sender0, source0) = InMemoryShareSource::new();
+    let (sender1, source1) = InMemoryShareSource::new();
+    for i in 0..2 {
+        let (shares0, shares1) = generate_dt_shares(&ti, i);
+        sender0.send(shares0).unwrap();
+        sender1.send(shares1).unwrap();
+    }
+    let source0 = RecordingShareSource { source: source0, received: Arc::clone(&received) };
+    let mut ctx0 = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(source0)).unwrap();
+    let mut ctx1 = party_context_from_settings(&settings, 1, &path1, Arc::new(channel1), Arc::new(source1)).unwrap();
+    let party1 = thread::spawn(move || random_forest::train(&mut ctx1));
+    random_forest::train(&mut ctx0).unwrap();
+    party1.join().unwrap().unwrap();
+    let received = received.lock().unwrap().clone();
+    received
+}
+
+#[test]
+fn training_consumes_exactly_the_budget() {
+    let counts = TiConfig::from_settings(&shape_settings(&[])).unwrap().budget().unwrap().per_tree();
+    assert_eq!(counts.equality, 0);
+    let received = train_with(&counts, "budget_exact_test");
+    assert_eq!(received.len(), 2);
+    for shares in received {
+        assert_eq!(*shares.current_additive_index.lock().unwrap(), counts.additive);
+        assert_eq!(*shares.current_additive_bigint_index.lock().unwrap(), counts.additive_bigint);
+        assert_eq!(*shares.current_binary_index.lock().unwrap(), counts.binary);
+    }
+}
+
+#[test]
+fn the_budget_follows_the_shape() {
+    let budget = TiConfig::from_settings(&shape_settings(&[])).unwrap().budget().unwrap();
+    let stump = TripleBudget { max_depth: 1, ..budget.clone() }.per_tree();
+    let leaf = TripleBudget { max_depth: 0, ..budget.clone() }.per_tree();
+    let narrow = TripleBudget { feature_count: 1, ..budget.clone() }.per_tree();
+    assert!(leaf.binary < stump.binary && stump.binary < budget.per_tree().binary);
+    assert_eq!(narrow, stump);
+    /* a leaf only converts the columns and finds its majority class */
+    assert_eq!(leaf.additive_bigint, (3 * 2 + 3) * 12);
+    assert!(TiConfig::from_settings(&common::settings(&[])).unwrap().budget().is_none());
+}
+
+#[test]
+fn the_ti_sizes_unset_pools_by_the_budget() {
+    let mut settings = config::Config::default();
+    for (key, value) in [
+        ("tree_count", "1"), ("batch_size", "176"), ("tree_training_batch_size", "1"), ("thread_count", "2"),
+        ("prime", "2147483647"), ("big_int_prime", "170141183460469231731687303715884105727"), ("bit_length", "127"),
+        ("bigint_bit_size", "127"), ("max_depth", "2"), ("instance_count", "12"), ("attribute_count", "3"),
+        ("attr_value_count", "2"), ("class_value_count", "3"), ("add_shares_per_tree", "7"),
+    ].iter() {
+        settings.set(key, *value).unwrap();
+    }
+    let budget = TiConfig::from_settings(&settings).unwrap().budget().unwrap().per_tree();
+    let ti = ti_context_from_settings(&settings).unwrap();
+    assert_eq!(ti.add_shares_per_tree, 7);
+    assert_eq!(ti.binary_shares_per_tree, budget.binary);
+    assert_eq!(ti.add_shares_bigint_per_tree, budget.additive_bigint);
+    assert_eq!(ti.equality_shares_per_tree, 0);
+}
+
+#[test]
+fn short_pools_are_named() {
+    let budget = TiConfig::from_settings(&shape_settings(&[])).unwrap().budget().unwrap();
+    let counts = budget.per_tree();
+    let ti = ti_context_from_settings(&shape_settings(&[
+        ("add_shares_per_tree", &counts.additive.to_string()), ("add_shares_bigint_per_tree", &counts.additive_bigint.to_string()),
+        ("binary_shares_per_tree", &(counts.binary - 1).to_string()),
+    ])).unwrap();
+    let (shares, _) = generate_dt_shares(&ti, 0);
+    match budget.check(&shares) {
+        Err(MpcError::Exhausted(short)) => assert_eq!(short, vec![Shortfall {
+            pool: "binary triples",
+            key: "binary_shares_per_tree",
+            available: counts.binary - 1,
+            needed: Some(counts.binary),
+        }]),
+        other => panic!("expected the binary triples to fall short, got {:?}", other),
+    }
+
+    /* a protocol running past the end names the pool too */
+    let (path0, _) = write_dataset("budget_short_test");
+    let (channel0, _channel1) = InMemoryChannel::pair(None);
+    let (_sender, source) = InMemoryShareSource::new();
+    let mut ctx = party_context_from_settings(&shape_settings(&[]), 0, &path0, Arc::new(channel0), Arc::new(source)).unwrap();
+    ctx.dt_shares = shares;
+    *ctx.dt_shares.current_binary_index.lock().unwrap() = counts.binary - 1;
+    match get_current_binary_share(&ctx) {
+        Err(MpcError::Exhausted(short)) => {
+            assert_eq!(short[0].pool, "binary triples");
+            assert!(MpcError::Exhausted(short).to_string().contains("binary_shares_per_tree"));
+        }
+        other => panic!("expected the binary triples to run out, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_short_tree_waits_for_the_trees_in_flight() {
+    let budget = TiConfig::from_settings(&shape_settings(&[])).unwrap().budget().unwrap();
+    let counts = budget.per_tree();
+    let settings_for = |binary: usize| {
+        let (additive, bigint, binary) = (counts.additive.to_string(), counts.additive_bigint.to_string(), binary.to_string());
+        shape_settings(&[
+            ("tree_count", "2"), ("tree_training_batch_size", "2"), ("prefetch_trees", "0"), ("add_shares_per_tree", &additive),
+            ("add_shares_bigint_per_tree", &bigint), ("binary_shares_per_tree", &binary), ("equality_shares_per_tree", "0"),
+        ])
+    };
+    let settings = settings_for(counts.binary);
+    let (full0, full1) = generate_dt_shares(&ti_context_from_settings(&settings).unwrap(), 0);
+    let (short0, short1) = generate_dt_shares(&ti_context_from_settings(&settings_for(counts.binary - 1)).unwrap(), 1);
+
+    let (path0, path1) = write_dataset("budget_in_flight_test");
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let (sender0, source0) = InMemoryShareSource::new();
+    let (sender1, source1) = InMemoryShareSource::new();
+    for (sender, shares) in [(&sender0, full0), (&sender0, short0), (&sender1, full1), (&sender1, short1)] {
+        sender.send(shares).unwrap();
+    }
+    let source0 = RecordingShareSource { source: source0, received: Arc::clone(&received) };
+    let mut ctx0 = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(source0)).unwrap();
+    let mut ctx1 = party_context_from_settings(&settings, 1, &path1, Arc::new(channel1), Arc::new(source1)).unwrap();
+    let party1 = thread::spawn(move || random_forest::train(&mut ctx1));
+    let result0 = random_forest::train(&mut ctx0);
+    assert!(matches!(party1.join().unwrap(), Err(MpcError::Exhausted(_))));
+    match result0 {
+        Err(MpcError::Exhausted(short)) => assert_eq!(short[0].pool, "binary triples"),
+        other => panic!("expected the second tree's shares to fall short, got {:?}", other.map(|_| ())),
+    }
+    // the first tree was already training and ran to completion before train returned
+    let received = received.lock().unwrap();
+    assert_eq!(*received[0].current_binary_index.lock().unwrap(), counts.binary);
+}
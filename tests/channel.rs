@@ -0,0 +1,119 @@
+extern crate random_forest_rust;
+
+use random_forest_rust::channel::channel::{Channel, InMemoryChannel, TcpChannel};
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::message::message::{MessageError, Payload, RFMessage};
+use std::io::{ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn message(message_id: &str, message_content: &str) -> RFMessage {
+    RFMessage {
+        message_id: message_id.to_string(),
+        payload: Payload::Bytes(message_content.as_bytes().to_vec()),
+    }
+}
+
+/* both parties exchange under the same tags, out of order, from several threads */
+fn exchange_between(channel0: Arc<dyn Channel>, channel1: Arc<dyn Channel>) {
+    let mut handles = Vec::new();
+    for i in 0..8 {
+        for (party_id, channel) in [(0u8, Arc::clone(&channel0)), (1u8, Arc::clone(&channel1))] {
+            handles.push(thread::spawn(move || {
+                let id = format!("main:{}", i);
+                let received = channel.exchange(&message(&id, &format!("{}-{}", party_id, i)), party_id).unwrap();
+                assert_eq!(received.message_id, id);
+                assert_eq!(received.payload, Payload::Bytes(format!("{}-{}", 1 - party_id, i).into_bytes()));
+            }));
+        }
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn in_memory_channel_exchanges_tagged_messages() {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    exchange_between(Arc::new(channel0), Arc::new(channel1));
+}
+
+#[test]
+fn tcp_channel_exchanges_tagged_messages() {
+    let listener0 = TcpListener::bind("127.0.0.1:0").unwrap();
+    let listener1 = TcpListener::bind("127.0.0.1:0").unwrap();
+    let o_stream0 = TcpStream::connect(listener1.local_addr().unwrap()).unwrap();
+    let o_stream1 = TcpStream::connect(listener0.local_addr().unwrap()).unwrap();
+    let (in_stream0, _) = listener0.accept().unwrap();
+    let (in_stream1, _) = listener1.accept().unwrap();
+    exchange_between(Arc::new(TcpChannel::new(in_stream0, o_stream0, None)), Arc::new(TcpChannel::new(in_stream1, o_stream1, None)));
+}
+
+#[test]
+fn receive_times_out_when_the_message_is_late() {
+    let (channel0, _channel1) = InMemoryChannel::pair(Some(Duration::from_millis(50)));
+    match channel0.receive("main:late") {
+        Err(MpcError::PeerAbort(MessageError::Timeout { message_id, waited })) => {
+            assert_eq!(message_id, "main:late");
+            assert!(waited >= Duration::from_millis(50));
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[test]
+fn receive_fails_once_the_other_party_hangs_up() {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    channel1.send(&message("main:sent", "content")).unwrap();
+    drop(channel1);
+    assert_eq!(channel0.receive("main:sent").unwrap().payload, Payload::Bytes(b"content".to_vec()));
+    match channel0.receive("main:missing") {
+        Err(MpcError::PeerAbort(error)) => assert_eq!(error, MessageError::Disconnected { message_id: "main:missing".to_string() }),
+        other => panic!("expected a disconnect, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_reused_tag_fails_every_waiting_receiver() {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    channel1.send(&message("main:reused", "first")).unwrap();
+    channel1.send(&message("main:reused", "second")).unwrap();
+    match channel0.receive("main:other") {
+        Err(MpcError::PeerAbort(error)) => assert_eq!(error, MessageError::Duplicate { message_id: "main:reused".to_string() }),
+        other => panic!("expected the reused tag to be reported, got {:?}", other),
+    }
+}
+
+/* a channel whose incoming stream carries whatever bytes the test writes to the returned stream */
+fn raw_tcp_channel() -> (TcpChannel, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let raw = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (in_stream, _) = listener.accept().unwrap();
+    let o_stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    (TcpChannel::new(in_stream, o_stream, None), raw)
+}
+
+#[test]
+fn malformed_and_cut_off_frames_are_reported_to_receivers() {
+    // an empty message id and the unknown type tag 9
+    let (channel, mut raw) = raw_tcp_channel();
+    raw.write_all(&[3, 0, 0, 0, 0, 0, 9]).unwrap();
+    match channel.receive("main:next") {
+        Err(MpcError::Deserialize(problem)) => assert!(problem.contains("unknown payload type tag 9"), "{}", problem),
+        other => panic!("expected a decoding problem, got {:?}", other),
+    }
+
+    // a frame of 100 bytes that ends after 3
+    let (channel, mut raw) = raw_tcp_channel();
+    raw.write_all(&[100, 0, 0, 0, 1, 2, 3]).unwrap();
+    drop(raw);
+    match channel.receive("main:next") {
+        Err(MpcError::Io { context, error }) => {
+            assert!(context.contains("failed to read"), "{}", context);
+            assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        }
+        other => panic!("expected a read error, got {:?}", other),
+    }
+}
@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::{party_context_from_settings, ComputingParty};
+use random_forest_rust::share::share::{write_dt_training_file, xor_share};
+use random_forest_rust::share_source::share_source::InMemoryShareSource;
+use random_forest_rust::ti::ti::{generate_dt_shares, ti_context_from_settings};
+use std::env;
+use std::sync::Arc;
+
+/* the union of the party and TI options train_in_memory needs, with primes wide enough for small datasets */
+pub fn settings(overrides: &[(&str, &str)]) -> config::Config {
+    let mut settings = config::Config::default();
+    for (key, value) in [
+        ("debug_output", "false"), ("output_path", ""), ("decimal_precision", "10"), ("integer_precision", "15"),
+        ("tree_count", "1"), ("batch_size", "176"), ("tree_training_batch_size", "1"), ("thread_count", "2"),
+        ("bootstrap", "false"), ("max_features", "all"), ("max_depth", "1"), ("alpha", "8"), ("epsilon", "0.1"),
+        ("prime", "2147483647"), ("big_int_prime", "170141183460469231731687303715884105727"), ("bit_length", "127"),
+        ("add_shares_per_tree", "50000"), ("add_shares_bigint_per_tree", "20000"), ("equality_shares_per_tree", "100"),
+        ("binary_shares_per_tree", "200000"), ("bigint_bit_size", "127"),
+    ].iter().chain(overrides.iter()) {
+        settings.set(key, *value).unwrap();
+    }
+    settings
+}
+
+/* XOR-shares the one-hot rows into two share files under the temp directory named after tag */
+pub fn write_shares(tag: &str, class_value_count: usize, attribute_count: usize, attr_value_count: usize, one_hot_encoding: &[Vec<u8>]) -> (String, String) {
+    let instance_count = one_hot_encoding[0].len();
+    let (share0, share1) = xor_share(one_hot_encoding);
+    let path0 = env::temp_dir().join(format!("{}_share0.csv", tag)).to_str().unwrap().to_string();
+    let path1 = env::temp_dir().join(format!("{}_share1.csv", tag)).to_str().unwrap().to_string();
+    write_dt_training_file(&path0, class_value_count, attribute_count, attr_value_count, instance_count, &share0).unwrap();
+    write_dt_training_file(&path1, class_value_count, attribute_count, attr_value_count, instance_count, &share1).unwrap();
+    (path0, path1)
+}
+
+/* both parties' contexts over an in-memory channel, holding the TI's shares of one tree */
+pub fn parties(settings: &config::Config, path0: &String, path1: &String) -> (ComputingParty, ComputingParty) {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let mut ctx0 = party_context_from_settings(settings, 0, path0, Arc::new(channel0), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    let mut ctx1 = party_context_from_settings(settings, 1, path1, Arc::new(channel1), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    let (shares0, shares1) = generate_dt_shares(&ti_context_from_settings(settings).unwrap(), 0);
+    ctx0.dt_shares = shares0;
+    ctx1.dt_shares = shares1;
+    (ctx0, ctx1)
+}
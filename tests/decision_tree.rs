@@ -0,0 +1,69 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::plaintext::plaintext::{self, PlaintextData};
+use random_forest_rust::reveal::reveal::{reveal_forest, RevealedTree};
+use random_forest_rust::simulation::simulation::train_in_memory;
+
+/* 10 records of 3 binary attributes whose class is attribute 2 except in the last record */
+fn records() -> Vec<[usize; 4]> {
+    (0..10).map(|t| {
+        let informative = t / 2 % 2;
+        [t % 2, t / 3 % 2, informative, if t == 9 { 1 - informative } else { informative }]
+    }).collect()
+}
+
+fn one_hot(records: &[[usize; 4]]) -> Vec<Vec<u8>> {
+    let mut one_hot_encoding = vec![Vec::new(); 8];
+    for record in records.iter() {
+        for (k, value) in record.iter().enumerate() {
+            for j in 0..2 {
+                one_hot_encoding[k * 2 + j].push((*value == j) as u8);
+            }
+        }
+    }
+    one_hot_encoding
+}
+
+// Trains a single tree on all records and attributes and reconstructs it, along with the tree plaintext grows
+// on the same records. Without bootstrap and with every feature, the tree's seed leaves nothing to chance.
+fn train(tag: &str, overrides: &[(&str, &str)], max_depth: usize, cutoff_transaction_set_size: usize) -> (RevealedTree, RevealedTree) {
+    let records = records();
+    let one_hot_encoding = one_hot(&records);
+    let (path0, path1) = common::write_shares(tag, 2, 3, 2, &one_hot_encoding);
+    let (forest0, forest1) = train_in_memory(&common::settings(overrides), &path0, &path1).unwrap();
+    let mut trees = reveal_forest(&forest0, &forest1).unwrap();
+    assert_eq!(trees.len(), 1);
+
+    let data = PlaintextData::from_one_hot(2, 3, 2, records.len(), &one_hot_encoding);
+    let expected = plaintext::train_tree(&data, max_depth, 8, cutoff_transaction_set_size, &vec![1; records.len()], &[1, 1, 1]);
+    (trees.remove(0), expected)
+}
+
+#[test]
+fn depth_one_tree_matches_plaintext() {
+    let (tree, expected) = train("decision_tree_depth_one_test", &[], 1, 1);
+    assert_eq!(tree, expected);
+    assert_eq!(tree.nodes.len(), 3);
+    assert_eq!(tree.nodes[0].split_attribute, Some(2));
+    assert!(!tree.nodes[0].is_leaf);
+    for record in records().iter().take(9) {
+        assert_eq!(tree.classify(&record[..3]), Some(record[3]));
+    }
+}
+
+#[test]
+fn small_subsets_become_leaves() {
+    // 10 * 0.7 = 7 records are needed to split, so only the root does; its children's subtrees inherit their class
+    let (tree, expected) = train("decision_tree_cutoff_test", &[("max_depth", "2"), ("epsilon", "0.7")], 2, 7);
+    assert_eq!(tree, expected);
+    assert!(!tree.nodes[0].is_leaf);
+    for child in 1..3 {
+        assert!(tree.nodes[child].is_leaf);
+        for grandchild in 2 * child + 1..2 * child + 3 {
+            assert!(tree.nodes[grandchild].is_leaf);
+            assert_eq!(tree.nodes[grandchild].class_index, tree.nodes[child].class_index);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::{party_context_from_settings, ComputingParty};
+use random_forest_rust::error::error::{Conflict, MpcError};
+use random_forest_rust::handshake::handshake::{handshake_over_stream, party_role, start_session, SessionParameters, TI_ROLE};
+use random_forest_rust::settings::settings::TiConfig;
+use random_forest_rust::share_source::share_source::InMemoryShareSource;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/* the two parties' contexts, over the same twelve records unless the settings say otherwise */
+fn parties(tag: &str, settings0: &config::Config, settings1: &config::Config) -> (ComputingParty, ComputingParty) {
+    let one_hot_encoding: Vec<Vec<u8>> = (0..4).map(|j| (0..12).map(|i| ((i + j) % 2) as u8).collect()).collect();
+    let (path0, path1) = common::write_shares(tag, 2, 1, 2, &one_hot_encoding);
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let ctx0 = party_context_from_settings(settings0, 0, &path0, Arc::new(channel0), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    let ctx1 = party_context_from_settings(settings1, 1, &path1, Arc::new(channel1), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    (ctx0, ctx1)
+}
+
+fn start_both(ctx0: ComputingParty, ctx1: ComputingParty) -> (Result<(), MpcError>, Result<(), MpcError>) {
+    let party1 = thread::spawn(move || start_session(&ctx1));
+    let result0 = start_session(&ctx0);
+    (result0, party1.join().unwrap())
+}
+
+#[test]
+fn parties_with_the_same_parameters_proceed() {
+    let settings = common::settings(&[("session_key", "secret")]);
+    let (ctx0, ctx1) = parties("handshake_agree_test", &settings, &settings);
+    let (result0, result1) = start_both(ctx0, ctx1);
+    result0.unwrap();
+    result1.unwrap();
+}
+
+#[test]
+fn a_mismatch_is_reported_as_a_diff_on_both_sides() {
+    let (ctx0, ctx1) = parties("handshake_mismatch_test", &common::settings(&[("max_depth", "2")]),
+                               &common::settings(&[("max_depth", "3"), ("tree_count", "4")]));
+    match start_both(ctx0, ctx1) {
+        (Err(MpcError::Mismatch { peer: peer0, conflicts: conflicts0 }), Err(MpcError::Mismatch { peer: peer1, conflicts: conflicts1 })) => {
+            assert_eq!(peer0, "party 1");
+            assert_eq!(peer1, "party 0");
+            assert_eq!(conflicts0, vec![
+                Conflict { key: "max_depth".to_string(), own: "2".to_string(), peer: "3".to_string() },
+                Conflict { key: "tree_count".to_string(), own: "1".to_string(), peer: "4".to_string() },
+            ]);
+            assert_eq!(conflicts1[0], Conflict { key: "max_depth".to_string(), own: "3".to_string(), peer: "2".to_string() });
+        }
+        other => panic!("expected both parties to refuse, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_peer_without_the_session_key_is_refused() {
+    let (ctx0, ctx1) = parties("handshake_key_test", &common::settings(&[("session_key", "secret")]), &common::settings(&[("session_key", "guess")]));
+    match start_both(ctx0, ctx1) {
+        (Err(MpcError::Protocol(problem)), Err(MpcError::Protocol(_))) => assert!(problem.contains("session_key"), "{}", problem),
+        other => panic!("expected both parties to refuse, got {:?}", other),
+    }
+}
+
+#[test]
+fn the_ti_compares_only_the_parameters_it_knows() {
+    let settings = common::settings(&[("tree_count", "3")]);
+    let mut party = SessionParameters::default();
+    party.set("tree_count", 2);
+    party.set("prime", settings.get_str("prime").unwrap());
+    party.set("big_int_prime", settings.get_str("big_int_prime").unwrap());
+    party.set("instance_count", 12);
+    let ti = SessionParameters::for_ti(&TiConfig::from_settings(&settings).unwrap());
+    assert_eq!(ti.get("instance_count"), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        handshake_over_stream(&mut stream, TI_ROLE, &party_role(0), &ti, None)
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+    match handshake_over_stream(&mut stream, &party_role(0), TI_ROLE, &party, None) {
+        Err(MpcError::Mismatch { peer, conflicts }) => {
+            assert_eq!(peer, TI_ROLE);
+            assert_eq!(conflicts, vec![Conflict { key: "tree_count".to_string(), own: "2".to_string(), peer: "3".to_string() }]);
+        }
+        other => panic!("expected a tree_count mismatch, got {:?}", other),
+    }
+    match server.join().unwrap() {
+        Err(MpcError::Mismatch { peer, .. }) => assert_eq!(peer, "party 0"),
+        other => panic!("expected the TI to refuse as well, got {:?}", other),
+    }
+}
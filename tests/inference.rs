@@ -0,0 +1,92 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use rand::Rng;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::inference::inference::evaluate_tree;
+use random_forest_rust::model::model::SecretSharedTree;
+use random_forest_rust::reveal::reveal::{reveal_bit_shares, reveal_tree};
+use random_forest_rust::ring::ring::AdditiveShare;
+use std::thread;
+
+/* every combination of two binary attributes, twice, labelled with their XOR */
+fn queries() -> Vec<[usize; 2]> {
+    (0..8).map(|q| [q % 2, q / 2 % 2]).collect()
+}
+
+fn one_hot(queries: &[[usize; 2]]) -> Vec<Vec<u8>> {
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for query in queries.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((query[0] == j) as u8);
+            one_hot_encoding[2 + j].push((query[1] == j) as u8);
+            one_hot_encoding[4 + j].push((query[0] ^ query[1] == j) as u8);
+        }
+    }
+    one_hot_encoding
+}
+
+// The depth-2 tree for class = attribute 0 XOR attribute 1: the root splits on attribute 1 and both
+// children on attribute 0. Party 0 holds random bits and party 1 the tree XOR those bits.
+fn xor_tree() -> (SecretSharedTree, SecretSharedTree) {
+    let mut rng = rand::thread_rng();
+    let mut tree0 = SecretSharedTree::new(0, 2, 2, 2, 2);
+    let mut tree1 = SecretSharedTree::new(1, 2, 2, 2, 2);
+    for index in 0..tree0.nodes.len() {
+        let depth = tree0.nodes[index].depth;
+        let split_attribute = match depth {
+            0 => vec![0, 1],
+            1 => vec![1, 0],
+            _ => vec![0, 0],
+        };
+        let class = match index {
+            4 | 5 => vec![0, 1],
+            _ => vec![1, 0],
+        };
+        let masks: Vec<u8> = (0..5).map(|_| rng.gen_range(0, 2)).collect();
+        tree0.nodes[index].split_attribute = AdditiveShare::wrap(masks[..2].to_vec());
+        tree0.nodes[index].is_leaf = AdditiveShare::wrap(vec![masks[2]])[0];
+        tree0.nodes[index].class_index = AdditiveShare::wrap(masks[3..].to_vec());
+        tree1.nodes[index].split_attribute = AdditiveShare::wrap(split_attribute.iter().zip(masks[..2].iter()).map(|(x, m)| x ^ m).collect());
+        tree1.nodes[index].is_leaf = AdditiveShare::wrap(vec![(depth == 2) as u8 ^ masks[2]])[0];
+        tree1.nodes[index].class_index = AdditiveShare::wrap(class.iter().zip(masks[3..].iter()).map(|(x, m)| x ^ m).collect());
+    }
+    (tree0, tree1)
+}
+
+#[test]
+fn known_tree_votes_for_each_query() {
+    let queries = queries();
+    let (path0, path1) = common::write_shares("inference_known_tree_test", 2, 2, 2, &one_hot(&queries));
+    let (mut ctx0, mut ctx1) = common::parties(&common::settings(&[]), &path0, &path1);
+    let (tree0, tree1) = xor_tree();
+    let revealed = reveal_tree(&tree0, &tree1).unwrap();
+
+    let party1 = thread::spawn(move || evaluate_tree(&tree1, &mut ctx1));
+    let votes0 = evaluate_tree(&tree0, &mut ctx0).unwrap();
+    let votes1 = party1.join().unwrap().unwrap();
+
+    assert_eq!(votes0.len(), queries.len());
+    for (q, query) in queries.iter().enumerate() {
+        let vote = reveal_bit_shares(&votes0[q], &votes1[q]).unwrap();
+        let mut expected = vec![0u8; 2];
+        expected[query[0] ^ query[1]] = 1;
+        assert_eq!(vote, expected, "query {:?}", query);
+        assert_eq!(revealed.classify(query), Some(query[0] ^ query[1]));
+    }
+}
+
+#[test]
+fn trees_of_another_shape_are_refused() {
+    let (path0, path1) = common::write_shares("inference_shape_test", 2, 2, 2, &one_hot(&queries()));
+    let (mut ctx0, _ctx1) = common::parties(&common::settings(&[]), &path0, &path1);
+    match evaluate_tree(&SecretSharedTree::new(0, 3, 2, 2, 1), &mut ctx0) {
+        Err(MpcError::Config { key, .. }) => assert_eq!(key, "attribute_count"),
+        other => panic!("expected the attribute count to be refused, got {:?}", other.map(|_| ())),
+    }
+    match evaluate_tree(&SecretSharedTree::new(0, 2, 3, 2, 1), &mut ctx0) {
+        Err(MpcError::Config { key, .. }) => assert_eq!(key, "attr_value_count"),
+        other => panic!("expected the value count to be refused, got {:?}", other.map(|_| ())),
+    }
+}
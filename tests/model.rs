@@ -0,0 +1,67 @@
+extern crate random_forest_rust;
+
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::model::model::{SecretSharedForest, SecretSharedTree};
+use random_forest_rust::ring::ring::AdditiveShare;
+use std::env;
+
+#[test]
+fn trees_are_complete_and_breadth_first() {
+    let tree = SecretSharedTree::new(1, 4, 3, 2, 2);
+    assert_eq!(tree.nodes.len(), 1 + 3 + 9);
+    for (index, node) in tree.nodes.iter().enumerate() {
+        assert_eq!(node.index, index);
+        assert_eq!(node.split_attribute.len(), 4);
+        assert_eq!(node.class_index.len(), 2);
+    }
+    assert_eq!(tree.child_index(0, 2), 3);
+    assert_eq!(tree.child_index(3, 0), 10);
+    assert_eq!(tree.nodes[tree.child_index(2, 1)].depth, 2);
+    assert_eq!(tree.leaf_indices(), 4..13);
+    assert_eq!(SecretSharedTree::new(0, 4, 3, 2, 0).leaf_indices(), 0..1);
+}
+
+#[test]
+fn forests_survive_a_save_and_load() {
+    let mut forest = SecretSharedForest::new(1);
+    for t in 0..2u8 {
+        let mut tree = SecretSharedTree::new(1, 3, 2, 2, 1);
+        for node in tree.nodes.iter_mut() {
+            node.split_attribute = AdditiveShare::wrap(vec![t, 1, (node.index % 2) as u8]);
+            node.is_leaf = AdditiveShare::wrap(vec![(node.depth == 1) as u8])[0];
+            node.class_index = AdditiveShare::wrap(vec![1 - t, t]);
+        }
+        tree.seed = Some([t; 32]);
+        forest.trees.push(tree);
+    }
+    let path = env::temp_dir().join("model_round_trip_test.json").to_str().unwrap().to_string();
+    forest.save(&path).unwrap();
+
+    let loaded = SecretSharedForest::load(&path).unwrap();
+    // the model types hold shares, not comparable values, so compare their serialised form
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), serde_json::to_string(&forest).unwrap());
+    assert_eq!(loaded.party_id, 1);
+    assert_eq!(loaded.trees[1].seed, Some([1; 32]));
+    assert_eq!(*loaded.trees[1].nodes[2].split_attribute[2].value(), 0);
+}
+
+#[test]
+fn models_without_a_seed_still_load() {
+    let path = env::temp_dir().join("model_without_seed_test.json").to_str().unwrap().to_string();
+    std::fs::write(&path, r#"{"party_id":0,"trees":[{"party_id":0,"attribute_count":1,"attr_value_count":2,"class_value_count":2,"max_depth":0,
+        "nodes":[{"index":0,"depth":0,"split_attribute":[0],"is_leaf":1,"class_index":[1,0]}]}]}"#).unwrap();
+    let forest = SecretSharedForest::load(&path).unwrap();
+    assert_eq!(forest.trees[0].seed, None);
+    assert_eq!(*forest.trees[0].nodes[0].class_index[0].value(), 1);
+}
+
+#[test]
+fn unreadable_models_are_reported() {
+    let path = env::temp_dir().join("model_truncated_test.json").to_str().unwrap().to_string();
+    std::fs::write(&path, r#"{"party_id":0,"trees":[{"#).unwrap();
+    match SecretSharedForest::load(&path) {
+        Err(MpcError::Deserialize(message)) => assert!(message.contains(&path), "{}", message),
+        other => panic!("expected a deserialisation error, got {:?}", other.map(|_| ())),
+    }
+    assert!(SecretSharedForest::load(&format!("{}.missing", path)).is_err());
+}
@@ -0,0 +1,121 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use random_forest_rust::channel::channel::{Channel, InMemoryChannel};
+use random_forest_rust::ot::ot::{Modulus, OtSession};
+use random_forest_rust::reveal::reveal::reveal_forest;
+use random_forest_rust::share_source::share_source::ShareCounts;
+use random_forest_rust::simulation::simulation::train_in_memory;
+use std::num::Wrapping;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+/* runs the same calls on both ends of an in-memory pair of sessions */
+fn both_parties<T: Send + 'static>(generate: fn(&mut OtSession) -> T) -> (T, T) {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let (channel0, channel1): (Arc<dyn Channel>, Arc<dyn Channel>) = (Arc::new(channel0), Arc::new(channel1));
+    let party1 = thread::spawn(move || generate(&mut OtSession::setup(channel1, 1).unwrap()));
+    let triples0 = generate(&mut OtSession::setup(channel0, 0).unwrap());
+    (triples0, party1.join().unwrap())
+}
+
+#[test]
+fn binary_triples_reconstruct() {
+    let (triples0, triples1) = both_parties(|session| {
+        let mut triples = session.binary_triples(1000).unwrap();
+        triples.extend(session.binary_triples(3).unwrap());
+        triples
+    });
+    assert_eq!(triples0.len(), 1003);
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert!(t0.0 < 2 && t0.1 < 2 && t0.2 < 2);
+        assert_eq!((t0.0 ^ t1.0) & (t0.1 ^ t1.1), t0.2 ^ t1.2);
+    }
+    /* the triples are not constant */
+    assert!(triples0.iter().zip(triples1.iter()).any(|(t0, t1)| t0.0 ^ t1.0 == 1));
+    assert!(triples0.iter().zip(triples1.iter()).any(|(t0, t1)| t0.0 ^ t1.0 == 0));
+}
+
+#[test]
+fn additive_triples_reconstruct_modulo_a_prime() {
+    let prime = 2147483647u64;
+    let (triples0, triples1) = both_parties(|session| session.additive_triples(100, Modulus::Prime(2147483647)).unwrap());
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert!(t0.0.0 < prime && t1.2.0 < prime);
+        let a = (t0.0.0 + t1.0.0) % prime;
+        let b = (t0.1.0 + t1.1.0) % prime;
+        assert_eq!(Wrapping(a * b % prime), Wrapping((t0.2.0 + t1.2.0) % prime));
+    }
+}
+
+#[test]
+fn additive_triples_reconstruct_modulo_two_to_the_64() {
+    let (triples0, triples1) = both_parties(|session| session.additive_triples(100, Modulus::TwoToThe64).unwrap());
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert_eq!((t0.0 + t1.0) * (t0.1 + t1.1), t0.2 + t1.2);
+    }
+    /* the high half is used, not only the bits below some prime */
+    assert!(triples0.iter().any(|t| t.0.0 >= 1 << 63));
+}
+
+#[test]
+fn dt_shares_fill_the_modulo_two_to_the_64_pool() {
+    let (shares0, shares1) = both_parties(|session| {
+        let counts = ShareCounts { additive: 3, additive_z64: 10, additive_bigint: 2, binary: 5, equality: 4 };
+        session.dt_shares(2147483647, &BigUint::from(2147483647u64), &counts).unwrap()
+    });
+    assert_eq!(shares0.additive_z64_triples.len(), 10);
+    for (t0, t1) in shares0.additive_z64_triples.iter().zip(shares1.additive_z64_triples.iter()) {
+        assert_eq!((t0.0 + t1.0) * (t0.1 + t1.1), t0.2 + t1.2);
+    }
+}
+
+#[test]
+fn bigint_triples_reconstruct() {
+    let (triples0, triples1) = both_parties(|session| {
+        session.bigint_triples(20, &BigUint::from_str("170141183460469231731687303715884105727").unwrap()).unwrap()
+    });
+    let prime = BigUint::from_str("170141183460469231731687303715884105727").unwrap();
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert_eq!(((&t0.0 + &t1.0) * (&t0.1 + &t1.1)) % &prime, (&t0.2 + &t1.2) % &prime);
+    }
+}
+
+/* 2^521 - 1, well past the 256 bits of an OT key */
+#[test]
+fn bigint_triples_reconstruct_past_the_key_length() {
+    let (triples0, triples1) = both_parties(|session| {
+        session.bigint_triples(10, &((BigUint::from(1u8) << 521) - BigUint::from(1u8))).unwrap()
+    });
+    let prime = (BigUint::from(1u8) << 521) - BigUint::from(1u8);
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert!(t0.2 < prime && t1.2 < prime);
+        assert_eq!(((&t0.0 + &t1.0) * (&t0.1 + &t1.1)) % &prime, (&t0.2 + &t1.2) % &prime);
+    }
+}
+
+/* the simulation test's data, trained without a TI */
+#[test]
+fn parties_train_on_ot_triples() {
+    let records: Vec<(usize, usize)> = (0..12).map(|i| (i % 3 % 2, i % 2)).collect();
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for (noise, class) in records.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((*noise == j) as u8);
+            one_hot_encoding[2 + j].push((*class == j) as u8);
+            one_hot_encoding[4 + j].push((*class == j) as u8);
+        }
+    }
+    let (path0, path1) = common::write_shares("ot_training_test", 2, 2, 2, &one_hot_encoding);
+    let settings = common::settings(&[
+        ("share_source", "ot"), ("add_shares_per_tree", "2000"), ("add_shares_bigint_per_tree", "2000"),
+        ("equality_shares_per_tree", "100"), ("binary_shares_per_tree", "20000"),
+    ]);
+
+    let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+    let trees = reveal_forest(&forest0, &forest1).unwrap();
+    assert_eq!(trees[0].nodes[0].split_attribute, Some(1));
+}
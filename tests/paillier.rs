@@ -0,0 +1,108 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use random_forest_rust::channel::channel::{Channel, InMemoryChannel};
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::paillier::paillier::{PaillierKey, PaillierSession};
+use random_forest_rust::parameters::parameters::is_probable_prime;
+use random_forest_rust::reveal::reveal::reveal_forest;
+use random_forest_rust::simulation::simulation::train_in_memory;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+const BIG_INT_PRIME: &str = "170141183460469231731687303715884105727";
+
+/* runs the same calls on both ends of an in-memory pair of sessions */
+fn both_parties<T: Send + 'static>(modulus_bits: usize, generate: fn(&mut PaillierSession, &BigUint) -> T) -> (T, T) {
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let (channel0, channel1): (Arc<dyn Channel>, Arc<dyn Channel>) = (Arc::new(channel0), Arc::new(channel1));
+    let prime = BigUint::from_str(BIG_INT_PRIME).unwrap();
+    let prime1 = prime.clone();
+    let party1 = thread::spawn(move || generate(&mut PaillierSession::setup(channel1, 1, modulus_bits).unwrap(), &prime1));
+    let result0 = generate(&mut PaillierSession::setup(channel0, 0, modulus_bits).unwrap(), &prime);
+    (result0, party1.join().unwrap())
+}
+
+#[test]
+fn primality_is_recognised() {
+    let mut rng = rand::thread_rng();
+    assert!(is_probable_prime(&mut rng, &BigUint::from_str(BIG_INT_PRIME).unwrap()));
+    assert!(is_probable_prime(&mut rng, &BigUint::from(2147483647u64)));
+    assert!(!is_probable_prime(&mut rng, &BigUint::from(1u8)));
+    /* a Carmichael number */
+    assert!(!is_probable_prime(&mut rng, &BigUint::from(561u32)));
+    assert!(!is_probable_prime(&mut rng, &(BigUint::from(2147483647u64) * BigUint::from(2305843009213693951u64))));
+}
+
+#[test]
+fn encryption_is_additively_homomorphic() {
+    let mut rng = rand::thread_rng();
+    let key = PaillierKey::generate(&mut rng, 512);
+    assert_eq!(key.public.n.bits(), 512);
+    let (x, y) = (BigUint::from(123456789u64), BigUint::from_str(BIG_INT_PRIME).unwrap());
+    let (ex, ey) = (key.public.encrypt(&mut rng, &x), key.public.encrypt(&mut rng, &y));
+    assert_ne!(ex, key.public.encrypt(&mut rng, &x));
+    assert_eq!(key.decrypt(&ex), x);
+    assert_eq!(key.decrypt(&key.public.add(&ex, &ey)), &x + &y);
+    assert_eq!(key.decrypt(&key.public.multiply_plain(&ex, &BigUint::from(1000u32))), &x * BigUint::from(1000u32));
+}
+
+#[test]
+fn bigint_triples_reconstruct() {
+    let (triples0, triples1) = both_parties(512, |session, prime| session.bigint_triples(30, prime).unwrap());
+    let prime = BigUint::from_str(BIG_INT_PRIME).unwrap();
+    assert_eq!(triples0.len(), 30);
+    for (t0, t1) in triples0.iter().zip(triples1.iter()) {
+        assert!(t0.2 < prime && t1.2 < prime);
+        assert_eq!(((&t0.0 + &t1.0) * (&t0.1 + &t1.1)) % &prime, (&t0.2 + &t1.2) % &prime);
+    }
+}
+
+#[test]
+fn equality_shares_reconstruct_to_nonzero_values() {
+    let (shares0, shares1) = both_parties(512, |session, prime| session.equality_shares(30, prime).unwrap());
+    let prime = BigUint::from_str(BIG_INT_PRIME).unwrap();
+    for (r0, r1) in shares0.iter().zip(shares1.iter()) {
+        assert_ne!((r0 + r1) % &prime, BigUint::from(0u8));
+    }
+}
+
+#[test]
+fn a_modulus_too_small_for_the_prime_is_refused() {
+    let (refusal0, refusal1) = both_parties(256, |session, prime| session.products(&[BigUint::from(1u8)], prime));
+    for refusal in [refusal0, refusal1] {
+        match refusal {
+            Err(MpcError::Config { key, problem }) => {
+                assert_eq!(key, "paillier_modulus_bits");
+                assert!(problem.contains("cannot hide products"));
+            }
+            other => panic!("expected the modulus to be refused, got {:?}", other),
+        }
+    }
+}
+
+/* the simulation test's data, with the BigUint shares from Paillier products */
+#[test]
+fn parties_train_on_paillier_triples() {
+    let records: Vec<(usize, usize)> = (0..12).map(|i| (i % 3 % 2, i % 2)).collect();
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for (noise, class) in records.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((*noise == j) as u8);
+            one_hot_encoding[2 + j].push((*class == j) as u8);
+            one_hot_encoding[4 + j].push((*class == j) as u8);
+        }
+    }
+    let (path0, path1) = common::write_shares("paillier_training_test", 2, 2, 2, &one_hot_encoding);
+    let settings = common::settings(&[
+        ("share_source", "paillier"), ("paillier_modulus_bits", "512"), ("add_shares_per_tree", "2000"),
+        ("add_shares_bigint_per_tree", "2000"), ("equality_shares_per_tree", "100"), ("binary_shares_per_tree", "20000"),
+    ]);
+
+    let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+    let trees = reveal_forest(&forest0, &forest1).unwrap();
+    assert_eq!(trees[0].nodes[0].split_attribute, Some(1));
+}
@@ -0,0 +1,92 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::party_context_from_settings;
+use random_forest_rust::error::error::{ConfigProblem, MpcError};
+use random_forest_rust::parameters::parameters::{check_moduli, check_modulus, derive, is_safe_prime, safe_prime};
+use random_forest_rust::settings::settings::{PartyConfig, TiConfig};
+use random_forest_rust::share_source::share_source::InMemoryShareSource;
+use std::sync::Arc;
+
+fn problem_keys(error: MpcError) -> Vec<String> {
+    match error {
+        MpcError::Settings(problems) => problems.into_iter().map(|ConfigProblem { key, .. }| key).collect(),
+        error => panic!("expected a settings problem, got {}", error),
+    }
+}
+
+#[test]
+fn safe_primes_have_the_requested_size() {
+    let mut rng = rand::thread_rng();
+    for bits in [16, 64, 130].iter() {
+        let prime = safe_prime(&mut rng, *bits).unwrap();
+        assert_eq!(prime.bits(), *bits);
+        assert!(is_safe_prime(&mut rng, &prime), "{} is not a safe prime", prime);
+        assert_eq!(check_modulus("big_int_prime", &prime), None);
+    }
+    match safe_prime(&mut rng, 8) {
+        Err(MpcError::Usage(problem)) => assert!(problem.contains("8 bits"), "{}", problem),
+        other => panic!("expected a small safe prime to be refused, got {:?}", other),
+    }
+}
+
+#[test]
+fn moduli_that_are_not_prime_are_rejected() {
+    // the values the settings shipped with: 512, and 2^175 for big_int_prime
+    let even = BigUint::from(1u8) << 175;
+    assert!(check_modulus("big_int_prime", &even).unwrap().problem.contains("even"));
+    assert!(check_modulus("prime", &BigUint::from(512u32)).is_some());
+    let composite = BigUint::from(2147483647u64 * 3);
+    assert!(check_modulus("prime", &composite).unwrap().problem.contains("composite"));
+    assert!(check_modulus("prime", &BigUint::from(1u8)).is_some());
+    assert_eq!(check_modulus("prime", &BigUint::from(2147483647u64)), None);
+}
+
+#[test]
+fn bounds_grow_with_the_dataset() {
+    let small = derive(100, 10, 8, 100);
+    assert_eq!(small.min_prime, 101);
+    assert_eq!(small.big_int_prime_bits, small.score_bits + 2);
+    assert!(small.paillier_modulus_bits > 2 * small.big_int_prime_bits);
+    let large = derive(10000, 10, 8, 100);
+    assert_eq!(large.min_prime, 10001);
+    assert!(large.score_bits > small.score_bits);
+
+    let problems = check_moduli(97, &safe_prime(&mut rand::thread_rng(), small.big_int_prime_bits - 1).unwrap(), &small);
+    let keys: Vec<String> = problems.into_iter().map(|problem| problem.key).collect();
+    assert_eq!(keys, vec!["prime", "big_int_prime"]);
+}
+
+#[test]
+fn settings_with_composite_moduli_are_rejected() {
+    let settings = common::settings(&[("prime", "512"), ("big_int_prime", "47890485652059026823698344598447161988085597568237568"),
+        ("bit_length", "176"), ("bigint_bit_size", "176")]);
+    assert_eq!(problem_keys(PartyConfig::from_settings(&settings).unwrap_err()), vec!["prime", "big_int_prime"]);
+    assert_eq!(problem_keys(TiConfig::from_settings(&settings).unwrap_err()), vec!["prime", "big_int_prime"]);
+
+    // bit_length and bigint_bit_size follow big_int_prime when left out
+    let settings = common::settings(&[]);
+    let mut copy = config::Config::default();
+    for (key, value) in settings.clone().try_into::<std::collections::HashMap<String, config::Value>>().unwrap() {
+        if key != "bit_length" && key != "bigint_bit_size" {
+            copy.set(&key, value).unwrap();
+        }
+    }
+    assert_eq!(PartyConfig::from_settings(&copy).unwrap().bit_length(), 127);
+    assert_eq!(TiConfig::from_settings(&copy).unwrap().bigint_bit_size, None);
+}
+
+#[test]
+fn a_dataset_too_large_for_the_moduli_is_refused() {
+    let one_hot_encoding: Vec<Vec<u8>> = (0..4).map(|j| (0..12).map(|i| ((i + j) % 2) as u8).collect()).collect();
+    let (path0, _) = common::write_shares("parameters_too_large_test", 2, 1, 2, &one_hot_encoding);
+    let settings = common::settings(&[("prime", "11"), ("big_int_prime", "1000003"), ("bit_length", "20")]);
+    let (channel0, _channel1) = InMemoryChannel::pair(None);
+    match party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(InMemoryShareSource::new().1)) {
+        Err(error) => assert_eq!(problem_keys(error), vec!["prime", "big_int_prime"]),
+        Ok(_) => panic!("expected the moduli to be refused"),
+    }
+}
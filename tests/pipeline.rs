@@ -0,0 +1,71 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::pipeline::pipeline::prefetch;
+use random_forest_rust::reveal::reveal::reveal_forest;
+use random_forest_rust::simulation::simulation::train_in_memory;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn items_arrive_in_order() {
+    /* later indices finish first */
+    let items: Vec<usize> = prefetch(40, 8, 2, |i| {
+        thread::sleep(Duration::from_millis(((40 - i) % 7) as u64));
+        i * i
+    }).iter().collect();
+    assert_eq!(items, (0..40).map(|i| i * i).collect::<Vec<usize>>());
+}
+
+#[test]
+fn production_stays_bounded_ahead() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&produced);
+    let receiver = prefetch(100, 2, 3, move |i| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        i
+    });
+    thread::sleep(Duration::from_millis(200));
+    /* the queue and one item per worker */
+    assert_eq!(produced.load(Ordering::SeqCst), 3 + 2);
+    assert_eq!(receiver.recv().unwrap(), 0);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(produced.load(Ordering::SeqCst), 3 + 2 + 1);
+    assert_eq!(receiver.iter().count(), 99);
+    assert_eq!(produced.load(Ordering::SeqCst), 100);
+}
+
+#[test]
+fn a_failing_producer_disconnects_the_receiver() {
+    let receiver = prefetch(10, 3, 0, |i| {
+        if i == 4 {
+            panic!("generation failed");
+        }
+        i
+    });
+    assert_eq!(receiver.iter().collect::<Vec<usize>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn forests_train_with_and_without_prefetching() {
+    let records: Vec<(usize, usize)> = (0..12).map(|i| (i % 3 % 2, i % 2)).collect();
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for (noise, class) in records.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((*noise == j) as u8);
+            one_hot_encoding[2 + j].push((*class == j) as u8);
+            one_hot_encoding[4 + j].push((*class == j) as u8);
+        }
+    }
+    let (path0, path1) = common::write_shares("pipeline_training_test", 2, 2, 2, &one_hot_encoding);
+    for prefetch_trees in ["0", "1", "3"].iter() {
+        let settings = common::settings(&[("tree_count", "3"), ("prefetch_trees", prefetch_trees)]);
+        let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+        let trees = reveal_forest(&forest0, &forest1).unwrap();
+        assert_eq!(trees.len(), 3);
+        assert!(trees.iter().all(|tree| tree.nodes[0].split_attribute == Some(1)));
+    }
+}
@@ -0,0 +1,88 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::computing_party::computing_party::party_context_from_settings;
+use random_forest_rust::preprocessing::preprocessing::{write_preprocessed_shares, ShareManifest};
+use random_forest_rust::random_forest::random_forest;
+use random_forest_rust::reveal::reveal::reveal_forest;
+use random_forest_rust::share_source::share_source::{FileShareSource, InMemoryShareSource, ShareSource};
+use random_forest_rust::ti::ti::ti_context_from_settings;
+use std::env;
+use std::sync::Arc;
+use std::thread;
+
+fn preprocess(tag: &str, settings: &config::Config) -> (String, ShareManifest) {
+    let dir = env::temp_dir().join(tag).to_str().unwrap().to_string();
+    let manifest = write_preprocessed_shares(&ti_context_from_settings(settings).unwrap(), &dir).unwrap();
+    (dir, manifest)
+}
+
+#[test]
+fn the_manifest_describes_the_files() {
+    let settings = common::settings(&[("tree_count", "3"), ("add_shares_per_tree", "10"), ("binary_shares_per_tree", "100")]);
+    let (dir, manifest) = preprocess("preprocessing_manifest_test", &settings);
+    assert_eq!(ShareManifest::load(&dir).unwrap(), manifest);
+    assert_eq!(manifest.tree_count, 3);
+    assert_eq!(manifest.prime, 2147483647);
+    assert_eq!(manifest.party_files[1], vec!["party1/tree_0.rfts", "party1/tree_1.rfts", "party1/tree_2.rfts"]);
+
+    let source0 = FileShareSource::new(&dir, 0, &manifest);
+    let source1 = FileShareSource::new(&dir, 1, &manifest);
+    for _ in 0..3 {
+        let (shares0, shares1) = (source0.receive_shares().unwrap(), source1.receive_shares().unwrap());
+        assert_eq!(shares0.additive_triples.len(), 10);
+        for (t0, t1) in shares0.binary_triples.iter().zip(shares1.binary_triples.iter()) {
+            assert_eq!((t0.0 ^ t1.0) & (t0.1 ^ t1.1), t0.2 ^ t1.2);
+        }
+    }
+    match source0.receive_shares() {
+        Err(MpcError::Config { key, .. }) => assert_eq!(key, "preprocessed_shares_dir"),
+        other => panic!("expected the shares to run out, got {:?}", other.map(|_| ())),
+    }
+
+    /* a later run replays the same material */
+    let first = FileShareSource::new(&dir, 1, &manifest).receive_shares().unwrap();
+    let again = FileShareSource::new(&dir, 1, &manifest).receive_shares().unwrap();
+    assert_eq!(first.additive_triples, again.additive_triples);
+}
+
+#[test]
+fn parties_train_from_preprocessed_shares() {
+    let records: Vec<(usize, usize)> = (0..12).map(|i| (i % 3 % 2, i % 2)).collect();
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for (noise, class) in records.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((*noise == j) as u8);
+            one_hot_encoding[2 + j].push((*class == j) as u8);
+            one_hot_encoding[4 + j].push((*class == j) as u8);
+        }
+    }
+    let (path0, path1) = common::write_shares("preprocessing_training_test", 2, 2, 2, &one_hot_encoding);
+    let settings = common::settings(&[]);
+    let (dir, manifest) = preprocess("preprocessing_training_test", &settings);
+
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let mut ctx0 = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(FileShareSource::new(&dir, 0, &manifest))).unwrap();
+    let mut ctx1 = party_context_from_settings(&settings, 1, &path1, Arc::new(channel1), Arc::new(FileShareSource::new(&dir, 1, &manifest))).unwrap();
+    manifest.check_party(&ctx0).unwrap();
+    manifest.check_party(&ctx1).unwrap();
+    let party0 = thread::spawn(move || random_forest::train(&mut ctx0));
+    let party1 = thread::spawn(move || random_forest::train(&mut ctx1));
+    let trees = reveal_forest(&party0.join().unwrap().unwrap(), &party1.join().unwrap().unwrap()).unwrap();
+    assert_eq!(trees[0].nodes[0].split_attribute, Some(1));
+}
+
+#[test]
+fn a_party_with_other_primes_is_refused() {
+    let (_, manifest) = preprocess("preprocessing_mismatch_test", &common::settings(&[("tree_count", "1"), ("add_shares_per_tree", "1")]));
+    let settings = common::settings(&[("prime", "2305843009213693951")]);
+    let (path0, _) = common::write_shares("preprocessing_mismatch_test", 2, 1, 2, &[vec![1], vec![0], vec![1], vec![0]]);
+    let (channel0, _channel1) = InMemoryChannel::pair(None);
+    let (_sender, share_source) = InMemoryShareSource::new();
+    let ctx = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(share_source)).unwrap();
+    let refusal = manifest.check_party(&ctx).unwrap_err();
+    assert!(refusal.to_string().contains("prime 2147483647"));
+}
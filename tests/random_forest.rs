@@ -0,0 +1,82 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use rand::prng::chacha::ChaChaRng;
+use rand::SeedableRng;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::random_forest::random_forest::{attribute_mask, bootstrap_weights, tree_sample, MaxFeatures};
+use random_forest_rust::simulation::simulation::train_in_memory;
+
+#[test]
+fn bootstrap_weights_count_every_draw() {
+    for seed in 0..20u8 {
+        let mut rng = ChaChaRng::from_seed([seed; 32]);
+        let weights = bootstrap_weights(&mut rng, 25);
+        assert_eq!(weights.len(), 25);
+        assert_eq!(weights.iter().sum::<u64>(), 25);
+        // 25 draws with replacement from 25 instances leave one out with probability 1 - 25!/25^25
+        assert!(weights.contains(&0), "seed {} drew every instance once", seed);
+    }
+}
+
+#[test]
+fn both_parties_draw_the_same_sample() {
+    let seed = [7u8; 32];
+    let sample0 = tree_sample(seed, 10, 4, true, &MaxFeatures::All, 3);
+    let sample1 = tree_sample(seed, 10, 4, true, &MaxFeatures::All, 3);
+    assert_eq!(sample0, sample1);
+    assert_eq!(sample0.max_depth, 3);
+    assert_eq!(tree_sample(seed, 10, 4, false, &MaxFeatures::All, 3).instance_weights, vec![1u64; 10]);
+}
+
+#[test]
+fn every_tree_records_a_fresh_joint_seed() {
+    // attribute 0, attribute 1 and the class of record t are the three bits of t
+    let one_hot_encoding: Vec<Vec<u8>> = (0..6).map(|row| (0..8).map(|t| ((t >> (row / 2)) & 1 == row % 2) as u8).collect()).collect();
+    let (path0, path1) = common::write_shares("random_forest_seed_test", 2, 2, 2, &one_hot_encoding);
+    let settings = common::settings(&[("tree_count", "3"), ("bootstrap", "true")]);
+    let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+
+    let seeds: Vec<[u8; 32]> = forest0.trees.iter().map(|tree| tree.seed.unwrap()).collect();
+    assert_eq!(seeds, forest1.trees.iter().map(|tree| tree.seed.unwrap()).collect::<Vec<[u8; 32]>>());
+    assert!(seeds[0] != seeds[1] && seeds[1] != seeds[2] && seeds[0] != seeds[2]);
+}
+
+#[test]
+fn attribute_masks_select_feature_count_attributes() {
+    for seed in 0..20u8 {
+        let mask = attribute_mask(&mut ChaChaRng::from_seed([seed; 32]), 9, 3);
+        assert_eq!(mask.iter().filter(|bit| **bit == 1).count(), 3, "seed {}", seed);
+        assert!(mask.iter().all(|bit| *bit < 2));
+    }
+    let sample = tree_sample([3u8; 32], 10, 9, false, &MaxFeatures::Sqrt, 5);
+    assert_eq!(sample.attribute_mask.iter().filter(|bit| **bit == 1).count(), 3);
+    // a tree cannot split deeper than on each of its attributes once
+    assert_eq!(sample.max_depth, 3);
+}
+
+#[test]
+fn max_features_parse_and_count() {
+    assert_eq!(MaxFeatures::parse("all").unwrap(), MaxFeatures::All);
+    assert_eq!(MaxFeatures::parse(" sqrt ").unwrap(), MaxFeatures::Sqrt);
+    assert_eq!(MaxFeatures::parse("log2").unwrap(), MaxFeatures::Log2);
+    assert_eq!(MaxFeatures::parse("0.5").unwrap(), MaxFeatures::Fraction(0.5));
+    assert_eq!(MaxFeatures::parse("4").unwrap(), MaxFeatures::Absolute(4));
+    for invalid in ["0", "1.5", "0.0", "-2", "half", ""].iter() {
+        match MaxFeatures::parse(invalid) {
+            Err(MpcError::Config { key, .. }) => assert_eq!(key, "max_features"),
+            other => panic!("expected {:?} to be refused, got {:?}", invalid, other),
+        }
+    }
+
+    assert_eq!(MaxFeatures::All.feature_count(10), 10);
+    assert_eq!(MaxFeatures::Sqrt.feature_count(10), 3);
+    assert_eq!(MaxFeatures::Log2.feature_count(10), 3);
+    assert_eq!(MaxFeatures::Fraction(0.25).feature_count(10), 3);
+    assert_eq!(MaxFeatures::Absolute(4).feature_count(10), 4);
+    // every tree keeps at least one attribute and at most all of them
+    assert_eq!(MaxFeatures::Log2.feature_count(1), 1);
+    assert_eq!(MaxFeatures::Fraction(0.01).feature_count(10), 1);
+    assert_eq!(MaxFeatures::Absolute(40).feature_count(10), 10);
+}
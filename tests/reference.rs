@@ -0,0 +1,98 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use rand::prng::chacha::ChaChaRng;
+use rand::{Rng, SeedableRng};
+use random_forest_rust::plaintext::plaintext::{self, PlaintextData};
+use random_forest_rust::random_forest::random_forest::MaxFeatures;
+use random_forest_rust::reveal::reveal::{reveal_bit_shares, reveal_forest};
+use random_forest_rust::simulation::simulation::{predict_in_memory, train_in_memory};
+
+/* one-hot rows of a random dataset whose class mostly follows attribute 0, so trees have something to find */
+fn random_dataset(rng: &mut ChaChaRng, class_value_count: usize, attribute_count: usize, attr_value_count: usize, instance_count: usize) -> Vec<Vec<u8>> {
+    let mut one_hot_encoding = vec![Vec::new(); attribute_count * attr_value_count + class_value_count];
+    for _ in 0..instance_count {
+        let values: Vec<usize> = (0..attribute_count).map(|_| rng.gen_range(0, attr_value_count)).collect();
+        let class = if rng.gen_range(0, 4) == 0 { rng.gen_range(0, class_value_count) } else { values[0] % class_value_count };
+        for k in 0..attribute_count {
+            for j in 0..attr_value_count {
+                one_hot_encoding[k * attr_value_count + j].push((values[k] == j) as u8);
+            }
+        }
+        for i in 0..class_value_count {
+            one_hot_encoding[attribute_count * attr_value_count + i].push((class == i) as u8);
+        }
+    }
+    one_hot_encoding
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_against_reference(seed: u64, class_value_count: usize, attribute_count: usize, attr_value_count: usize, instance_count: usize, max_depth: usize, epsilon: f64,
+                           bootstrap: bool, max_features: &str) {
+    let mut seed_bytes = [0u8; 32];
+    seed_bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = ChaChaRng::from_seed(seed_bytes);
+    let one_hot_encoding = random_dataset(&mut rng, class_value_count, attribute_count, attr_value_count, instance_count);
+    let tag = format!("reference_test_{}", seed);
+    let (path0, path1) = common::write_shares(&tag, class_value_count, attribute_count, attr_value_count, &one_hot_encoding);
+    let max_depth_value = max_depth.to_string();
+    let epsilon_value = epsilon.to_string();
+    let bootstrap_value = bootstrap.to_string();
+    let settings = common::settings(&[("tree_count", "2"), ("max_depth", &max_depth_value), ("epsilon", &epsilon_value),
+        ("bootstrap", &bootstrap_value), ("max_features", max_features)]);
+
+    let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+    let data = PlaintextData::from_one_hot(class_value_count, attribute_count, attr_value_count, instance_count, &one_hot_encoding);
+    let seeds: Vec<[u8; 32]> = forest0.trees.iter().map(|tree| tree.seed.unwrap()).collect();
+    let max_features = MaxFeatures::parse(max_features).unwrap();
+    let expected = plaintext::train_forest(&data, &seeds, max_depth, 8, epsilon, bootstrap, &max_features);
+    let shape = format!("seed {}: {} classes, {} attributes of {} values, {} instances, max_depth {}, epsilon {}, bootstrap {}, max_features {:?}",
+                        seed, class_value_count, attribute_count, attr_value_count, instance_count, max_depth, epsilon, bootstrap, max_features);
+    assert_eq!(reveal_forest(&forest0, &forest1).unwrap(), expected, "trees differ for {}", shape);
+
+    let (predictions0, predictions1) = predict_in_memory(&settings, forest0, forest1, &path0, &path1).unwrap();
+    let predictions: Vec<Option<usize>> = predictions0.iter().zip(predictions1.iter())
+        .map(|(x, y)| reveal_bit_shares(x, y).unwrap().iter().position(|bit| *bit == 1))
+        .collect();
+    let expected_predictions: Vec<Option<usize>> = plaintext::predict(&expected, &data).into_iter().map(Some).collect();
+    assert_eq!(predictions, expected_predictions, "predictions differ for {}", shape);
+}
+
+#[test]
+fn binary_attributes_match_reference() {
+    check_against_reference(1, 2, 3, 2, 16, 2, 0.1, false, "all");
+}
+
+#[test]
+fn ternary_attributes_match_reference() {
+    check_against_reference(2, 3, 3, 3, 18, 2, 0.2, false, "all");
+}
+
+#[test]
+fn depth_beyond_attribute_count_matches_reference() {
+    check_against_reference(3, 2, 2, 2, 12, 4, 0.0, false, "all");
+}
+
+#[test]
+fn bootstrap_samples_match_reference() {
+    check_against_reference(4, 2, 3, 2, 16, 2, 0.1, true, "all");
+}
+
+#[test]
+fn attribute_subsets_match_reference() {
+    check_against_reference(5, 2, 4, 2, 16, 3, 0.1, false, "sqrt");
+    check_against_reference(6, 3, 5, 2, 16, 2, 0.1, true, "log2");
+    check_against_reference(7, 2, 4, 3, 18, 3, 0.1, false, "3");
+}
+
+// A fresh dataset of a fresh shape on every run; a failure names the seed and shape that reproduce it.
+#[test]
+fn random_shapes_match_reference() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..4 {
+        let max_features = ["all", "sqrt", "log2", "0.5", "2"][rng.gen_range(0, 5)];
+        check_against_reference(rng.gen(), rng.gen_range(2, 4), rng.gen_range(1, 5), rng.gen_range(2, 4), rng.gen_range(8, 21),
+                                rng.gen_range(1, 4), [0.0, 0.1, 0.2][rng.gen_range(0, 3)], rng.gen(), max_features);
+    }
+}
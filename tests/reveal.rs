@@ -0,0 +1,151 @@
+extern crate random_forest_rust;
+
+use num::bigint::BigUint;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::model::model::{SecretSharedForest, SecretSharedTree};
+use random_forest_rust::plaintext::plaintext::PlaintextData;
+use random_forest_rust::ring::ring::AdditiveShare;
+use random_forest_rust::reveal::reveal::{reveal_additive_file, AdditiveModulus, RevealedNode, RevealedTree, reveal_bigint_file, reveal_binary_file, reveal_model_file};
+use random_forest_rust::share::share::share_dataset;
+use std::env;
+use std::fs;
+
+fn temp_path(name: &str) -> String {
+    env::temp_dir().join(name).to_str().unwrap().to_string()
+}
+
+/* writes the two parties' files under the temp directory named after tag */
+fn write_pair(tag: &str, contents0: &str, contents1: &str) -> (String, String) {
+    let (path0, path1) = (temp_path(&format!("{}_share0.csv", tag)), temp_path(&format!("{}_share1.csv", tag)));
+    fs::write(&path0, contents0).unwrap();
+    fs::write(&path1, contents1).unwrap();
+    (path0, path1)
+}
+
+fn deserialize_problem(result: Result<String, MpcError>) -> String {
+    match result {
+        Err(MpcError::Deserialize(problem)) => problem,
+        other => panic!("expected a decoding problem, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_shared_dataset_reveals_to_its_one_hot_encoding() {
+    let (csv_path, schema_path) = (temp_path("reveal_dataset_test_data.csv"), temp_path("reveal_dataset_test_schema.json"));
+    fs::write(&csv_path, "a,class\n0,1\n1,0\n1,1\n").unwrap();
+    fs::write(&schema_path, r#"{"attr_value_count": 2, "class_value_count": 2, "attributes": [{"name": "a", "values": null}],
+        "class_column": "class", "classes": null}"#).unwrap();
+    let (path0, path1) = (temp_path("reveal_dataset_test_share0.csv"), temp_path("reveal_dataset_test_share1.csv"));
+    share_dataset(&csv_path, &schema_path, &path0, &path1).unwrap();
+
+    let revealed = reveal_binary_file(&path0, &path1).unwrap();
+    assert_eq!(revealed, "2\n1\n2\n3\n1,0,0\n0,1,1\n0,1,0\n1,0,1");
+    let plaintext_path = temp_path("reveal_dataset_test_plaintext.csv");
+    fs::write(&plaintext_path, &revealed).unwrap();
+    let data = PlaintextData::load(&plaintext_path).unwrap();
+    assert_eq!(data.attr_values, vec![vec![vec![1, 0, 0], vec![0, 1, 1]]]);
+    assert_eq!(data.class_values, vec![vec![0, 1, 0], vec![1, 0, 1]]);
+
+    // a row short of the instance count the header claims
+    fs::write(&plaintext_path, "2\n1\n2\n3\n1,0,0\n0,1\n0,1,0\n1,0,1").unwrap();
+    match PlaintextData::load(&plaintext_path) {
+        Err(MpcError::Config { key, problem }) => assert!(key == "x_input_path" && problem.contains("row 2 holds 2 of the 3"), "{}", problem),
+        other => panic!("expected the file to be refused, got {:?}", other),
+    }
+}
+
+#[test]
+fn arithmetic_shares_reveal_modulo_the_prime() {
+    let (path0, path1) = write_pair("reveal_additive_test", "3,10\n0,7\n", "9,5\n0,4\n");
+    assert_eq!(reveal_additive_file(&path0, &path1, AdditiveModulus::Prime(11)).unwrap(), "1,4\n0,0");
+
+    let prime = BigUint::from(1000003u32);
+    let (path0, path1) = write_pair("reveal_bigint_test", "1000002,5\n", "2,1000000\n");
+    assert_eq!(reveal_bigint_file(&path0, &path1, &prime).unwrap(), "1,2");
+}
+
+#[test]
+fn fixed_point_shares_reveal_modulo_two_to_the_64() {
+    let (path0, path1) = write_pair("reveal_z64_test", "18446744073709551615,7\n", "3,0\n");
+    for modulus in [None, Some("2^64")].iter() {
+        assert_eq!(reveal_additive_file(&path0, &path1, AdditiveModulus::parse(*modulus).unwrap()).unwrap(), "2,7");
+    }
+    assert_eq!(AdditiveModulus::parse(Some("11")).unwrap(), AdditiveModulus::Prime(11));
+
+    // a zero modulus would divide by zero
+    for refused in [AdditiveModulus::parse(Some("0")).map(|_| String::new()), reveal_additive_file(&path0, &path1, AdditiveModulus::Prime(0)),
+                    reveal_bigint_file(&path0, &path1, &BigUint::from(0u8))].iter() {
+        match refused {
+            Err(MpcError::Usage(problem)) => assert!(problem.contains("must be positive"), "{}", problem),
+            other => panic!("expected the modulus to be refused, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn malformed_share_files_are_refused() {
+    let (path0, path1) = write_pair("reveal_row_count_test", "1,0\n0,1\n", "1,0\n");
+    assert!(deserialize_problem(reveal_binary_file(&path0, &path1)).contains("2 and 1 rows"));
+    let (path0, path1) = write_pair("reveal_not_a_bit_test", "1,2\n", "1,0\n");
+    assert!(deserialize_problem(reveal_binary_file(&path0, &path1)).contains("2 is not a binary share"));
+    let (path0, path1) = write_pair("reveal_not_a_u64_test", "1,-3\n", "1,0\n");
+    assert!(deserialize_problem(reveal_additive_file(&path0, &path1, AdditiveModulus::Prime(11))).contains("-3 is not a u64 share"));
+    let (path0, path1) = write_pair("reveal_not_a_bigint_test", "1\n", "x\n");
+    assert!(deserialize_problem(reveal_bigint_file(&path0, &path1, &BigUint::from(11u8))).contains("x is not a decimal integer share"));
+
+    // the rows pair up, the values within them do not
+    let (path0, path1) = write_pair("reveal_row_length_test", "1,0\n", "1\n");
+    match reveal_binary_file(&path0, &path1) {
+        Err(MpcError::Protocol(problem)) => assert!(problem.contains("2 and 1 shares"), "{}", problem),
+        other => panic!("expected the rows to be refused, got {:?}", other),
+    }
+
+    let missing = temp_path("reveal_missing_test_share.csv");
+    let _ = fs::remove_file(&missing);
+    match reveal_binary_file(&missing, &path1) {
+        Err(MpcError::Io { context, .. }) => assert!(context.contains("reveal_missing_test_share.csv"), "{}", context),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+}
+
+#[test]
+fn malformed_trees_classify_and_format_without_panicking() {
+    let node = |index, depth, split_attribute, class_index| RevealedNode { index, depth, split_attribute, is_leaf: false, class_index };
+    // a stump on attribute 0 whose second child is missing
+    let tree = RevealedTree { attr_value_count: 2, max_depth: 1, nodes: vec![node(0, 0, Some(0), Some(0)), node(1, 1, None, Some(1))] };
+    assert_eq!(tree.classify(&[0]), Some(1));
+    assert_eq!(tree.classify(&[1]), None);
+    assert_eq!(tree.classify(&[2]), None);
+    assert_eq!(tree.classify(&[]), None);
+    assert_eq!(tree.format(), "attribute 0 = 0:\n  class 1\nattribute 0 = 1:\n  missing node 2");
+}
+
+#[test]
+fn model_shares_reveal_to_the_forest() {
+    // a stump splitting on attribute 1, with class 1 under value 0 and class 0 under value 1
+    let mut tree0 = SecretSharedTree::new(0, 2, 2, 2, 1);
+    let mut tree1 = SecretSharedTree::new(1, 2, 2, 2, 1);
+    tree0.nodes[0].split_attribute = AdditiveShare::wrap(vec![1, 1]);
+    tree1.nodes[0].split_attribute = AdditiveShare::wrap(vec![1, 0]);
+    tree0.nodes[1].class_index = AdditiveShare::wrap(vec![0, 1]);
+    tree0.nodes[2].class_index = AdditiveShare::wrap(vec![1, 1]);
+    tree1.nodes[2].class_index = AdditiveShare::wrap(vec![0, 1]);
+    let (mut forest0, mut forest1) = (SecretSharedForest::new(0), SecretSharedForest::new(1));
+    forest0.trees.push(tree0.clone());
+    forest1.trees.push(tree1);
+    let (path0, path1) = (temp_path("reveal_model_test_model0.json"), temp_path("reveal_model_test_model1.json"));
+    forest0.save(&path0).unwrap();
+    forest1.save(&path1).unwrap();
+    assert_eq!(reveal_model_file(&path0, &path1).unwrap(),
+               "tree 0\nattribute 1 = 0:\n  class 1\nattribute 1 = 1:\n  class 0");
+
+    // a forest with a tree the other party does not hold
+    forest0.trees.push(tree0);
+    forest0.save(&path0).unwrap();
+    match reveal_model_file(&path0, &path1) {
+        Err(MpcError::Protocol(problem)) => assert!(problem.contains("2 and 1 trees"), "{}", problem),
+        other => panic!("expected the forests to be refused, got {:?}", other),
+    }
+    fs::write(&path0, "{\"party_id\": 0}").unwrap();
+    assert!(deserialize_problem(reveal_model_file(&path0, &path1)).contains("model file"));
+}
@@ -0,0 +1,164 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use rand::Rng;
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::{party_context_from_settings, ComputingParty};
+use random_forest_rust::dot_product::dot_product::dot_product;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::multiplication::multiplication::{batch_beaver_multiply, parallel_beaver_multiply};
+use random_forest_rust::or_xor::or_xor::or_xor;
+use random_forest_rust::ring::ring::{AdditiveShare, BigZp, Ring, Z2, Z64, Zp};
+use random_forest_rust::seeded_shares::seeded_shares::deal;
+use random_forest_rust::share_source::share_source::{InMemoryShareSource, ShareCounts};
+use std::num::Wrapping;
+use std::sync::Arc;
+use std::thread;
+
+/* the two parties' contexts with 50 triples of every kind dealt, multiplying in batches of 3 */
+fn parties(tag: &str) -> (ComputingParty, ComputingParty) {
+    let settings = common::settings(&[("batch_size", "3")]);
+    let one_hot_encoding: Vec<Vec<u8>> = (0..4).map(|j| (0..12).map(|i| ((i + j) % 2) as u8).collect()).collect();
+    let (path0, path1) = common::write_shares(tag, 2, 1, 2, &one_hot_encoding);
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let mut ctx0 = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    let mut ctx1 = party_context_from_settings(&settings, 1, &path1, Arc::new(channel1), Arc::new(InMemoryShareSource::new().1)).unwrap();
+    let counts = ShareCounts { additive: 50, additive_z64: 50, additive_bigint: 50, binary: 50, equality: 0 };
+    let (share0, share1) = deal(&mut rand::thread_rng(), ctx0.dt_training.prime, &ctx0.dt_training.big_int_prime, &counts);
+    ctx0.dt_shares = share0.expand();
+    ctx1.dt_shares = share1.expand();
+    (ctx0, ctx1)
+}
+
+/* runs the same protocol on both parties and opens its output */
+fn open<R, F>(ring: &R, mut ctx0: ComputingParty, mut ctx1: ComputingParty, inputs0: Vec<Vec<R::Element>>, inputs1: Vec<Vec<R::Element>>, protocol: F)
+              -> Result<Vec<R::Element>, MpcError>
+    where R: Ring, F: Fn(&R, &[Vec<AdditiveShare<R>>], &mut ComputingParty) -> Result<Vec<AdditiveShare<R>>, MpcError> + Send + Sync + Copy + 'static {
+    let ring1 = ring.clone();
+    let party1 = thread::spawn(move || {
+        let inputs1: Vec<Vec<AdditiveShare<R>>> = inputs1.into_iter().map(AdditiveShare::wrap).collect();
+        protocol(&ring1, &inputs1, &mut ctx1)
+    });
+    let inputs0: Vec<Vec<AdditiveShare<R>>> = inputs0.into_iter().map(AdditiveShare::wrap).collect();
+    let output0 = protocol(ring, &inputs0, &mut ctx0)?;
+    let output1 = party1.join().unwrap()?;
+    Ok(output0.iter().zip(output1.iter()).map(|(share0, share1)| ring.add(share0.value(), share1.value())).collect())
+}
+
+/* shares of values: a random mask for party 0, the rest for party 1 */
+fn share<R: Ring>(ring: &R, values: &[R::Element], masks: Vec<R::Element>) -> (Vec<R::Element>, Vec<R::Element>) {
+    let rest = values.iter().zip(masks.iter()).map(|(value, mask)| ring.subtract(value, mask)).collect();
+    (masks, rest)
+}
+
+fn multiply<R: Ring>(ring: &R, inputs: &[Vec<AdditiveShare<R>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<R>>, MpcError> {
+    batch_beaver_multiply(ring, &inputs[0], &inputs[1], ctx)
+}
+
+#[test]
+fn beaver_multiplication_is_the_same_protocol_in_every_ring() {
+    let mut rng = rand::thread_rng();
+    let bits: Vec<u8> = (0..8).map(|i| (i % 2) as u8).collect();
+    let others: Vec<u8> = (0..8).map(|i| (i / 2 % 2) as u8).collect();
+    let (x0, x1) = share(&Z2, &bits, (0..8).map(|_| rng.gen_range(0, 2)).collect());
+    let (y0, y1) = share(&Z2, &others, (0..8).map(|_| rng.gen_range(0, 2)).collect());
+    let (ctx0, ctx1) = parties("ring_z2_test");
+    let products = open(&Z2, ctx0, ctx1, vec![x0, y0], vec![x1, y1], multiply).unwrap();
+    assert_eq!(products, bits.iter().zip(others.iter()).map(|(x, y)| x & y).collect::<Vec<u8>>());
+
+    let (ctx0, ctx1) = parties("ring_zp_test");
+    let ring = Zp::of(&ctx0);
+    let xs: Vec<Wrapping<u64>> = (0..7).map(|i| Wrapping(ring.prime - 1 - i)).collect();
+    let ys: Vec<Wrapping<u64>> = (0..7).map(|i| Wrapping(i * 1000)).collect();
+    let (x0, x1) = share(&ring, &xs, (0..7).map(|_| Wrapping(rng.gen_range(0, ring.prime))).collect());
+    let (y0, y1) = share(&ring, &ys, (0..7).map(|_| Wrapping(rng.gen_range(0, ring.prime))).collect());
+    let products = open(&ring, ctx0, ctx1, vec![x0, y0], vec![x1, y1], multiply).unwrap();
+    assert_eq!(products, xs.iter().zip(ys.iter()).map(|(x, y)| ring.multiply(x, y)).collect::<Vec<_>>());
+
+    let (ctx0, ctx1) = parties("ring_bigzp_test");
+    let ring = BigZp::of(&ctx0);
+    let xs: Vec<BigUint> = (1..6u32).map(|i| &ring.prime / BigUint::from(i)).collect();
+    let ys: Vec<BigUint> = (1..6u32).map(|i| &ring.prime - BigUint::from(i)).collect();
+    let (x0, x1) = share(&ring, &xs, (0..5u32).map(|i| BigUint::from(rng.gen::<u64>()) * BigUint::from(i + 1)).collect());
+    let (y0, y1) = share(&ring, &ys, (0..5u32).map(|_| BigUint::from(rng.gen::<u64>())).collect());
+    let products = open(&ring, ctx0, ctx1, vec![x0, y0], vec![x1, y1], multiply).unwrap();
+    assert_eq!(products, xs.iter().zip(ys.iter()).map(|(x, y)| ring.multiply(x, y)).collect::<Vec<_>>());
+}
+
+#[test]
+fn dot_product_and_or_xor_are_generic() {
+    fn dot<R: Ring>(ring: &R, inputs: &[Vec<AdditiveShare<R>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<R>>, MpcError> {
+        let before = *ctx.dt_shares.current_additive_index.lock().unwrap();
+        let result = dot_product(ring, &inputs[0], &inputs[1], ctx)?;
+        // ten entries in batches of three take ten triples
+        assert_eq!(*ctx.dt_shares.current_additive_index.lock().unwrap(), before + 10);
+        Ok(vec![result])
+    }
+    let mut rng = rand::thread_rng();
+    let (ctx0, ctx1) = parties("ring_dot_product_test");
+    let ring = Zp::of(&ctx0);
+    let xs: Vec<Wrapping<u64>> = (0..10).map(|i| Wrapping(i % 2)).collect();
+    let ys: Vec<Wrapping<u64>> = (0..10).map(|i| Wrapping(i + 1)).collect();
+    let (x0, x1) = share(&ring, &xs, (0..10).map(|_| Wrapping(rng.gen_range(0, ring.prime))).collect());
+    let (y0, y1) = share(&ring, &ys, (0..10).map(|_| Wrapping(rng.gen_range(0, ring.prime))).collect());
+    assert_eq!(open(&ring, ctx0, ctx1, vec![x0, y0], vec![x1, y1], dot).unwrap(), vec![Wrapping(2 + 4 + 6 + 8 + 10)]);
+
+    fn xor(ring: &BigZp, inputs: &[Vec<AdditiveShare<BigZp>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<BigZp>>, MpcError> {
+        or_xor(ring, &inputs[0], &inputs[1], ctx, &BigUint::from(2u8))
+    }
+    // the parties' shares of bits in Z_2, each lifted into Z_p for big_int_prime as a value the other holds 0 of
+    let (ctx0, ctx1) = parties("ring_or_xor_test");
+    let ring = BigZp::of(&ctx0);
+    let (zero, one) = (BigUint::from(0u8), BigUint::from(1u8));
+    let a0 = vec![zero.clone(), zero.clone(), one.clone(), one.clone()];
+    let a1 = vec![zero.clone(), one.clone(), zero.clone(), one.clone()];
+    let xored = open(&ring, ctx0, ctx1, vec![a0, vec![zero.clone(); 4]], vec![vec![zero.clone(); 4], a1], xor).unwrap();
+    assert_eq!(xored, vec![zero.clone(), one.clone(), one.clone(), zero]);
+}
+
+#[test]
+fn shares_modulo_two_to_the_64_wrap_around() {
+    let shares0 = AdditiveShare::wrap(vec![Wrapping(u64::MAX), Wrapping(5u64)]);
+    let shares1 = AdditiveShare::wrap(vec![Wrapping(3u64), Wrapping(u64::MAX - 1)]);
+    let sums: Vec<Wrapping<u64>> = shares0.iter().zip(shares1.iter()).map(|(x, y)| *x.add(&Z64, y).value()).collect();
+    assert_eq!(sums, vec![Wrapping(2), Wrapping(3)]);
+    assert_eq!(*shares0[1].scale(&Z64, &Wrapping(1 << 63)).value(), Wrapping(1 << 63));
+    assert_eq!(*shares0[1].subtract(&Z64, &shares1[1]).value(), Wrapping(7));
+}
+
+#[test]
+fn products_modulo_two_to_the_64_wrap_around() {
+    fn parallel<R: Ring>(ring: &R, inputs: &[Vec<AdditiveShare<R>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<R>>, MpcError> {
+        let before = *ctx.dt_shares.current_additive_z64_index.lock().unwrap();
+        let products = parallel_beaver_multiply(ring, &inputs[0], &inputs[1], ctx)?;
+        // the products draw on the triples modulo 2^64, not the ones modulo prime
+        assert_eq!(*ctx.dt_shares.current_additive_z64_index.lock().unwrap(), before + 7);
+        assert_eq!(*ctx.dt_shares.current_additive_index.lock().unwrap(), 0);
+        Ok(products)
+    }
+    let mut rng = rand::thread_rng();
+    let xs: Vec<Wrapping<u64>> = (0..7).map(|i| Wrapping(u64::MAX - i)).collect();
+    let ys: Vec<Wrapping<u64>> = (0..7).map(|i| Wrapping((1 << 40) + i)).collect();
+    let (x0, x1) = share(&Z64, &xs, (0..7).map(|_| Wrapping(rng.gen())).collect());
+    let (y0, y1) = share(&Z64, &ys, (0..7).map(|_| Wrapping(rng.gen())).collect());
+    let (ctx0, ctx1) = parties("ring_z64_test");
+    let products = open(&Z64, ctx0, ctx1, vec![x0, y0], vec![x1, y1], parallel).unwrap();
+    assert_eq!(products, xs.iter().zip(ys.iter()).map(|(x, y)| x * y).collect::<Vec<_>>());
+}
+
+#[test]
+fn multiplying_past_the_dealt_triples_is_refused() {
+    fn parallel<R: Ring>(ring: &R, inputs: &[Vec<AdditiveShare<R>>], ctx: &mut ComputingParty) -> Result<Vec<AdditiveShare<R>>, MpcError> {
+        parallel_beaver_multiply(ring, &inputs[0], &inputs[1], ctx)
+    }
+    // 60 products in batches of 3, where 50 triples were dealt
+    let (ctx0, ctx1) = parties("ring_exhausted_test");
+    let ring = Zp::of(&ctx0);
+    let values = vec![Wrapping(3u64); 60];
+    match open(&ring, ctx0, ctx1, vec![values.clone(), values.clone()], vec![values.clone(), values], parallel) {
+        Err(MpcError::Exhausted(shortfalls)) => assert_eq!(shortfalls[0].available, 50),
+        other => panic!("expected the multiplication to be refused, got {:?}", other),
+    }
+}
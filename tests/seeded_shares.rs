@@ -0,0 +1,105 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use num::Zero;
+use random_forest_rust::decision_tree::decision_tree::DecisionTreeShares;
+use random_forest_rust::seeded_shares::seeded_shares::{deal, SeededShares};
+use random_forest_rust::share_source::share_source::ShareCounts;
+use random_forest_rust::share_stream::share_stream::{read_dt_shares, write_dt_shares, write_seeded_shares};
+use random_forest_rust::ti::ti::{generate_dt_shares, generate_seeded_shares, ti_context_from_settings};
+use std::str::FromStr;
+
+const PRIME: u64 = 2147483647;
+const BIG_INT_PRIME: &str = "170141183460469231731687303715884105727";
+
+fn assert_reconstructs_triples(share0: &DecisionTreeShares, share1: &DecisionTreeShares, prime: u64, big_int_prime: &BigUint) {
+    for (t0, t1) in share0.additive_triples.iter().zip(share1.additive_triples.iter()) {
+        let (a, b, c) = ((t0.0 + t1.0).0 % prime, (t0.1 + t1.1).0 % prime, (t0.2 + t1.2).0 % prime);
+        assert!(t0.0 .0 < prime && t1.2 .0 < prime);
+        assert_eq!((a as u128 * b as u128 % prime as u128) as u64, c);
+    }
+    for (t0, t1) in share0.additive_z64_triples.iter().zip(share1.additive_z64_triples.iter()) {
+        assert_eq!((t0.0 + t1.0) * (t0.1 + t1.1), t0.2 + t1.2);
+    }
+    for (t0, t1) in share0.additive_bigint_triples.iter().zip(share1.additive_bigint_triples.iter()) {
+        let c = (&t0.2 + &t1.2) % big_int_prime;
+        assert_eq!((&t0.0 + &t1.0) * (&t0.1 + &t1.1) % big_int_prime, c);
+    }
+    for (t0, t1) in share0.binary_triples.iter().zip(share1.binary_triples.iter()) {
+        assert_eq!((t0.0 ^ t1.0) & (t0.1 ^ t1.1), t0.2 ^ t1.2);
+    }
+    for (r0, r1) in share0.equality_shares.iter().zip(share1.equality_shares.iter()) {
+        assert!(!((r0 + r1) % big_int_prime).is_zero());
+    }
+}
+
+fn dealt() -> (SeededShares, SeededShares) {
+    let counts = ShareCounts { additive: 500, additive_z64: 200, additive_bigint: 100, binary: 1000, equality: 50 };
+    deal(&mut rand::thread_rng(), PRIME, &BigUint::from_str(BIG_INT_PRIME).unwrap(), &counts)
+}
+
+#[test]
+fn expanded_seeds_form_beaver_triples() {
+    let (share0, share1) = dealt();
+    let (view0, view1) = (share0.expand(), share1.expand());
+    assert_eq!(view0.additive_triples.len(), 500);
+    assert_eq!(view1.additive_z64_triples.len(), 200);
+    assert_eq!(view1.binary_triples.len(), 1000);
+    assert_eq!(view1.equality_shares.len(), 50);
+    assert_reconstructs_triples(&view0, &view1, PRIME, &BigUint::from_str(BIG_INT_PRIME).unwrap());
+}
+
+#[test]
+fn a_seed_expands_to_the_same_shares_every_time() {
+    let (share0, share1) = dealt();
+    assert_eq!(share0.expand().additive_bigint_triples, share0.expand().additive_bigint_triples);
+    assert_eq!(share1.expand().binary_triples, share1.expand().binary_triples);
+    let (other0, _) = dealt();
+    assert_ne!(share0.expand().additive_triples, other0.expand().additive_triples);
+}
+
+#[test]
+fn seeded_streams_expand_on_receipt() {
+    let ti = ti_context_from_settings(&common::settings(&[
+        ("add_shares_per_tree", "100"), ("add_shares_z64_per_tree", "20"), ("add_shares_bigint_per_tree", "30"),
+        ("equality_shares_per_tree", "10"), ("binary_shares_per_tree", "1000"),
+    ])).unwrap();
+    let (share0, share1) = generate_seeded_shares(&ti, 0);
+    let mut views = Vec::new();
+    for share in [&share0, &share1].iter() {
+        let mut bytes = Vec::new();
+        write_seeded_shares(&mut bytes, share).unwrap();
+        let received = read_dt_shares(&mut &bytes[..]).unwrap();
+        let expanded = share.expand();
+        assert_eq!(received.additive_triples, expanded.additive_triples);
+        assert_eq!(received.additive_z64_triples, expanded.additive_z64_triples);
+        assert_eq!(received.additive_bigint_triples, expanded.additive_bigint_triples);
+        assert_eq!(received.binary_triples, expanded.binary_triples);
+        assert_eq!(received.equality_shares, expanded.equality_shares);
+        views.push(received);
+    }
+    assert_reconstructs_triples(&views[0], &views[1], ti.prime, &ti.big_int_prime);
+}
+
+/* party 0 receives a fixed size header, party 1 a third of its full triples */
+#[test]
+fn seeds_shrink_the_transfer() {
+    let ti = ti_context_from_settings(&common::settings(&[
+        ("add_shares_per_tree", "3000"), ("add_shares_bigint_per_tree", "300"),
+        ("equality_shares_per_tree", "0"), ("binary_shares_per_tree", "6400"),
+    ])).unwrap();
+    let (seeded0, seeded1) = generate_seeded_shares(&ti, 0);
+    let (full0, full1) = generate_dt_shares(&ti, 0);
+    let size = |write: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = Vec::new();
+        write(&mut bytes);
+        bytes.len()
+    };
+    let full = size(&|bytes| write_dt_shares(bytes, &full0).unwrap()) + size(&|bytes| write_dt_shares(bytes, &full1).unwrap());
+    let party0 = size(&|bytes| write_seeded_shares(bytes, &seeded0).unwrap());
+    let party1 = size(&|bytes| write_seeded_shares(bytes, &seeded1).unwrap());
+    assert!(party0 < 128, "party 0 received {} bytes", party0);
+    assert!(party1 * 5 < full, "party 1 received {} of {} bytes", party1, full);
+}
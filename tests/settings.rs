@@ -0,0 +1,163 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::error::error::{ConfigProblem, MpcError};
+use random_forest_rust::random_forest::random_forest::MaxFeatures;
+use random_forest_rust::settings::settings::{both, deprecated_options, load_settings, PartyConfig, PartyNetworkConfig, RunConfig, TiConfig, TiNetworkConfig};
+use random_forest_rust::share_source::share_source::ShareSourceKind;
+use std::collections::HashMap;
+
+/* the keys of the problems a section reported */
+fn problem_keys(error: MpcError) -> Vec<String> {
+    match error {
+        MpcError::Settings(problems) => problems.into_iter().map(|ConfigProblem { key, .. }| key).collect(),
+        error => panic!("expected a settings problem, got {}", error),
+    }
+}
+
+/* a copy of the settings that leaves the keys unset */
+fn without(settings: &config::Config, keys: &[&str]) -> config::Config {
+    let mut copy = config::Config::default();
+    for (key, value) in settings.clone().try_into::<HashMap<String, config::Value>>().unwrap() {
+        if !keys.contains(&key.as_str()) {
+            copy.set(&key, value).unwrap();
+        }
+    }
+    copy
+}
+
+fn network(overrides: &[(&str, &str)]) -> config::Config {
+    let mut settings = config::Config::default();
+    for (key, value) in [
+        ("party_id", "0"), ("ti_ip", "127.0.0.1"), ("ti_port0", "4000"), ("ti_port1", "8000"), ("party0_ip", "127.0.0.1"),
+        ("party0_port", "5000"), ("party1_ip", "127.0.0.1"), ("party1_port", "6000"), ("x_input_path", "x.csv"),
+    ].iter().chain(overrides.iter()) {
+        settings.set(key, *value).unwrap();
+    }
+    settings
+}
+
+#[test]
+fn shipped_settings_load() {
+    for file in ["settings/Party0.toml", "settings/Party1.toml"].iter() {
+        let settings = load_settings(file).unwrap();
+        both(PartyNetworkConfig::from_settings(&settings), PartyConfig::from_settings(&settings)).unwrap();
+    }
+    let settings = load_settings("settings/TI.toml").unwrap();
+    let (_, ti) = both(TiNetworkConfig::from_settings(&settings), TiConfig::from_settings(&settings)).unwrap();
+    // the TI deals what the shipped parties' dataset shape needs
+    let party = PartyConfig::from_settings(&load_settings("settings/Party0.toml").unwrap()).unwrap();
+    assert_eq!(ti.share_counts().unwrap(), party.share_counts().unwrap());
+    let settings = load_settings("settings/Simulation.toml").unwrap();
+    both(TiConfig::from_settings(&settings), PartyConfig::from_settings(&settings)).unwrap();
+}
+
+#[test]
+fn unset_options_take_their_defaults() {
+    let minimal = without(&common::settings(&[]), &["debug_output", "decimal_precision", "integer_precision", "batch_size",
+        "tree_training_batch_size", "thread_count", "bootstrap", "max_features"]);
+    let config = PartyConfig::from_settings(&minimal).unwrap();
+    assert!(config.bootstrap);
+    assert!(!config.debug_output);
+    assert_eq!(config.max_features, MaxFeatures::All);
+    assert_eq!(config.share_source, ShareSourceKind::Ti);
+    assert_eq!(config.prefetch_trees, 1);
+    assert_eq!(config.message_timeout(), None);
+    assert_eq!(config.preprocessed_shares_dir, None);
+}
+
+#[test]
+fn every_problem_is_reported_at_once() {
+    let settings = without(&common::settings(&[("bit_length", "100"), ("max_features", "most"), ("alpha", "eight"), ("dataset_size_prime", "512")]), &["tree_count"]);
+    let keys = problem_keys(PartyConfig::from_settings(&settings).unwrap_err());
+    assert_eq!(keys, vec!["tree_count", "max_features", "alpha"]);
+
+    // the checks between options run once every option parses on its own
+    let settings = common::settings(&[("bit_length", "100"), ("tree_training_batch_size", "0"), ("epsilon", "1.5")]);
+    let keys = problem_keys(PartyConfig::from_settings(&settings).unwrap_err());
+    assert_eq!(keys, vec!["tree_training_batch_size", "bit_length", "epsilon"]);
+}
+
+#[test]
+fn servers_need_distinct_addresses() {
+    let keys = problem_keys(PartyNetworkConfig::from_settings(&network(&[("party1_port", "5000"), ("ti_port1", "4000")])).unwrap_err());
+    assert_eq!(keys, vec!["party1_port", "ti_port1"]);
+
+    // the same port on different hosts is fine
+    PartyNetworkConfig::from_settings(&network(&[("party1_ip", "127.0.0.2"), ("party1_port", "5000")])).unwrap();
+
+    let keys = problem_keys(PartyNetworkConfig::from_settings(&network(&[("party_id", "2"), ("party0_ip", "localhost"), ("ti_port0", "70000")])).unwrap_err());
+    assert_eq!(keys, vec!["ti_port0"]);
+    let keys = problem_keys(PartyNetworkConfig::from_settings(&network(&[("party_id", "2"), ("party0_ip", "localhost")])).unwrap_err());
+    assert_eq!(keys, vec!["party_id", "party0_ip"]);
+}
+
+#[test]
+fn ti_needs_share_counts_or_the_shape() {
+    let settings = without(&common::settings(&[("bigint_bit_size", "64")]), &["equality_shares_per_tree"]);
+    let keys = problem_keys(TiConfig::from_settings(&settings).unwrap_err());
+    assert_eq!(keys, vec!["bigint_bit_size", "equality_shares_per_tree"]);
+
+    let shaped = without(&common::settings(&[("instance_count", "12"), ("attribute_count", "3"), ("attr_value_count", "2"), ("class_value_count", "3")]),
+                         &["add_shares_per_tree", "add_shares_bigint_per_tree", "equality_shares_per_tree", "binary_shares_per_tree"]);
+    TiConfig::from_settings(&shaped).unwrap();
+}
+
+#[test]
+fn sections_report_their_problems_together() {
+    let settings = common::settings(&[("bit_length", "8"), ("party_id", "0"), ("ti_ip", "127.0.0.1"), ("ti_port0", "4000"), ("ti_port1", "8000"),
+        ("party0_ip", "127.0.0.1"), ("party0_port", "5000"), ("party1_ip", "127.0.0.1"), ("party1_port", "5000"), ("x_input_path", "x.csv")]);
+    let keys = problem_keys(both(PartyNetworkConfig::from_settings(&settings), PartyConfig::from_settings(&settings)).unwrap_err());
+    assert_eq!(keys, vec!["party1_port", "bit_length"]);
+}
+
+#[test]
+fn derived_options_are_deprecated_rather_than_refused() {
+    let settings = common::settings(&[("dataset_size_prime", "512"), ("big_int_ti_index", "0")]);
+    assert_eq!(deprecated_options(&settings).into_iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!["dataset_size_prime", "big_int_ti_index"]);
+    assert!(PartyConfig::from_settings(&settings).is_ok());
+    assert!(TiConfig::from_settings(&settings).is_ok());
+    assert!(deprecated_options(&common::settings(&[])).is_empty());
+}
+
+/* the options checked are the fields of the section structs, so no list of them can fall behind */
+#[test]
+fn every_field_of_a_section_is_checked() {
+    let empty = config::Config::default();
+    assert_eq!(problem_keys(PartyNetworkConfig::from_settings(&empty).unwrap_err()),
+               vec!["party_id", "ti_ip", "ti_port0", "ti_port1", "party0_ip", "party0_port", "party1_ip", "party1_port", "x_input_path"]);
+    assert_eq!(problem_keys(TiNetworkConfig::from_settings(&empty).unwrap_err()), vec!["ti_ip", "ti_port0", "ti_port1"]);
+    let wrong: Vec<(&str, &str)> = vec![("session_key", "key"), ("paillier_modulus_bits", "many"), ("bigint_bit_size", "-1")];
+    let settings = common::settings(&wrong);
+    assert_eq!(problem_keys(PartyConfig::from_settings(&settings).unwrap_err()), vec!["paillier_modulus_bits"]);
+    assert_eq!(problem_keys(TiConfig::from_settings(&settings).unwrap_err()), vec!["bigint_bit_size"]);
+}
+
+/* the kind of check and whether an option is required come from the field's type and serde attributes */
+#[test]
+fn options_are_checked_by_their_fields() {
+    let problems = match PartyNetworkConfig::from_settings(&without(&network(&[("party_id", "-1"), ("ti_port0", "70000"), ("party0_port", "0")]), &["x_input_path"])) {
+        Err(MpcError::Settings(problems)) => problems,
+        other => panic!("expected settings problems, got {:?}", other),
+    };
+    let texts: Vec<(String, String)> = problems.into_iter().map(|ConfigProblem { key, problem }| (key, problem)).collect();
+    assert_eq!(texts, vec![
+        ("party_id".to_string(), "must not be negative, got -1".to_string()),
+        ("ti_port0".to_string(), "must be at most 65535, got 70000".to_string()),
+        ("x_input_path".to_string(), "missing".to_string()),
+    ]);
+    // port 0 parses as a u16 but no party could connect to it
+    assert_eq!(problem_keys(PartyNetworkConfig::from_settings(&network(&[("party0_port", "0")])).unwrap_err()), vec!["party0_port"]);
+
+    let run = |overrides: &[(&str, &str)]| {
+        let mut settings = config::Config::default();
+        for (key, value) in overrides.iter() {
+            settings.set(key, *value).unwrap();
+        }
+        RunConfig::from_settings(&settings)
+    };
+    assert_eq!(problem_keys(run(&[]).unwrap_err()), vec!["ti"]);
+    assert_eq!(problem_keys(run(&[("ti", "false"), ("predict", "true")]).unwrap_err()), vec!["model_path"]);
+    assert!(!run(&[("ti", "false")]).unwrap().predict);
+}
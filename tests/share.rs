@@ -0,0 +1,82 @@
+extern crate random_forest_rust;
+
+use random_forest_rust::computing_party::computing_party::load_dt_training_file;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::share::share::share_dataset;
+use std::env;
+use std::fs;
+
+const SCHEMA: &str = r#"{
+    "attr_value_count": 3,
+    "class_value_count": 2,
+    "attributes": [{"name": "outlook", "values": ["sunny", "overcast", "rain"]}, {"name": "windy", "values": null}],
+    "class_column": "play",
+    "classes": ["no", "yes"]
+}"#;
+
+/* writes the csv and the schema under the temp directory named after tag and shares them */
+fn share(tag: &str, csv: &str, schema: &str) -> Result<(String, String), MpcError> {
+    let path = |name: &str| env::temp_dir().join(format!("{}_{}", tag, name)).to_str().unwrap().to_string();
+    let (csv_path, schema_path, path0, path1) = (path("data.csv"), path("schema.json"), path("share0.csv"), path("share1.csv"));
+    fs::write(&csv_path, csv).unwrap();
+    fs::write(&schema_path, schema).unwrap();
+    share_dataset(&csv_path, &schema_path, &path0, &path1)?;
+    Ok((path0, path1))
+}
+
+fn deserialize_problem(result: Result<(String, String), MpcError>) -> String {
+    match result {
+        Err(MpcError::Deserialize(problem)) => problem,
+        other => panic!("expected a decoding problem, got {:?}", other),
+    }
+}
+
+#[test]
+fn shares_xor_to_the_one_hot_encoding() {
+    let csv = "day,outlook,windy,play\n1,sunny,0,no\n2, rain ,1,yes\n3,overcast,2,yes\n";
+    let (path0, path1) = share("share_round_trip_test", csv, SCHEMA).unwrap();
+    let (class_value_count, attribute_count, attr_value_count, instance_count, share0) = load_dt_training_file(&path0).unwrap();
+    assert_eq!((class_value_count, attribute_count, attr_value_count, instance_count), (2, 2, 3, 3));
+    let share1 = load_dt_training_file(&path1).unwrap().4;
+    let revealed: Vec<Vec<u8>> = share0.iter().zip(share1.iter())
+        .map(|(row0, row1)| row0.iter().zip(row1.iter()).map(|(x, y)| x ^ y).collect())
+        .collect();
+    assert_eq!(revealed, vec![
+        vec![1, 0, 0], vec![0, 0, 1], vec![0, 1, 0],
+        vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1],
+        vec![1, 0, 0], vec![0, 1, 1],
+    ]);
+}
+
+#[test]
+fn malformed_datasets_are_refused() {
+    let unlisted = deserialize_problem(share("share_unlisted_test", "outlook,windy,play\nsunny,0,no\nfoggy,1,yes\n", SCHEMA));
+    assert!(unlisted.contains("line 3") && unlisted.contains("foggy"), "{}", unlisted);
+    let too_large = deserialize_problem(share("share_too_large_test", "outlook,windy,play\nsunny,3,no\n", SCHEMA));
+    assert!(too_large.contains("exceeds the value count 3"), "{}", too_large);
+    let not_an_index = deserialize_problem(share("share_not_an_index_test", "outlook,windy,play\nsunny,yes,no\n", SCHEMA));
+    assert!(not_an_index.contains("is not a value index"), "{}", not_an_index);
+    let missing_column = deserialize_problem(share("share_missing_column_test", "outlook,play\nsunny,no\n", SCHEMA));
+    assert!(missing_column.contains("column windy not found"), "{}", missing_column);
+    let short_record = deserialize_problem(share("share_short_record_test", "outlook,windy,play\nsunny,0\n", SCHEMA));
+    assert!(short_record.contains("share_short_record_test_data.csv"), "{}", short_record);
+    let schema = deserialize_problem(share("share_bad_schema_test", "outlook,windy,play\n", "{\"attr_value_count\": 3}"));
+    assert!(schema.contains("schema file"), "{}", schema);
+}
+
+#[test]
+fn a_missing_input_file_is_an_io_error() {
+    let missing = env::temp_dir().join("share_missing_test_data.csv").to_str().unwrap().to_string();
+    let _ = fs::remove_file(&missing);
+    let schema_path = env::temp_dir().join("share_missing_test_schema.json").to_str().unwrap().to_string();
+    fs::write(&schema_path, SCHEMA).unwrap();
+    let output = env::temp_dir().join("share_missing_test_share.csv").to_str().unwrap().to_string();
+    match share_dataset(&missing, &schema_path, &output, &output) {
+        Err(MpcError::Io { context, .. }) => assert!(context.contains("share_missing_test_data.csv"), "{}", context),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+    match share_dataset(&missing, &missing, &output, &output) {
+        Err(MpcError::Io { context, .. }) => assert!(context.contains("schema file"), "{}", context),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+}
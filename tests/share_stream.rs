@@ -0,0 +1,125 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use num::bigint::BigUint;
+use num::One;
+use random_forest_rust::computing_party::computing_party::ti_receive;
+use random_forest_rust::decision_tree::decision_tree::DecisionTreeShares;
+use random_forest_rust::share_stream::share_stream::{read_dt_shares, write_dt_shares, SHARE_STREAM_VERSION};
+use random_forest_rust::ti::ti::{generate_dt_shares, ti_context_from_settings};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::num::Wrapping;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn shares(binary_triples: Vec<(u8, u8, u8)>, big_values: Vec<BigUint>) -> DecisionTreeShares {
+    DecisionTreeShares {
+        additive_triples: Arc::new(vec![(Wrapping(0), Wrapping(1), Wrapping(u64::MAX)), (Wrapping(1 << 63), Wrapping(7), Wrapping(42))]),
+        additive_z64_triples: Arc::new(vec![(Wrapping(u64::MAX), Wrapping(1 << 63), Wrapping(3))]),
+        additive_bigint_triples: Arc::new(big_values.chunks(3).filter(|chunk| chunk.len() == 3)
+            .map(|chunk| (chunk[0].clone(), chunk[1].clone(), chunk[2].clone())).collect()),
+        binary_triples: Arc::new(binary_triples),
+        equality_shares: Arc::new(big_values),
+        current_additive_index: Arc::new(Mutex::new(5)),
+        current_additive_z64_index: Arc::new(Mutex::new(5)),
+        current_additive_bigint_index: Arc::new(Mutex::new(5)),
+        current_equality_index: Arc::new(Mutex::new(5)),
+        current_binary_index: Arc::new(Mutex::new(5)),
+    }
+}
+
+fn assert_round_trip(original: &DecisionTreeShares) {
+    let mut bytes = Vec::new();
+    write_dt_shares(&mut bytes, original).unwrap();
+    let received = read_dt_shares(&mut &bytes[..]).unwrap();
+    assert_eq!(received.additive_triples, original.additive_triples);
+    assert_eq!(received.additive_z64_triples, original.additive_z64_triples);
+    assert_eq!(received.additive_bigint_triples, original.additive_bigint_triples);
+    assert_eq!(received.binary_triples, original.binary_triples);
+    assert_eq!(received.equality_shares, original.equality_shares);
+    assert_eq!(*received.current_additive_index.lock().unwrap(), 0);
+    assert_eq!(*received.current_binary_index.lock().unwrap(), 0);
+}
+
+/* every bit pattern of three bits, cycled to the given length */
+fn binary_triples(count: usize) -> Vec<(u8, u8, u8)> {
+    (0..count).map(|i| ((i & 1) as u8, (i >> 1 & 1) as u8, (i >> 2 & 1) as u8)).collect()
+}
+
+#[test]
+fn shares_survive_a_round_trip() {
+    let big_values = vec![
+        BigUint::from(0u8),
+        BigUint::one(),
+        BigUint::from(255u8),
+        BigUint::from(256u16),
+        BigUint::from_str("170141183460469231731687303715884105726").unwrap(),
+        BigUint::one() << 4000,
+    ];
+    for count in [0, 1, 63, 64, 65, 130].iter() {
+        assert_round_trip(&shares(binary_triples(*count), big_values.clone()));
+    }
+}
+
+#[test]
+fn generated_shares_survive_a_round_trip() {
+    let ti = ti_context_from_settings(&common::settings(&[
+        ("add_shares_per_tree", "100"), ("add_shares_z64_per_tree", "20"), ("add_shares_bigint_per_tree", "30"),
+        ("equality_shares_per_tree", "10"), ("binary_shares_per_tree", "1000"),
+    ])).unwrap();
+    let (share0, share1) = generate_dt_shares(&ti, 0);
+    assert_round_trip(&share0);
+    assert_round_trip(&share1);
+}
+
+/* the TI side of ti_receive's handshake: echo the request, then stream the shares */
+#[test]
+fn a_party_receives_the_shares_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let sent = shares(binary_triples(100), vec![BigUint::from(1u8) << 130, BigUint::from(9u8), BigUint::from(0u8)]);
+    let (binary, bigint) = (sent.binary_triples.clone(), sent.additive_bigint_triples.clone());
+    let ti = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 11];
+        stream.read_exact(&mut request).unwrap();
+        stream.write_all(&request).unwrap();
+        write_dt_shares(&mut stream, &sent).unwrap();
+    });
+    let received = ti_receive(TcpStream::connect(address).unwrap()).unwrap();
+    ti.join().unwrap();
+    assert_eq!(received.binary_triples, binary);
+    assert_eq!(received.additive_bigint_triples, bigint);
+}
+
+#[test]
+fn binary_triples_take_three_bits_each() {
+    let mut small = Vec::new();
+    write_dt_shares(&mut small, &shares(binary_triples(0), vec![])).unwrap();
+    let mut large = Vec::new();
+    write_dt_shares(&mut large, &shares(binary_triples(6400), vec![])).unwrap();
+    assert_eq!(large.len() - small.len(), 3 * 6400 / 8);
+}
+
+#[test]
+fn malformed_streams_are_rejected() {
+    let mut bytes = Vec::new();
+    write_dt_shares(&mut bytes, &shares(binary_triples(10), vec![BigUint::from(3u8)])).unwrap();
+
+    let mut other_version = bytes.clone();
+    other_version[4..6].copy_from_slice(&(SHARE_STREAM_VERSION + 1).to_le_bytes());
+    assert_eq!(read_dt_shares(&mut &other_version[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+    let mut other_magic = bytes.clone();
+    other_magic[0] = b'{';
+    assert_eq!(read_dt_shares(&mut &other_magic[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert_eq!(read_dt_shares(&mut &truncated[..]).err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+
+    let not_bits = shares(vec![(0, 2, 1)], vec![]);
+    assert_eq!(write_dt_shares(&mut Vec::new(), &not_bits).err().unwrap().kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,77 @@
+extern crate random_forest_rust;
+
+mod common;
+
+use random_forest_rust::channel::channel::InMemoryChannel;
+use random_forest_rust::computing_party::computing_party::party_context_from_settings;
+use random_forest_rust::error::error::MpcError;
+use random_forest_rust::message::message::MessageError;
+use random_forest_rust::random_forest::random_forest;
+use random_forest_rust::reveal::reveal::reveal_forest;
+use random_forest_rust::share_source::share_source::InMemoryShareSource;
+use random_forest_rust::simulation::simulation::train_in_memory;
+use random_forest_rust::ti::ti::{generate_dt_shares, ti_context_from_settings};
+use std::sync::Arc;
+
+/* the class is attribute 1, attribute 0 is noise; both have two values */
+fn records() -> (Vec<(usize, usize)>, Vec<Vec<u8>>) {
+    let records: Vec<(usize, usize)> = (0..12).map(|i| (i % 3 % 2, i % 2)).collect();
+    let mut one_hot_encoding = vec![Vec::new(); 6];
+    for (noise, class) in records.iter() {
+        for j in 0..2 {
+            one_hot_encoding[j].push((*noise == j) as u8);
+            one_hot_encoding[2 + j].push((*class == j) as u8);
+            one_hot_encoding[4 + j].push((*class == j) as u8);
+        }
+    }
+    (records, one_hot_encoding)
+}
+
+#[test]
+fn simulated_training_finds_the_informative_attribute() {
+    let (records, one_hot_encoding) = records();
+    let (path0, path1) = common::write_shares("simulation_test", 2, 2, 2, &one_hot_encoding);
+
+    let (forest0, forest1) = train_in_memory(&common::settings(&[]), &path0, &path1).unwrap();
+    let trees = reveal_forest(&forest0, &forest1).unwrap();
+    assert_eq!(trees.len(), 1);
+    assert_eq!(trees[0].nodes[0].split_attribute, Some(1));
+    for (noise, class) in records.iter() {
+        assert_eq!(trees[0].classify(&[*noise, *class]), Some(*class));
+    }
+}
+
+#[test]
+fn trees_train_concurrently_over_one_channel() {
+    let (records, one_hot_encoding) = records();
+    let (path0, path1) = common::write_shares("simulation_concurrent_test", 2, 2, 2, &one_hot_encoding);
+
+    /* a full window of three trees, then the two that start as earlier ones finish */
+    let settings = common::settings(&[("tree_count", "5"), ("tree_training_batch_size", "3")]);
+    let (forest0, forest1) = train_in_memory(&settings, &path0, &path1).unwrap();
+    let trees = reveal_forest(&forest0, &forest1).unwrap();
+    assert_eq!(trees.len(), 5);
+    for tree in trees.iter() {
+        assert_eq!(tree.nodes[0].split_attribute, Some(1));
+        for (noise, class) in records.iter() {
+            assert_eq!(tree.classify(&[*noise, *class]), Some(*class));
+        }
+    }
+}
+
+#[test]
+fn training_reports_a_peer_that_hung_up() {
+    let (_, one_hot_encoding) = records();
+    let (path0, _) = common::write_shares("simulation_hang_up_test", 2, 2, 2, &one_hot_encoding);
+    let settings = common::settings(&[]);
+    let (channel0, channel1) = InMemoryChannel::pair(None);
+    let (sender, share_source) = InMemoryShareSource::new();
+    sender.send(generate_dt_shares(&ti_context_from_settings(&settings).unwrap(), 0).0).unwrap();
+    let mut ctx = party_context_from_settings(&settings, 0, &path0, Arc::new(channel0), Arc::new(share_source)).unwrap();
+    drop(channel1);
+
+    match random_forest::train(&mut ctx) {
+        Err(MpcError::PeerAbort(MessageError::Disconnected { .. })) => {}
+        other => panic!("expected the other party's disconnect, got {:?}", other.map(|_| ())),
+    }
+}
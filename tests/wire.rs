@@ -0,0 +1,79 @@
+extern crate random_forest_rust;
+
+use num::bigint::BigUint;
+use random_forest_rust::message::message::{Payload, RFMessage};
+use random_forest_rust::utils::utils::serialize_biguint_vec;
+use random_forest_rust::wire::wire::{decode_message, encode_message, pack_bits, read_message, unpack_bits, WireError, MAX_FRAME_LENGTH};
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+fn round_trip(payload: Payload) {
+    let message = RFMessage { message_id: "main:RF:0:DT".to_string(), payload };
+    let frame = encode_message(&message).unwrap();
+    assert_eq!(decode_message(&frame[4..]).unwrap(), message);
+    assert_eq!(read_message(&mut &frame[..]).unwrap(), Some(message));
+}
+
+#[test]
+fn every_payload_survives_a_round_trip() {
+    round_trip(Payload::Bits(vec![1, 0, 1, 1, 0, 0, 0, 1, 1]));
+    round_trip(Payload::Bits(vec![]));
+    round_trip(Payload::U64s(vec![0, 1, u64::MAX]));
+    round_trip(Payload::BigUints(vec![BigUint::from(0u8), BigUint::from_str("170141183460469231731687303715884105726").unwrap()]));
+    round_trip(Payload::Bytes(b"seed".to_vec()));
+}
+
+#[test]
+fn bits_are_packed_eight_to_a_byte() {
+    let bits = vec![1, 0, 0, 0, 0, 0, 0, 1, 1];
+    assert_eq!(pack_bits(&bits), vec![0b1000_0001, 0b1]);
+    assert_eq!(unpack_bits(&pack_bits(&bits), bits.len()), bits);
+}
+
+#[test]
+fn malformed_frames_are_rejected() {
+    let frame = encode_message(&RFMessage { message_id: "id".to_string(), payload: Payload::U64s(vec![7]) }).unwrap();
+    assert_eq!(decode_message(&frame[4..frame.len() - 1]), Err(WireError::Truncated));
+    let mut unknown = frame[4..].to_vec();
+    unknown[4] = 9;
+    assert_eq!(decode_message(&unknown), Err(WireError::UnknownTag(9)));
+    assert_eq!(read_message(&mut &frame[..frame.len() - 1]).unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(read_message(&mut &[][..]).unwrap(), None);
+
+    // a length prefix past the limit is refused before anything is allocated for it
+    let mut too_long = ((MAX_FRAME_LENGTH + 1) as u32).to_le_bytes().to_vec();
+    too_long.extend_from_slice(&frame[4..]);
+    let error = read_message(&mut &too_long[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("exceeds"), "{}", error);
+    // as is a count no frame of this length could hold
+    let mut count = frame[4..].to_vec();
+    count[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(decode_message(&count), Err(WireError::Truncated));
+}
+
+#[test]
+fn lengths_beyond_their_fields_are_refused() {
+    let long_id = RFMessage { message_id: "x".repeat(70000), payload: Payload::Bytes(vec![]) };
+    assert_eq!(encode_message(&long_id).unwrap_err().kind(), ErrorKind::InvalidInput);
+    let wide = RFMessage { message_id: "id".to_string(), payload: Payload::BigUints(vec![BigUint::from(1u8) << (8 * 65536)]) };
+    assert_eq!(encode_message(&wide).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+// The JSON message contents batch_multiplication_byte and batch_multiply_bigint used to send. Bits
+// shrink by more than an order of magnitude; big integers, which JSON already wrote as plain decimal
+// digits, only by about a factor of three.
+#[test]
+fn frames_are_smaller_than_json() {
+    let message_id = "main:RF:0:DT:node_0:batch_multiplication_byte".to_string();
+    let pairs: Vec<Vec<u8>> = (0..10000).map(|i| vec![(i % 2) as u8, (i / 3 % 2) as u8]).collect();
+    let json = serde_json::to_string(&pairs).unwrap().len() + message_id.len();
+    let frame = encode_message(&RFMessage { message_id: message_id.clone(), payload: Payload::Bits(pairs.concat()) }).unwrap().len();
+    assert!(frame * 20 <= json, "{} byte frame for {} bytes of JSON", frame, json);
+
+    let prime = BigUint::from_str("170141183460469231731687303715884105727").unwrap();
+    let values: Vec<BigUint> = (1..2001u64).map(|i| &prime / BigUint::from(i * 7919)).collect();
+    let json = serialize_biguint_vec(values.clone()).unwrap().len() + message_id.len();
+    let frame = encode_message(&RFMessage { message_id, payload: Payload::BigUints(values) }).unwrap().len();
+    assert!(frame * 3 <= json, "{} byte frame for {} bytes of JSON", frame, json);
+}